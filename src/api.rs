@@ -1,21 +1,44 @@
 use hyper::{Body, Request, Response};
+use std::collections::HashMap;
 
 pub mod _404;
+pub mod backup_db;
+pub mod get_boot_events;
+pub mod get_certificate_checks;
+pub mod get_cgroup_status;
+pub mod get_collector_stats;
 pub mod get_cpu_status;
+pub mod get_custom_metrics;
 pub mod get_desc;
 pub mod get_disk_status;
+pub mod get_endpoint_checks;
+pub mod get_hardware_changes;
+pub mod get_hardware_history;
 pub mod get_hardware_info;
+pub mod get_log_matches;
 pub mod get_mem_status;
 pub mod get_otp_qr;
+pub mod get_pressure_status;
+pub mod get_process_watch_events;
+pub mod get_systemd_unit_events;
+pub mod get_temperature_status;
 pub mod healthcheck;
 pub mod hello;
 pub mod login;
+pub mod prune_db;
 pub mod teapot;
 pub mod update_info;
 pub mod validate_token_test;
 
 use serde::{Deserialize, Serialize};
 
+use crate::monitor::models::get_status_rollups::{Aggregate, StatusQuery};
+
+// the most buckets a status endpoint returns, so a small step can't dump the raw tables
+const MAX_STATUS_POINTS: i64 = 10_000;
+// a year, in seconds, well below where the step overflows in milliseconds
+const MAX_STATUS_STEP: i64 = 365 * 24 * 60 * 60;
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ResponseBody {
@@ -44,3 +67,94 @@ fn authenticate(req: &Request<Body>) -> Result<String, Response<Body>> {
         }
     };
 }
+
+fn get_query_params(req: &Request<Body>) -> HashMap<String, String> {
+    let query_str = req.uri().query().unwrap_or_default();
+
+    query_str
+        .split("&")
+        .filter_map(|param| {
+            let (key, value) = param.split_once("=")?;
+
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn bad_request(msg: &str) -> Response<Body> {
+    Response::builder()
+        .status(hyper::StatusCode::BAD_REQUEST)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::to_string(&ResponseBody::Error(msg.to_string())).unwrap(),
+        ))
+        .unwrap()
+}
+
+// reads the `start_time` and `end_time` query params
+fn get_time_range(req: &Request<Body>) -> Result<(i64, i64), Response<Body>> {
+    let params = get_query_params(req);
+
+    let start_time = params.get("start_time").and_then(|v| v.parse::<i64>().ok());
+    let end_time = params.get("end_time").and_then(|v| v.parse::<i64>().ok());
+
+    match (start_time, end_time) {
+        (Some(start_time), Some(end_time)) => Ok((start_time, end_time)),
+        _ => Err(bad_request("Invalid start_time or end_time.")),
+    }
+}
+
+fn parse_query_param<T: std::str::FromStr>(
+    params: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<T>, Response<Body>> {
+    match params.get(key) {
+        Some(val) => match val.parse::<T>() {
+            Ok(val) => Ok(Some(val)),
+            Err(_) => Err(bad_request(&format!("Invalid {}.", key))),
+        },
+        None => Ok(None),
+    }
+}
+
+// reads the time range and the `step`, `max_points` and `aggregate` query
+// params of the status endpoints, and the id filter, e.g. `cpu_id`
+fn get_status_query(
+    req: &Request<Body>,
+    entity_param: &str,
+) -> Result<StatusQuery, Response<Body>> {
+    let (start_time, end_time) = get_time_range(req)?;
+    let params = get_query_params(req);
+
+    let step = parse_query_param::<i64>(&params, "step")?;
+    let max_points = parse_query_param::<i64>(&params, "max_points")?;
+
+    let aggregate = match params.get("aggregate") {
+        Some(val) => match Aggregate::from_name(val) {
+            Some(val) => Some(val),
+            None => return Err(bad_request("Invalid aggregate.")),
+        },
+        None => None,
+    };
+
+    if step.is_some_and(|step| step <= 0 || step > MAX_STATUS_STEP)
+        || max_points.is_some_and(|max_points| max_points <= 0 || max_points > MAX_STATUS_POINTS)
+    {
+        return Err(bad_request("Invalid step or max_points."));
+    }
+
+    if step
+        .is_some_and(|step| end_time.saturating_sub(start_time) / (step * 1000) > MAX_STATUS_POINTS)
+    {
+        return Err(bad_request("The step is too small for the range."));
+    }
+
+    Ok(StatusQuery {
+        start_time,
+        end_time,
+        step,
+        max_points,
+        aggregate,
+        entity_id: params.get(entity_param).cloned(),
+    })
+}
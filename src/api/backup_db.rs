@@ -0,0 +1,43 @@
+use hyper::{Body, Request, Response};
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, ResponseBody},
+    config::get_config,
+    persistence::backup,
+};
+
+// backs the database up right away, whether the scheduled backups are enabled or not
+pub async fn backup_db(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let report = match backup::backup_db(&get_config().backup).await {
+        Ok(val) => val,
+        Err(err) => {
+            let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+            let response = Response::builder()
+                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "application/json")
+                .body(Body::from(bod))
+                .unwrap();
+
+            return Ok(response);
+        }
+    };
+
+    let res_json = serde_json::to_string(&report).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
@@ -0,0 +1,73 @@
+use hyper::{Body, Request, Response};
+use log::debug;
+use serde_derive::Serialize;
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, get_query_params, get_time_range, ResponseBody},
+    monitor::{
+        models::get_certificate_checks::{CertificateCheck, GetCertificateChecksRequest},
+        persistence::get_certificate_checks_between_dates,
+    },
+};
+
+#[derive(Serialize)]
+struct GetCertificateChecksResponse {
+    checks: Vec<CertificateCheck>,
+}
+
+pub async fn get_certificate_checks(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let req = match get_time_range(&req) {
+        Ok((start_time, end_time)) => GetCertificateChecksRequest {
+            start_time,
+            end_time,
+            path: get_query_params(&req).get("path").cloned(),
+        },
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    debug!("start_time: {}", req.start_time);
+    debug!("end_time: {}", req.end_time);
+
+    let checks = match get_certificate_checks_between_dates(
+        req.start_time,
+        req.end_time,
+        req.path.as_deref(),
+    )
+    .await
+    {
+        Ok(val) => val,
+        Err(err) => {
+            let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+            let response = Response::builder()
+                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "application/json")
+                .body(Body::from(bod))
+                .unwrap();
+
+            return Ok(response);
+        }
+    };
+
+    let res_model = GetCertificateChecksResponse { checks };
+
+    let res_json = serde_json::to_string(&res_model).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
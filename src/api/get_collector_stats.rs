@@ -0,0 +1,39 @@
+use hyper::{Body, Request, Response};
+use serde_derive::Serialize;
+use std::convert::Infallible;
+
+use crate::{
+    api::authenticate,
+    monitor::{
+        self,
+        models::get_collector_stats::{CollectorStats, WriteStats},
+    },
+};
+
+#[derive(Serialize)]
+struct GetCollectorStatsResponse {
+    collectors: Vec<CollectorStats>,
+    writes: WriteStats,
+}
+
+pub async fn get_collector_stats(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let body = serde_json::to_string(&GetCollectorStatsResponse {
+        collectors: monitor::get_collector_stats(),
+        writes: monitor::get_write_stats(),
+    })
+    .unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    Ok(response)
+}
@@ -1,21 +1,12 @@
 use hyper::{Body, Request, Response};
 use log::debug;
-use serde_derive::Serialize;
 use std::convert::Infallible;
 
 use crate::{
-    api::{authenticate, ResponseBody},
-    monitor::{
-        models::{get_disk_status::DiskFrameStatus, get_mem_status::GetMemStatusRequest},
-        persistence::get_disk_status_between_dates,
-    },
+    api::{authenticate, get_status_query, ResponseBody},
+    monitor,
 };
 
-#[derive(Serialize)]
-struct GetDiskStatusResponse {
-    frames: Vec<DiskFrameStatus>,
-}
-
 pub async fn get_disk_status(req: Request<Body>) -> Result<Response<Body>, Infallible> {
     match authenticate(&req) {
         Ok(val) => val,
@@ -24,29 +15,17 @@ pub async fn get_disk_status(req: Request<Body>) -> Result<Response<Body>, Infal
         }
     };
 
-    // read query params and convert to GetDiskStatusRequest
-    let query_str = req.uri().query().unwrap();
-    let query_params: Vec<&str> = query_str.split("&").collect();
-    let start_time = query_params[0].split("=").collect::<Vec<&str>>()[1]
-        .parse::<i64>()
-        .unwrap();
-    let end_time = query_params[1].split("=").collect::<Vec<&str>>()[1]
-        .parse::<i64>()
-        .unwrap();
-    let req = GetMemStatusRequest {
-        start_time,
-        end_time,
+    let query = match get_status_query(&req, "disk_id") {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
     };
 
-    let start_time = req.start_time;
-    let end_time = req.end_time;
-
-    debug!("start_time: {}", start_time);
-    debug!("end_time: {}", end_time);
+    debug!("start_time: {}", query.start_time);
+    debug!("end_time: {}", query.end_time);
 
-    // TODO(isaidsari): read data frequency from config
-
-    let frames = match get_disk_status_between_dates(start_time, end_time).await {
+    let history = match monitor::get_disk_history(&query).await {
         Ok(val) => val,
         Err(err) => {
             let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
@@ -61,9 +40,7 @@ pub async fn get_disk_status(req: Request<Body>) -> Result<Response<Body>, Infal
         }
     };
 
-    let res_model = GetDiskStatusResponse { frames };
-
-    let res_json = serde_json::to_string(&res_model).unwrap();
+    let res_json = serde_json::to_string(&history).unwrap();
 
     let response = Response::builder()
         .status(hyper::StatusCode::OK)
@@ -0,0 +1,70 @@
+use hyper::{Body, Request, Response};
+use log::debug;
+use serde_derive::Serialize;
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, get_query_params, get_time_range, ResponseBody},
+    monitor::{
+        models::get_endpoint_checks::{EndpointCheck, GetEndpointChecksRequest},
+        persistence::get_endpoint_checks_between_dates,
+    },
+};
+
+#[derive(Serialize)]
+struct GetEndpointChecksResponse {
+    checks: Vec<EndpointCheck>,
+}
+
+pub async fn get_endpoint_checks(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let req = match get_time_range(&req) {
+        Ok((start_time, end_time)) => GetEndpointChecksRequest {
+            start_time,
+            end_time,
+            name: get_query_params(&req).get("name").cloned(),
+        },
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    debug!("start_time: {}", req.start_time);
+    debug!("end_time: {}", req.end_time);
+
+    let checks =
+        match get_endpoint_checks_between_dates(req.start_time, req.end_time, req.name.as_deref())
+            .await
+        {
+            Ok(val) => val,
+            Err(err) => {
+                let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+                let response = Response::builder()
+                    .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(bod))
+                    .unwrap();
+
+                return Ok(response);
+            }
+        };
+
+    let res_model = GetEndpointChecksResponse { checks };
+
+    let res_json = serde_json::to_string(&res_model).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
@@ -0,0 +1,78 @@
+use hyper::{Body, Request, Response};
+use log::debug;
+use serde_derive::Serialize;
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, get_time_range, ResponseBody},
+    monitor::{
+        models::{
+            get_hardware_changes::HardwareChangeEvent,
+            get_hardware_info::{GetHardwareHistoryRequest, HardwareInfo},
+        },
+        persistence::{get_hardware_change_events_between_dates, get_hardware_info_between_dates},
+    },
+};
+
+#[derive(Serialize)]
+struct GetHardwareHistoryResponse {
+    // every version of the hardware that was in use in the time range
+    versions: HardwareInfo,
+    changes: Vec<HardwareChangeEvent>,
+}
+
+async fn fetch_hardware_history(
+    req: &GetHardwareHistoryRequest,
+) -> Result<GetHardwareHistoryResponse, sqlx::Error> {
+    let versions = get_hardware_info_between_dates(req.start_time, req.end_time).await?;
+    let changes = get_hardware_change_events_between_dates(req.start_time, req.end_time).await?;
+
+    Ok(GetHardwareHistoryResponse { versions, changes })
+}
+
+pub async fn get_hardware_history(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let req = match get_time_range(&req) {
+        Ok((start_time, end_time)) => GetHardwareHistoryRequest {
+            start_time,
+            end_time,
+        },
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    debug!("start_time: {}", req.start_time);
+    debug!("end_time: {}", req.end_time);
+
+    let res_model = match fetch_hardware_history(&req).await {
+        Ok(val) => val,
+        Err(err) => {
+            let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+            let response = Response::builder()
+                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "application/json")
+                .body(Body::from(bod))
+                .unwrap();
+
+            return Ok(response);
+        }
+    };
+
+    let res_json = serde_json::to_string(&res_model).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
@@ -0,0 +1,71 @@
+use hyper::{Body, Request, Response};
+use log::debug;
+use serde_derive::Serialize;
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, get_query_params, get_time_range, ResponseBody},
+    monitor::{
+        models::get_log_matches::{GetLogMatchesRequest, LogMatch, LogMatchCount},
+        persistence::get_log_matches_between_dates,
+    },
+};
+
+#[derive(Serialize)]
+struct GetLogMatchesResponse {
+    matches: Vec<LogMatch>,
+    counts: Vec<LogMatchCount>,
+}
+
+pub async fn get_log_matches(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let req = match get_time_range(&req) {
+        Ok((start_time, end_time)) => GetLogMatchesRequest {
+            start_time,
+            end_time,
+            watch: get_query_params(&req).get("watch").cloned(),
+        },
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    debug!("start_time: {}", req.start_time);
+    debug!("end_time: {}", req.end_time);
+
+    let (matches, counts) =
+        match get_log_matches_between_dates(req.start_time, req.end_time, req.watch.as_deref())
+            .await
+        {
+            Ok(val) => val,
+            Err(err) => {
+                let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+                let response = Response::builder()
+                    .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(bod))
+                    .unwrap();
+
+                return Ok(response);
+            }
+        };
+
+    let res_model = GetLogMatchesResponse { matches, counts };
+
+    let res_json = serde_json::to_string(&res_model).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
@@ -0,0 +1,47 @@
+use hyper::{Body, Request, Response};
+use std::convert::Infallible;
+
+use crate::{
+    api::{authenticate, ResponseBody},
+    config::get_config,
+    persistence::retention,
+};
+
+// prunes the expired rows right away, instead of waiting for the next run
+pub async fn prune_db(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    match authenticate(&req) {
+        Ok(val) => val,
+        Err(err) => {
+            return Ok(err);
+        }
+    };
+
+    let report = match retention::prune_db(&get_config().retention).await {
+        Ok(val) => val,
+        Err(err) => {
+            let status = match err {
+                retention::PruneError::Disabled => hyper::StatusCode::CONFLICT,
+                retention::PruneError::Sql(_) => hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let bod = serde_json::to_string(&ResponseBody::Error(err.to_string())).unwrap();
+
+            let response = Response::builder()
+                .status(status)
+                .header("Content-Type", "application/json")
+                .body(Body::from(bod))
+                .unwrap();
+
+            return Ok(response);
+        }
+    };
+
+    let res_json = serde_json::to_string(&report).unwrap();
+
+    let response = Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(res_json))
+        .unwrap();
+
+    Ok(response)
+}
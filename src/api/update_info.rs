@@ -53,17 +53,88 @@ pub async fn update_info(req: Request<Body>) -> Result<Response<Body>, Infallibl
         }
     };
 
+    let stored_config = match persistence::fetch_monitor_config(&dev_id).await {
+        Ok(val) => val,
+        Err(err) => {
+            error!("{}", err);
+
+            let response = Response::builder()
+                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::to_string(&ResponseBody::Error(
+                        "Failed to update monitor config.".to_string(),
+                    ))
+                    .unwrap(),
+                ))
+                .unwrap();
+            return Ok(response);
+        }
+    };
+
+    // the thresholds that weren't sent keep their stored values
+    let inode_threshold = match update_info.inode_threshold {
+        Some(threshold) => threshold,
+        None => stored_config.as_ref().and_then(|c| c.inode_threshold),
+    };
+    let cpu_pressure_threshold = match update_info.cpu_pressure_threshold {
+        Some(threshold) => threshold,
+        None => stored_config
+            .as_ref()
+            .and_then(|c| c.cpu_pressure_threshold),
+    };
+    let mem_pressure_threshold = match update_info.mem_pressure_threshold {
+        Some(threshold) => threshold,
+        None => stored_config
+            .as_ref()
+            .and_then(|c| c.mem_pressure_threshold),
+    };
+    let io_pressure_threshold = match update_info.io_pressure_threshold {
+        Some(threshold) => threshold,
+        None => stored_config.as_ref().and_then(|c| c.io_pressure_threshold),
+    };
+    let cgroup_mem_threshold = match update_info.cgroup_mem_threshold {
+        Some(threshold) => threshold,
+        None => stored_config.as_ref().and_then(|c| c.cgroup_mem_threshold),
+    };
+
     let mon_config = MonitorConfig {
         id: -1,
         device_id: "".to_string(),
         cpu_threshold: update_info.cpu_threshold,
         disk_threshold: update_info.disk_threshold,
         mem_threshold: update_info.mem_threshold,
-        fcm_token: update_info.fcm_token,
+        inode_threshold,
+        cpu_pressure_threshold,
+        mem_pressure_threshold,
+        io_pressure_threshold,
+        cgroup_mem_threshold,
+        fcm_token: update_info.fcm_token.to_string(),
         updated_at: chrono::Utc::now().timestamp_millis(),
     };
 
-    match persistence::insert_or_update_monitor_config(&mon_config, &dev_id).await {
+    let update_res = match persistence::insert_or_update_monitor_config(&mon_config, &dev_id).await
+    {
+        Ok(_) => match &update_info.temperature_thresholds {
+            Some(thresholds) => {
+                persistence::replace_temperature_thresholds(&dev_id, thresholds).await
+            }
+            None => Ok(()),
+        },
+        Err(err) => Err(err),
+    };
+
+    let update_res = match update_res {
+        Ok(_) => match &update_info.custom_metric_thresholds {
+            Some(thresholds) => {
+                persistence::replace_custom_metric_thresholds(&dev_id, thresholds).await
+            }
+            None => Ok(()),
+        },
+        Err(err) => Err(err),
+    };
+
+    match update_res {
         Ok(_) => {
             let response = Response::builder()
                 .status(hyper::StatusCode::OK)
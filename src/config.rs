@@ -0,0 +1,260 @@
+use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::monitor::models::{
+    get_custom_metrics::CustomProbe, get_endpoint_checks::EndpointProbe, get_log_matches::LogWatch,
+    get_process_watch::WatchedProcess,
+};
+
+const CONFIG_PATH_ENV_VAR: &str = "REMON_CONFIG_PATH";
+const DEFAULT_CONFIG_PATH: &str = "./remon.json";
+
+// host level configuration, unlike MonitorConfig which is set per device
+// from the mobile app, this one is owned by whoever runs the server
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub sampling: SamplingConfig,
+    // processes that are expected to be running on the host
+    pub process_watchlist: Vec<WatchedProcess>,
+    pub cgroups: CgroupConfig,
+    // systemd units to alert on when they fail or restart, e.g. "nginx.service"
+    pub systemd_units: Vec<String>,
+    // user defined commands whose output is stored as custom metrics
+    pub custom_probes: Vec<CustomProbe>,
+    // http and tcp endpoints that are checked on their own schedule
+    pub endpoint_probes: Vec<EndpointProbe>,
+    // log files whose new lines are matched against regexes
+    pub log_watches: Vec<LogWatch>,
+    // pem certificate files whose expiry is checked
+    pub certificates: CertificateConfig,
+    pub reboot_detection: RebootDetectionConfig,
+    // how long the samples and the logs are kept in the database
+    pub retention: RetentionConfig,
+    pub database: DatabaseConfig,
+    pub backup: BackupConfig,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SamplingConfig {
+    // how often every collector runs, unless it's overridden in `collector_intervals`
+    pub interval_secs: u64,
+    // collector name -> its interval, e.g. {"disk": 60, "processes": 30}
+    pub collector_intervals: HashMap<String, u64>,
+    // how often the thresholds are checked against the latest samples
+    pub threshold_interval_secs: u64,
+    // collectors that don't run on this host, e.g. ["temperature"] on VMs
+    pub disabled_collectors: Vec<String>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            collector_intervals: HashMap::new(),
+            threshold_interval_secs: 10,
+            disabled_collectors: vec![],
+        }
+    }
+}
+
+impl SamplingConfig {
+    pub fn get_collector_interval(&self, name: &str) -> Duration {
+        let secs = self
+            .collector_intervals
+            .get(name)
+            .copied()
+            .unwrap_or(self.interval_secs);
+
+        Duration::from_secs(secs)
+    }
+
+    pub fn is_collector_enabled(&self, name: &str) -> bool {
+        !self.disabled_collectors.iter().any(|d| d == name)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CgroupConfig {
+    pub enabled: bool,
+    // the mount point of the cgroup v2 hierarchy
+    pub root: String,
+    // how deep to walk the hierarchy, e.g. "system.slice/docker-<id>.scope" is 2 levels deep
+    pub max_depth: usize,
+}
+
+impl Default for CgroupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            root: "/sys/fs/cgroup".to_string(),
+            max_depth: 2,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CertificateConfig {
+    pub paths: Vec<String>,
+    // how many days before the expiry to notify, each of them is notified once
+    pub lead_days: Vec<i64>,
+    pub interval_secs: u64,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        Self {
+            paths: vec![],
+            lead_days: vec![30, 7, 1],
+            interval_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RebootDetectionConfig {
+    // whether the enrolled devices are notified about the reboots
+    pub notify: bool,
+    // a shutdown is flagged as unclean when the last sample is further than this from the boot
+    pub unclean_gap_secs: i64,
+}
+
+impl Default for RebootDetectionConfig {
+    fn default() -> Self {
+        Self {
+            notify: true,
+            unclean_gap_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    // how often the expired rows are pruned
+    pub interval_secs: u64,
+    // the rows deleted per statement, so the writes in between aren't blocked for long
+    pub batch_size: i64,
+    // table name -> the days its rows are kept, overrides the defaults. 0 keeps them forever
+    pub days: HashMap<String, i64>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 3600,
+            batch_size: 1000,
+            days: HashMap::new(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    // None when the rows of the table are kept forever
+    pub fn get_retention_days(&self, table_name: &str, default_days: Option<i64>) -> Option<i64> {
+        self.days
+            .get(table_name)
+            .copied()
+            .or(default_days)
+            .filter(|days| *days > 0)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    // an absolute path, the default one depends on the user remon runs as
+    pub path: Option<String>,
+    // the connections shared by the api, the collectors and the background tasks
+    pub max_connections: u32,
+    // how long a connection waits for the write lock before failing
+    pub busy_timeout_ms: u64,
+    // normal is safe with the wal journal, only the last commits can be lost on a power cut
+    pub synchronous: Synchronous,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_connections: 4,
+            busy_timeout_ms: 5000,
+            synchronous: Synchronous::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackupConfig {
+    // whether the database is backed up on a schedule, the cli and the admin
+    // endpoint back it up either way
+    pub enabled: bool,
+    pub interval_secs: u64,
+    // the directory the backups are kept in, `backups` next to the database by default
+    pub path: Option<String>,
+    // the newest backups that are kept, the older ones are deleted after every backup
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 86400,
+            path: None,
+            keep: 7,
+        }
+    }
+}
+
+static SERVER_CONFIG: OnceLock<ServerConfig> = OnceLock::new();
+
+fn get_config_path() -> String {
+    std::env::var(CONFIG_PATH_ENV_VAR).unwrap_or(DEFAULT_CONFIG_PATH.to_string())
+}
+
+pub fn read_config_file(path: &str) -> Result<ServerConfig, String> {
+    if !std::path::Path::new(path).exists() {
+        info!("no config file found at {}, using the defaults", path);
+
+        return Ok(ServerConfig::default());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read config file {}: {}", path, e))?;
+
+    serde_json::from_str::<ServerConfig>(&content)
+        .map_err(|e| format!("failed to parse config file {}: {}", path, e))
+}
+
+pub fn init_config() -> Result<(), String> {
+    let config = read_config_file(&get_config_path())?;
+
+    // if it was already set, we keep the first one
+    let _ = SERVER_CONFIG.set(config);
+
+    Ok(())
+}
+
+pub fn get_config() -> &'static ServerConfig {
+    SERVER_CONFIG.get_or_init(ServerConfig::default)
+}
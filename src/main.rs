@@ -5,6 +5,7 @@ use std::convert::Infallible;
 use std::net::SocketAddr;
 
 mod api;
+mod config;
 mod notification_service;
 pub mod persistence;
 
@@ -65,9 +66,46 @@ async fn req_handler(req: Request<Body>) -> Result<Response<Body>, Infallible> {
         (&Method::GET, "/get-hardware-info") => {
             api::get_hardware_info::get_hardware_info(req).await
         }
+        (&Method::GET, "/get-hardware-changes") => {
+            api::get_hardware_changes::get_hardware_changes(req).await
+        }
+        (&Method::GET, "/get-boot-events") => api::get_boot_events::get_boot_events(req).await,
+        (&Method::GET, "/hardware-history") => {
+            api::get_hardware_history::get_hardware_history(req).await
+        }
         (&Method::GET, "/get-cpu-status") => api::get_cpu_status::get_cpu_status(req).await,
         (&Method::GET, "/get-mem-status") => api::get_mem_status::get_mem_status(req).await,
         (&Method::GET, "/get-disk-status") => api::get_disk_status::get_disk_status(req).await,
+        (&Method::GET, "/get-log-matches") => api::get_log_matches::get_log_matches(req).await,
+        (&Method::GET, "/get-certificate-checks") => {
+            api::get_certificate_checks::get_certificate_checks(req).await
+        }
+        (&Method::GET, "/get-endpoint-checks") => {
+            api::get_endpoint_checks::get_endpoint_checks(req).await
+        }
+        (&Method::GET, "/get-custom-metrics") => {
+            api::get_custom_metrics::get_custom_metrics(req).await
+        }
+        (&Method::GET, "/get-cgroup-status") => {
+            api::get_cgroup_status::get_cgroup_status(req).await
+        }
+        (&Method::GET, "/get-pressure-status") => {
+            api::get_pressure_status::get_pressure_status(req).await
+        }
+        (&Method::GET, "/get-temperature-status") => {
+            api::get_temperature_status::get_temperature_status(req).await
+        }
+        (&Method::GET, "/get-process-watch-events") => {
+            api::get_process_watch_events::get_process_watch_events(req).await
+        }
+        (&Method::GET, "/get-systemd-unit-events") => {
+            api::get_systemd_unit_events::get_systemd_unit_events(req).await
+        }
+        (&Method::GET, "/get-collector-stats") => {
+            api::get_collector_stats::get_collector_stats(req).await
+        }
+        (&Method::POST, "/prune-db") => api::prune_db::prune_db(req).await,
+        (&Method::POST, "/backup-db") => api::backup_db::backup_db(req).await,
         (&Method::GET, "/validate-token-test") => {
             api::validate_token_test::validate_token_test(req).await
         }
@@ -98,6 +136,83 @@ async fn shutdown_signal() {
         .expect("failed to install CTRL+C signal handler");
 }
 
+async fn prune_command() {
+    let report = match persistence::retention::prune_db(&config::get_config().retention).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("failed to prune the expired rows: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    for table in &report.tables {
+        println!(
+            "{}: deleted {} rows older than {} days",
+            table.table_name, table.deleted_rows, table.retention_days
+        );
+    }
+    println!(
+        "reclaimed {} bytes, the database is now {} bytes",
+        report.reclaimed_bytes, report.size_after
+    );
+}
+
+async fn vacuum_command() {
+    let report = match persistence::retention::vacuum_db().await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("failed to vacuum the database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "vacuumed the database in {} ms, from {} bytes to {} bytes",
+        report.duration_ms, report.size_before, report.size_after
+    );
+}
+
+async fn backup_command() {
+    let report = match persistence::backup::backup_db(&config::get_config().backup).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("failed to back up the database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "backed up the database to {}, {} bytes",
+        report.path, report.size
+    );
+    for path in &report.removed {
+        println!("deleted the old backup {}", path);
+    }
+}
+
+async fn restore_command(backup_path: Option<String>) {
+    let Some(backup_path) = backup_path else {
+        error!("usage: remon-server restore <backup file>");
+        std::process::exit(1);
+    };
+
+    let report = match persistence::backup::restore_db(std::path::Path::new(&backup_path)).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("failed to restore {}: {}", backup_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "restored {} to {}, at schema version {}",
+        backup_path, report.path, report.schema_version
+    );
+    if let Some(previous_path) = report.previous_path {
+        println!("the replaced database was moved to {}", previous_path);
+    }
+}
+
 // https://stackoverflow.com/a/63442117/12555423
 #[cfg(test)]
 #[ctor::ctor]
@@ -107,20 +222,53 @@ fn init_tests() {
 
 #[tokio::main]
 async fn main() {
+    dotenv::dotenv().ok();
+
     init_logger(false);
 
-    let socket_addr = match get_socket_addr() {
-        Some(addr) => addr,
-        None => {
-            error!("Failed to get local IP address.");
-            return;
-        }
-    };
+    if let Err(e) = config::init_config() {
+        error!("{}", e);
+        return;
+    }
+
+    // `remon-server restore <file>` swaps the database for a backup, before it's opened.
+    // the server has to be stopped, it's refused while the database is open
+    if std::env::args().nth(1).as_deref() == Some("restore") {
+        restore_command(std::env::args().nth(2)).await;
+        return;
+    }
 
     match crate::persistence::init_db().await {
         Ok(val) => val,
         Err(e) => {
-            error!("Database initialization failed: {:?}", e);
+            error!("Database initialization failed: {}", e);
+            return;
+        }
+    };
+
+    // `remon-server prune` prunes the expired rows once, without starting the server
+    if std::env::args().nth(1).as_deref() == Some("prune") {
+        prune_command().await;
+        return;
+    }
+
+    // `remon-server vacuum` compacts the database and switches it to incremental vacuum,
+    // it rewrites the whole file, so it's best run while the server is stopped
+    if std::env::args().nth(1).as_deref() == Some("vacuum") {
+        vacuum_command().await;
+        return;
+    }
+
+    // `remon-server backup` takes a backup once, without starting the server
+    if std::env::args().nth(1).as_deref() == Some("backup") {
+        backup_command().await;
+        return;
+    }
+
+    let socket_addr = match get_socket_addr() {
+        Some(addr) => addr,
+        None => {
+            error!("Failed to get local IP address.");
             return;
         }
     };
@@ -1,16 +1,61 @@
+use self::clock::{SharedClock, SystemClock};
 use self::models::ServerDescription;
+use crate::config::get_config;
+use crate::persistence::get_default_sql_connection;
 
-use log::debug;
+use log::{debug, error};
+use std::sync::Arc;
 use sysinfo::{CpuRefreshKind, RefreshKind, System};
 
+mod backups;
+mod certificates;
+mod cgroups;
+mod clock;
+mod collectors;
 mod config_exceeds;
+mod custom_probes;
+mod diskstats;
+mod endpoint_probes;
+mod frame_writer;
+mod hardware_changes;
+mod inode_usage;
+mod log_watch;
 pub mod models;
+mod notifier;
 pub mod persistence;
+mod pressure;
+mod process_watch;
+mod reboot_detection;
+mod retention;
+mod rollups;
+mod scheduler;
 pub mod system_monitor;
+mod system_source;
+mod systemd_units;
+
+pub use frame_writer::get_write_stats;
+pub use rollups::{get_cpu_history, get_disk_history, get_mem_history};
+pub use scheduler::get_collector_stats;
 
 pub async fn init() -> Result<(), ()> {
-    let monitor = system_monitor::SystemMonitor::new();
-    monitor.start_monitoring().await;
+    let clock: SharedClock = Arc::new(SystemClock);
+    let collectors = collectors::CollectorRegistry::from_config(get_config(), &clock);
+
+    let pool = get_default_sql_connection().await.map_err(|e| {
+        error!("failed to get db connection: {}", e);
+    })?;
+    // released before the collectors start, so they don't wait on it when the pool is small
+    {
+        let mut conn = pool.acquire().await.map_err(|e| {
+            error!("failed to get db connection: {}", e);
+        })?;
+        collectors.register_schemas(&mut conn).await.map_err(|e| {
+            error!("failed to create the tables of the collectors: {}", e);
+        })?;
+    }
+
+    let monitor = system_monitor::SystemMonitor::new(clock);
+    monitor.start_monitoring(collectors).await;
     debug!("System monitor started");
 
     // TODO(isaidsari): Check sysinfo library has support for current platform
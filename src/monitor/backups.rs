@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use tokio_util::sync::CancellationToken;
+
+use super::scheduler::Schedule;
+use crate::config::BackupConfig;
+use crate::persistence::backup::backup_db;
+
+pub(super) fn spawn_backups(config: &BackupConfig, token: &CancellationToken) {
+    if !config.enabled {
+        return;
+    }
+
+    let config = config.clone();
+    let mut schedule = Schedule::new("backup", Duration::from_secs(config.interval_secs), token);
+
+    tokio::spawn(async move {
+        // the first tick completes right away, the first backup is taken an interval
+        // after the start, so a server that keeps restarting doesn't rotate out the backups
+        if !schedule.tick().await {
+            return;
+        }
+
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            match backup_db(&config).await {
+                Ok(report) => info!(
+                    "backed up the database to {}, {} bytes",
+                    report.path, report.size
+                ),
+                Err(e) => error!("failed to back up the database: {}", e),
+            }
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+use tokio_util::sync::CancellationToken;
+use x509_parser::pem::parse_x509_pem;
+
+use super::clock::SharedClock;
+use super::models::get_certificate_checks::{CertificateCheck, CertificateStatus};
+use super::notifier::{send_to_all_devices, FcmNotifier, Notifier};
+use super::persistence::insert_certificate_check;
+use super::scheduler::Schedule;
+use crate::config::CertificateConfig;
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+const MILLIS_PER_DAY: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+// the subject, the issuer and the not_after in milliseconds
+// of the first certificate in the pem content
+fn parse_certificate(content: &[u8]) -> Result<(String, String, i64), String> {
+    let (_, pem) = parse_x509_pem(content).map_err(|e| format!("invalid pem: {}", e))?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| format!("invalid certificate: {}", e))?;
+
+    Ok((
+        cert.subject().to_string(),
+        cert.issuer().to_string(),
+        cert.validity().not_after.timestamp() * 1000,
+    ))
+}
+
+pub fn check_certificate_file(path: &str, now: i64) -> CertificateCheck {
+    let mut check = CertificateCheck {
+        id: -1,
+        path: path.to_string(),
+        status: CertificateStatus::Ok,
+        subject: None,
+        issuer: None,
+        not_after: None,
+        days_to_expiry: None,
+        message: None,
+        last_check: now,
+    };
+
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(e) => {
+            check.status = if e.kind() == ErrorKind::NotFound {
+                CertificateStatus::Missing
+            } else {
+                CertificateStatus::Unreadable
+            };
+            check.message = Some(e.to_string());
+
+            return check;
+        }
+    };
+
+    match parse_certificate(&content) {
+        Ok((subject, issuer, not_after)) => {
+            check.subject = Some(subject);
+            check.issuer = Some(issuer);
+            check.not_after = Some(not_after);
+            check.days_to_expiry = Some((not_after - now) as f64 / MILLIS_PER_DAY);
+        }
+        Err(e) => {
+            check.status = CertificateStatus::Invalid;
+            check.message = Some(e);
+        }
+    }
+
+    check
+}
+
+// the smallest lead time the certificate is already within, 0 once it's expired.
+// e.g. with lead days of 30, 7 and 1, a certificate that expires in 5 days is at 7
+fn get_alert_stage(days_to_expiry: f64, lead_days: &[i64]) -> Option<i64> {
+    if days_to_expiry <= 0.0 {
+        return Some(0);
+    }
+
+    lead_days
+        .iter()
+        .filter(|&&lead| days_to_expiry <= lead as f64)
+        .min()
+        .copied()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum CertificateAlert {
+    // the certificate is within a new lead time, or expired
+    Expiring(i64),
+    // the file couldn't be checked
+    FileProblem,
+}
+
+// what was last notified about a file, so every alert is only sent once
+#[derive(Debug, Default)]
+struct CertificateAlertState {
+    stage: Option<i64>,
+    has_file_problem: bool,
+}
+
+impl CertificateAlertState {
+    fn register(
+        &mut self,
+        check: &CertificateCheck,
+        lead_days: &[i64],
+    ) -> Option<CertificateAlert> {
+        if check.status != CertificateStatus::Ok {
+            let is_new = !self.has_file_problem;
+            self.has_file_problem = true;
+
+            return if is_new {
+                Some(CertificateAlert::FileProblem)
+            } else {
+                None
+            };
+        }
+        self.has_file_problem = false;
+
+        let stage = check
+            .days_to_expiry
+            .and_then(|days| get_alert_stage(days, lead_days));
+
+        let previous_stage = self.stage;
+        // a renewed certificate goes back to None
+        self.stage = stage;
+
+        match (previous_stage, stage) {
+            (_, None) => None,
+            (Some(previous), Some(stage)) if stage >= previous => None,
+            (_, Some(stage)) => Some(CertificateAlert::Expiring(stage)),
+        }
+    }
+}
+
+fn get_notification_message(
+    check: &CertificateCheck,
+    alert: &CertificateAlert,
+) -> NotificationMessage {
+    match alert {
+        CertificateAlert::Expiring(0) => NotificationMessage {
+            title: format!("IMPORTANT: the certificate {} expired", check.path),
+            body: format!(
+                "the certificate of {} expired",
+                check.subject.as_deref().unwrap_or_default()
+            ),
+        },
+        CertificateAlert::Expiring(_) => NotificationMessage {
+            title: format!("IMPORTANT: the certificate {} is expiring", check.path),
+            body: format!(
+                "the certificate of {} expires in {:.0} days",
+                check.subject.as_deref().unwrap_or_default(),
+                check.days_to_expiry.unwrap_or_default().floor()
+            ),
+        },
+        CertificateAlert::FileProblem => NotificationMessage {
+            title: format!("IMPORTANT: the certificate {} can't be checked", check.path),
+            body: format!(
+                "the certificate file is {:?}: {}",
+                check.status,
+                check.message.as_deref().unwrap_or_default()
+            )
+            .to_lowercase(),
+        },
+    }
+}
+
+async fn check_certificates(
+    config: &CertificateConfig,
+    states: &mut HashMap<String, CertificateAlertState>,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let now = clock.now_millis();
+
+    for path in &config.paths {
+        let check = check_certificate_file(path, now);
+
+        if let Err(e) = insert_certificate_check(&check).await {
+            error!("failed to insert certificate check: {}", e);
+        }
+
+        let alert = states
+            .entry(path.to_string())
+            .or_default()
+            .register(&check, &config.lead_days);
+
+        let alert = match alert {
+            Some(alert) => alert,
+            None => continue,
+        };
+
+        warn!("certificate alert for {}: {:?}", path, alert);
+
+        let notification_type = match alert {
+            CertificateAlert::Expiring(_) => NotificationType::CertificateExpiring,
+            CertificateAlert::FileProblem => NotificationType::CertificateFileProblem,
+        };
+
+        let not_res = send_to_all_devices(
+            notifier,
+            &get_notification_message(&check, &alert),
+            &notification_type,
+        )
+        .await;
+
+        if let Err(not_res) = not_res {
+            error!(
+                "Sending certificate notification resulted with the following error: {}",
+                not_res
+            );
+        }
+    }
+}
+
+pub(super) fn spawn_certificate_checks(
+    config: &CertificateConfig,
+    token: &CancellationToken,
+    clock: &SharedClock,
+) {
+    if config.paths.is_empty() {
+        return;
+    }
+
+    let config = config.clone();
+    let mut schedule = Schedule::new(
+        "certificates",
+        Duration::from_secs(config.interval_secs),
+        token,
+    );
+    let clock = Arc::clone(clock);
+
+    tokio::spawn(async move {
+        // kept in memory, so after a restart the current stage is notified once more
+        let mut states: HashMap<String, CertificateAlertState> = HashMap::new();
+
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            check_certificates(&config, &mut states, &FcmNotifier, &clock).await;
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_NOT_AFTER: i64 = 2107739529000;
+
+    fn fixture_path(name: &str) -> String {
+        format!(
+            "{}/tests/fixtures/certs/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        )
+    }
+
+    #[test]
+    fn check_certificate_file_test() {
+        let now = EXAMPLE_NOT_AFTER - 10 * MILLIS_PER_DAY as i64;
+        let check = check_certificate_file(&fixture_path("example.pem"), now);
+
+        assert_eq!(check.status, CertificateStatus::Ok);
+        assert_eq!(
+            check.subject.as_deref(),
+            Some("O=Remon Test, CN=example.internal")
+        );
+        assert_eq!(check.issuer, check.subject);
+        assert_eq!(check.not_after, Some(EXAMPLE_NOT_AFTER));
+        assert_eq!(check.days_to_expiry, Some(10.0));
+
+        let missing = check_certificate_file(&fixture_path("missing.pem"), now);
+        assert_eq!(missing.status, CertificateStatus::Missing);
+        assert_eq!(missing.days_to_expiry, None);
+
+        let invalid = check_certificate_file(&fixture_path("invalid.pem"), now);
+        assert_eq!(invalid.status, CertificateStatus::Invalid);
+    }
+
+    #[test]
+    fn get_alert_stage_test() {
+        let lead_days = vec![30, 7, 1];
+
+        assert_eq!(get_alert_stage(45.0, &lead_days), None);
+        assert_eq!(get_alert_stage(30.0, &lead_days), Some(30));
+        assert_eq!(get_alert_stage(5.5, &lead_days), Some(7));
+        assert_eq!(get_alert_stage(0.5, &lead_days), Some(1));
+        assert_eq!(get_alert_stage(-2.0, &lead_days), Some(0));
+    }
+
+    #[test]
+    fn certificate_alert_state_test() {
+        let lead_days = vec![30, 7, 1];
+        let check = |status: CertificateStatus, days_to_expiry: f64| CertificateCheck {
+            id: -1,
+            path: "/etc/ssl/example.pem".to_string(),
+            status,
+            subject: None,
+            issuer: None,
+            not_after: None,
+            days_to_expiry: Some(days_to_expiry),
+            message: None,
+            last_check: -1,
+        };
+
+        let mut state = CertificateAlertState::default();
+        let mut register = |status, days| state.register(&check(status, days), &lead_days);
+
+        assert_eq!(register(CertificateStatus::Ok, 40.0), None);
+        assert_eq!(
+            register(CertificateStatus::Ok, 29.0),
+            Some(CertificateAlert::Expiring(30))
+        );
+        assert_eq!(register(CertificateStatus::Ok, 20.0), None);
+        assert_eq!(
+            register(CertificateStatus::Ok, 6.0),
+            Some(CertificateAlert::Expiring(7))
+        );
+        assert_eq!(
+            register(CertificateStatus::Missing, 0.0),
+            Some(CertificateAlert::FileProblem)
+        );
+        assert_eq!(register(CertificateStatus::Unreadable, 0.0), None);
+        // the file is back, and the stage it was at is not notified again
+        assert_eq!(register(CertificateStatus::Ok, 5.0), None);
+        // renewed
+        assert_eq!(register(CertificateStatus::Ok, 90.0), None);
+        assert_eq!(
+            register(CertificateStatus::Ok, -1.0),
+            Some(CertificateAlert::Expiring(0))
+        );
+    }
+}
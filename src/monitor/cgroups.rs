@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use super::models::get_cgroup_status::SingleCgroupInfo;
+
+// the raw, mostly cumulative, values of a single cgroup
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgroupCounters {
+    pub cpu_usage_usec: Option<u64>,
+    pub memory_current: Option<i64>,
+    pub memory_max: Option<i64>,
+    pub io_read_bytes: Option<u64>,
+    pub io_write_bytes: Option<u64>,
+    pub pids_current: Option<i64>,
+    pub pids_max: Option<i64>,
+}
+
+// cgroup path, e.g. "/system.slice" -> its counters
+pub type CgroupCountersMap = HashMap<String, CgroupCounters>;
+
+fn read_file(dir: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(dir.join(name)).ok()
+}
+
+// the limit files contain "max" when there's no limit
+fn parse_limit(content: &str) -> Option<i64> {
+    content.trim().parse::<i64>().ok()
+}
+
+// cpu.stat looks like:
+// usage_usec 2336245
+// user_usec 1604562
+// ...
+fn parse_cpu_usage_usec(content: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (key, val) = line.split_once(' ')?;
+
+        if key != "usage_usec" {
+            return None;
+        }
+
+        val.trim().parse::<u64>().ok()
+    })
+}
+
+// io.stat has a line per device, the bytes are summed up:
+// 8:0 rbytes=1459200 wbytes=314773504 rios=192 wios=353 dbytes=0 dios=0
+fn parse_io_bytes(content: &str) -> (u64, u64) {
+    let mut read_bytes = 0;
+    let mut write_bytes = 0;
+
+    for field in content.split_whitespace() {
+        match field.split_once('=') {
+            Some(("rbytes", val)) => read_bytes += val.parse::<u64>().unwrap_or(0),
+            Some(("wbytes", val)) => write_bytes += val.parse::<u64>().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    (read_bytes, write_bytes)
+}
+
+fn read_cgroup_counters(dir: &Path) -> CgroupCounters {
+    let io_bytes = read_file(dir, "io.stat").map(|c| parse_io_bytes(&c));
+
+    CgroupCounters {
+        cpu_usage_usec: read_file(dir, "cpu.stat").and_then(|c| parse_cpu_usage_usec(&c)),
+        memory_current: read_file(dir, "memory.current").and_then(|c| parse_limit(&c)),
+        memory_max: read_file(dir, "memory.max").and_then(|c| parse_limit(&c)),
+        io_read_bytes: io_bytes.map(|b| b.0),
+        io_write_bytes: io_bytes.map(|b| b.1),
+        pids_current: read_file(dir, "pids.current").and_then(|c| parse_limit(&c)),
+        pids_max: read_file(dir, "pids.max").and_then(|c| parse_limit(&c)),
+    }
+}
+
+fn walk_cgroups(
+    dir: &Path,
+    path: &str,
+    depth: usize,
+    max_depth: usize,
+    res: &mut CgroupCountersMap,
+) {
+    res.insert(path.to_string(), read_cgroup_counters(dir));
+
+    if depth >= max_depth {
+        return;
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if !is_dir {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let child_path = if path == "/" {
+            format!("/{}", name)
+        } else {
+            format!("{}/{}", path, name)
+        };
+
+        walk_cgroups(&entry.path(), &child_path, depth + 1, max_depth, res);
+    }
+}
+
+// returns None where there's no cgroup v2 hierarchy mounted at the root,
+// like on hosts still on cgroup v1, windows and macos
+pub fn read_cgroups(root: &Path, max_depth: usize) -> Option<CgroupCountersMap> {
+    // only the unified, v2, hierarchy has this file
+    if !root.join("cgroup.controllers").exists() {
+        return None;
+    }
+
+    let mut res = HashMap::new() as CgroupCountersMap;
+    walk_cgroups(root, "/", 0, max_depth, &mut res);
+
+    Some(res)
+}
+
+fn per_second(previous: Option<u64>, current: Option<u64>, elapsed: &Duration) -> Option<f64> {
+    // the counters go back when the cgroup is recreated with the same path
+    let delta = current?.checked_sub(previous?)?;
+
+    Some(delta as f64 / elapsed.as_secs_f64())
+}
+
+// the rates are computed from the previous sample, and are None for
+// the cgroups that weren't there before
+pub fn compute_cgroups(
+    previous: &CgroupCountersMap,
+    current: &CgroupCountersMap,
+    elapsed: &Duration,
+) -> Vec<SingleCgroupInfo> {
+    let mut res: Vec<SingleCgroupInfo> = current
+        .iter()
+        .map(|(path, cur)| {
+            let prev = previous.get(path);
+            let rate = |f: fn(&CgroupCounters) -> Option<u64>| {
+                if elapsed.is_zero() {
+                    return None;
+                }
+
+                per_second(prev.and_then(f), f(cur), elapsed)
+            };
+
+            SingleCgroupInfo {
+                id: -1,
+                frame_id: -1,
+                path: path.to_string(),
+                // usec per second is the fraction of a core, times 100
+                cpu_usage_percent: rate(|c| c.cpu_usage_usec).map(|r| r / 10_000.0),
+                memory_current: cur.memory_current,
+                memory_max: cur.memory_max,
+                io_read_bytes_per_sec: rate(|c| c.io_read_bytes),
+                io_write_bytes_per_sec: rate(|c| c.io_write_bytes),
+                pids_current: cur.pids_current,
+                pids_max: cur.pids_max,
+            }
+        })
+        .collect();
+
+    res.sort_by(|a, b| a.path.cmp(&b.path));
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_root(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/cgroupfs")
+            .join(name)
+    }
+
+    #[test]
+    fn read_cgroups_test() {
+        let cgroups = read_cgroups(&fixture_root("t0"), 2).unwrap();
+
+        let mut paths = cgroups.keys().map(|p| p.as_str()).collect::<Vec<&str>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "/",
+                "/system.slice",
+                "/system.slice/docker-abc.scope",
+                "/user.slice"
+            ]
+        );
+
+        assert_eq!(
+            cgroups.get("/system.slice/docker-abc.scope"),
+            Some(&CgroupCounters {
+                cpu_usage_usec: Some(5000000),
+                memory_current: Some(429391872),
+                memory_max: Some(536870912),
+                io_read_bytes: Some(3000),
+                io_write_bytes: Some(10000),
+                pids_current: Some(12),
+                pids_max: Some(100),
+            })
+        );
+
+        // "max" means no limit, and the root has no memory or pids files
+        assert_eq!(cgroups.get("/user.slice").unwrap().memory_max, None);
+        assert_eq!(cgroups.get("/").unwrap().memory_current, None);
+
+        // the nested cgroup is deeper than the max depth
+        assert!(!read_cgroups(&fixture_root("t0"), 1)
+            .unwrap()
+            .contains_key("/system.slice/docker-abc.scope"));
+
+        // not a cgroup v2 hierarchy
+        assert_eq!(read_cgroups(&fixture_root("missing"), 2), None);
+    }
+
+    #[test]
+    fn compute_cgroups_test() {
+        let previous = read_cgroups(&fixture_root("t0"), 2).unwrap();
+        let current = read_cgroups(&fixture_root("t1"), 2).unwrap();
+
+        let cgroups = compute_cgroups(&previous, &current, &Duration::from_secs(10));
+
+        let docker = cgroups
+            .iter()
+            .find(|c| c.path == "/system.slice/docker-abc.scope")
+            .unwrap();
+        // 5 seconds of cpu time in 10 seconds is half a core
+        assert_eq!(docker.cpu_usage_percent, Some(50.0));
+        assert_eq!(docker.io_read_bytes_per_sec, Some(100.0));
+        assert_eq!(docker.io_write_bytes_per_sec, Some(1000.0));
+        assert_eq!(docker.memory_current, Some(510656512));
+
+        // the new cgroup has nothing to compute the rates from yet
+        let new = cgroups
+            .iter()
+            .find(|c| c.path == "/system.slice/new.service")
+            .unwrap();
+        assert_eq!(new.cpu_usage_percent, None);
+        assert_eq!(new.pids_current, Some(1));
+    }
+}
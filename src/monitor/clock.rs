@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+// the time the samples are taken at, faked in the tests to simulate
+// long runs without waiting
+pub(super) trait Clock: Send + Sync {
+    fn now_millis(&self) -> i64;
+}
+
+pub(super) type SharedClock = Arc<dyn Clock>;
+
+pub(super) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        Utc::now().timestamp_millis()
+    }
+}
+
+#[cfg(test)]
+pub(super) use self::fake::FakeClock;
+
+#[cfg(test)]
+mod fake {
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::time::Duration;
+
+    use super::Clock;
+
+    // only moves when it's advanced
+    pub struct FakeClock {
+        now: AtomicI64,
+    }
+
+    impl FakeClock {
+        pub fn new(start_millis: i64) -> Self {
+            Self {
+                now: AtomicI64::new(start_millis),
+            }
+        }
+
+        pub fn advance(&self, duration: Duration) {
+            self.now
+                .fetch_add(duration.as_millis() as i64, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now_millis(&self) -> i64 {
+            self.now.load(Ordering::SeqCst)
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use sqlx::SqliteConnection;
+use tokio_util::sync::CancellationToken;
+
+use super::clock::SharedClock;
+use super::frame_writer::FrameSender;
+use super::persistence::StatusFrame;
+use super::scheduler::Schedule;
+use super::system_monitor::{LatestSamples, SharedSamples};
+use super::system_source::SysinfoSource;
+use crate::config::{get_config, SamplingConfig, ServerConfig};
+
+mod cgroups;
+mod cpu;
+mod disk;
+mod mem;
+mod pressure;
+#[cfg(test)]
+mod simulation_tests;
+mod temperature;
+
+use self::cgroups::CgroupCollector;
+use self::cpu::CpuCollector;
+use self::disk::DiskCollector;
+use self::mem::MemCollector;
+use self::pressure::PressureCollector;
+use self::temperature::TemperatureCollector;
+
+// a source of samples that runs on its own interval. to add one, implement
+// this and register it in `CollectorRegistry::from_config`
+pub(super) trait Collector: Send + 'static {
+    type Sample: Send + 'static;
+
+    // also the key of its interval in `sampling.collector_intervals`, and the
+    // name it's disabled with in `sampling.disabled_collectors`
+    fn name(&self) -> &'static str;
+
+    fn interval(&self) -> Duration {
+        get_config().sampling.get_collector_interval(self.name())
+    }
+
+    // runs once on startup after the migrations. the tables of the collectors
+    // here are created by the migrations, only a collector whose tables aren't
+    // in a migration creates them here, with `CREATE ... IF NOT EXISTS`
+    fn register_schema(
+        &self,
+        _conn: &mut SqliteConnection,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    fn collect(&mut self) -> Self::Sample;
+
+    // the frame the sample is stored as, written with the frames of the other
+    // collectors that ran on the same tick. None when there's nothing to store,
+    // e.g. on a host without sensors
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame>;
+
+    // shares the sample with the threshold checks and the hardware inventory
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples);
+}
+
+type SchemaFuture<'a> = Pin<Box<dyn Future<Output = Result<(), sqlx::Error>> + Send + 'a>>;
+
+// the object safe part of `Collector`, so collectors with different
+// sample types can be kept in the same registry
+trait RegisteredCollector: Send {
+    fn name(&self) -> &'static str;
+
+    fn register_schema<'a>(&'a self, conn: &'a mut SqliteConnection) -> SchemaFuture<'a>;
+
+    fn spawn(
+        self: Box<Self>,
+        token: &CancellationToken,
+        frames: &FrameSender,
+        samples: &SharedSamples,
+    );
+}
+
+impl<C: Collector> RegisteredCollector for C {
+    fn name(&self) -> &'static str {
+        Collector::name(self)
+    }
+
+    fn register_schema<'a>(&'a self, conn: &'a mut SqliteConnection) -> SchemaFuture<'a> {
+        Box::pin(Collector::register_schema(self, conn))
+    }
+
+    fn spawn(
+        self: Box<Self>,
+        token: &CancellationToken,
+        frames: &FrameSender,
+        samples: &SharedSamples,
+    ) {
+        spawn_collector(*self, token, frames, samples);
+    }
+}
+
+fn spawn_collector<C: Collector>(
+    mut collector: C,
+    token: &CancellationToken,
+    frames: &FrameSender,
+    samples: &SharedSamples,
+) {
+    let mut schedule = Schedule::new(collector.name(), collector.interval(), token);
+    let frames = frames.clone();
+    let samples = Arc::clone(samples);
+
+    tokio::spawn(async move {
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            let sample = collector.collect();
+
+            if let Some(frame) = collector.get_frame(&sample) {
+                if frames.send(frame).await.is_err() {
+                    error!(
+                        "the {} status was not stored, the writer stopped",
+                        collector.name()
+                    );
+                }
+            }
+
+            collector.update_latest(sample, &mut samples.lock().unwrap());
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
+pub(super) struct CollectorRegistry {
+    collectors: Vec<Box<dyn RegisteredCollector>>,
+    sampling: SamplingConfig,
+}
+
+impl CollectorRegistry {
+    pub fn new(sampling: SamplingConfig) -> Self {
+        Self {
+            collectors: vec![],
+            sampling,
+        }
+    }
+
+    pub fn from_config(config: &ServerConfig, clock: &SharedClock) -> Self {
+        let mut registry = Self::new(config.sampling.clone());
+
+        registry.register(CpuCollector::new(
+            Box::new(SysinfoSource::new()),
+            Arc::clone(clock),
+        ));
+        registry.register(MemCollector::new(
+            Box::new(SysinfoSource::new()),
+            Arc::clone(clock),
+        ));
+        registry.register(DiskCollector::new(
+            Box::new(SysinfoSource::new()),
+            Arc::clone(clock),
+        ));
+        registry.register(TemperatureCollector::new(
+            Box::new(SysinfoSource::new()),
+            Arc::clone(clock),
+        ));
+        registry.register(PressureCollector::new(
+            Box::new(SysinfoSource::new()),
+            Arc::clone(clock),
+        ));
+        if config.cgroups.enabled {
+            registry.register(CgroupCollector::new(
+                &config.cgroups,
+                Box::new(SysinfoSource::new()),
+                Arc::clone(clock),
+            ));
+        }
+
+        registry
+    }
+
+    pub fn register(&mut self, collector: impl Collector) {
+        self.collectors.push(Box::new(collector));
+    }
+
+    // the tables of the disabled collectors are created too, so their
+    // endpoints keep returning the samples taken before they were disabled
+    pub async fn register_schemas(&self, conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+        for collector in &self.collectors {
+            collector.register_schema(conn).await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn start(self, token: &CancellationToken, frames: &FrameSender, samples: &SharedSamples) {
+        for collector in self.collectors {
+            if !self.sampling.is_collector_enabled(collector.name()) {
+                info!("the {} collector is disabled", collector.name());
+                continue;
+            }
+
+            collector.spawn(token, frames, samples);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::models::get_mem_status::MemFrameStatus;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    struct CountingCollector {
+        name: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Collector for CountingCollector {
+        type Sample = usize;
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(60)
+        }
+
+        fn collect(&mut self) -> Self::Sample {
+            self.runs.fetch_add(1, Ordering::SeqCst) + 1
+        }
+
+        fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+            Some(StatusFrame::Mem(MemFrameStatus {
+                id: -1,
+                last_check: *sample as i64,
+                mems_usage: vec![],
+            }))
+        }
+
+        fn update_latest(&self, _sample: Self::Sample, _latest: &mut LatestSamples) {}
+    }
+
+    #[tokio::test]
+    async fn registry_start_test() {
+        let enabled_runs = Arc::new(AtomicUsize::new(0));
+        let disabled_runs = Arc::new(AtomicUsize::new(0));
+
+        let mut registry = CollectorRegistry::new(SamplingConfig {
+            disabled_collectors: vec!["disabled".to_string()],
+            ..Default::default()
+        });
+        registry.register(CountingCollector {
+            name: "enabled",
+            runs: Arc::clone(&enabled_runs),
+        });
+        registry.register(CountingCollector {
+            name: "disabled",
+            runs: Arc::clone(&disabled_runs),
+        });
+
+        let token = CancellationToken::new();
+        let (frames, mut receiver) = mpsc::channel(8);
+        let samples: SharedSamples = Arc::new(Mutex::new(LatestSamples::default()));
+        registry.start(&token, &frames, &samples);
+
+        // the first run is right away
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        token.cancel();
+
+        assert_eq!(enabled_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(disabled_runs.load(Ordering::SeqCst), 0);
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::Collector;
+use crate::config::CgroupConfig;
+use crate::monitor::cgroups::{compute_cgroups, CgroupCountersMap};
+use crate::monitor::clock::SharedClock;
+use crate::monitor::models::get_cgroup_status::CgroupFrameStatus;
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::SystemSource;
+
+// cgroups, only where a cgroup v2 hierarchy is mounted
+pub(super) struct CgroupCollector {
+    source: Box<dyn SystemSource>,
+    root: PathBuf,
+    max_depth: usize,
+    clock: SharedClock,
+    // like diskstats, the cgroup rates are computed from the previous sample
+    last_cgroups: Option<(CgroupCountersMap, Instant)>,
+}
+
+impl CgroupCollector {
+    pub fn new(config: &CgroupConfig, source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self {
+            source,
+            root: PathBuf::from(&config.root),
+            max_depth: config.max_depth,
+            clock,
+            last_cgroups: None,
+        }
+    }
+}
+
+impl Collector for CgroupCollector {
+    type Sample = Option<CgroupFrameStatus>;
+
+    fn name(&self) -> &'static str {
+        "cgroups"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        let mut cgroup_usage: Option<CgroupFrameStatus> = None;
+        let cgroups_time = Instant::now();
+        let cgroups = self.source.read_cgroups(&self.root, self.max_depth);
+        if let Some(current) = &cgroups {
+            let empty = HashMap::new() as CgroupCountersMap;
+            let (previous, elapsed) = match &self.last_cgroups {
+                Some((previous, previous_time)) => {
+                    (previous, cgroups_time.duration_since(*previous_time))
+                }
+                None => (&empty, Duration::ZERO),
+            };
+
+            cgroup_usage = Some(CgroupFrameStatus {
+                id: -1,
+                last_check: self.clock.now_millis(),
+                cgroups: compute_cgroups(previous, current, &elapsed),
+            });
+        }
+        self.last_cgroups = cgroups.map(|c| (c, cgroups_time));
+
+        cgroup_usage
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        sample.clone().map(StatusFrame::Cgroup)
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.cgroup_usage = sample;
+    }
+}
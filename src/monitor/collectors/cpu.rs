@@ -0,0 +1,104 @@
+use blake3::Hasher;
+
+use super::Collector;
+use crate::monitor::clock::SharedClock;
+use crate::monitor::models::{
+    get_cpu_status::{CpuCoreInfo, CpuFrameStatus},
+    get_hardware_info::HardwareCpuInfo,
+};
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::{CpuReading, SystemSource};
+
+trait CpuId {
+    fn get_cpu_id(&self) -> String;
+}
+
+impl CpuId for CpuReading {
+    fn get_cpu_id(&self) -> String {
+        let the_str: String = format!("{}{}", self.vendor_id, self.brand);
+        let mut hasher = Hasher::new();
+        hasher.update(the_str.as_bytes());
+
+        let hash = hasher.finalize();
+
+        let hashed = hash.to_string();
+
+        hashed
+    }
+}
+
+pub(super) struct CpuCollector {
+    source: Box<dyn SystemSource>,
+    clock: SharedClock,
+}
+
+impl CpuCollector {
+    pub fn new(source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self { source, clock }
+    }
+}
+
+impl Collector for CpuCollector {
+    type Sample = (CpuFrameStatus, Vec<HardwareCpuInfo>);
+
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        let all_cpus = self.source.read_cpus();
+        let last_check = self.clock.now_millis();
+        let mut cpu_usage: CpuFrameStatus = CpuFrameStatus {
+            id: -1,
+            last_check,
+            cores_usage: vec![],
+        };
+        let mut cpu_info: Vec<HardwareCpuInfo> = vec![];
+        for cpu in &all_cpus {
+            let cpu_id = &cpu.get_cpu_id();
+
+            cpu_usage.cores_usage.push(CpuCoreInfo {
+                id: -1,
+                frame_id: -1,
+                cpu_id: cpu_id.to_string(),
+                freq: cpu.frequency as i64,
+                usage: cpu.usage as i64,
+            });
+
+            let cpu_id_owned = cpu_id.to_owned();
+            if cpu_info.iter().any(|c| c.cpu_id == cpu_id_owned) {
+                continue;
+            } else {
+                let core_count = all_cpus
+                    .iter()
+                    .filter(|c| c.get_cpu_id() == cpu_id_owned)
+                    .count();
+
+                let new_info = HardwareCpuInfo {
+                    id: -1,
+                    cpu_id: cpu_id_owned,
+                    core_count: core_count as i32,
+                    brand: cpu.brand.to_string(),
+                    vendor_id: cpu.vendor_id.to_string(),
+                    last_check,
+                    valid_from: last_check,
+                    valid_to: None,
+                };
+
+                cpu_info.push(new_info);
+            }
+        }
+
+        (cpu_usage, cpu_info)
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        Some(StatusFrame::Cpu(sample.0.clone()))
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.cpu_usage = Some(sample.0);
+        latest.cpu_info = Some(sample.1);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use blake3::Hasher;
+use log::debug;
+
+use super::Collector;
+use crate::monitor::clock::SharedClock;
+use crate::monitor::diskstats::{compute_disks_io, get_device_name, DiskStatsMap};
+use crate::monitor::models::{
+    get_disk_status::{DiskFrameStatus, SingleDiskInfo},
+    get_hardware_info::HardwareDiskInfo,
+};
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::{DiskReading, SystemSource};
+
+trait DiskId {
+    fn get_disk_id(&self) -> String;
+}
+
+impl DiskId for DiskReading {
+    fn get_disk_id(&self) -> String {
+        // only what identifies the disk, so a resized or reformatted
+        // disk keeps its id and shows up as changed
+        let the_str: String = format!("{}\0{}", self.name, self.mount_point);
+
+        debug!("the str: {}", the_str);
+
+        let mut hasher = Hasher::new();
+
+        hasher.update(the_str.as_bytes());
+
+        let hash = hasher.finalize();
+
+        hash.to_string()
+    }
+}
+
+pub(super) struct DiskCollector {
+    source: Box<dyn SystemSource>,
+    clock: SharedClock,
+    // the previous /proc/diskstats sample and when it was taken,
+    // the I/O rates are computed from the deltas
+    last_diskstats: Option<(DiskStatsMap, i64)>,
+}
+
+impl DiskCollector {
+    pub fn new(source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self {
+            source,
+            clock,
+            last_diskstats: None,
+        }
+    }
+}
+
+impl Collector for DiskCollector {
+    type Sample = (DiskFrameStatus, Vec<HardwareDiskInfo>);
+
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        let disks = self.source.read_disks();
+        let last_check = self.clock.now_millis();
+
+        let mut disk_usage: DiskFrameStatus = DiskFrameStatus {
+            id: -1,
+            last_check,
+            disks_usage: vec![],
+            disks_io: vec![],
+        };
+        let mut disks_info: Vec<HardwareDiskInfo> = vec![];
+        // device name -> disk_id, to match the diskstats devices to the disks
+        let mut device_disk_ids: HashMap<String, String> = HashMap::new();
+        for disk in &disks {
+            let disk_id = disk.get_disk_id();
+            device_disk_ids.insert(get_device_name(&disk.name), disk_id.to_string());
+            // if the name is empty, it's probably a local disk
+            // TODO(isaidsari): add C: etc
+            let disk_name = if disk.name.is_empty() {
+                "Local Disk".to_string()
+            } else {
+                disk.name.to_string()
+            };
+
+            disk_usage.disks_usage.push(SingleDiskInfo {
+                id: -1,
+                frame_id: -1,
+                disk_id: disk_id.to_string(),
+                // sqlx doesn't support u64
+                available: disk.available_space as i64,
+                inodes_total: disk.inodes.map(|i| i.0),
+                inodes_free: disk.inodes.map(|i| i.1),
+            });
+
+            disks_info.push(HardwareDiskInfo {
+                id: -1,
+                fs_type: disk.file_system.to_string(),
+                is_removable: disk.is_removable,
+                kind: disk.kind.to_string(),
+                mount_point: disk.mount_point.to_string(),
+                // sqlx doesn't support u64
+                total_space: disk.total_space as i64,
+                disk_id: disk_id.to_string(),
+                name: disk_name,
+                last_check,
+                valid_from: last_check,
+                valid_to: None,
+            });
+        }
+
+        let diskstats = self.source.read_diskstats();
+        if let (Some((previous, previous_check)), Some(current)) =
+            (&self.last_diskstats, &diskstats)
+        {
+            let elapsed = Duration::from_millis((last_check - previous_check).max(0) as u64);
+            disk_usage.disks_io = compute_disks_io(previous, current, &elapsed, &device_disk_ids);
+        }
+        self.last_diskstats = diskstats.map(|d| (d, last_check));
+
+        (disk_usage, disks_info)
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        Some(StatusFrame::Disk(sample.0.clone()))
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.disk_usage = Some(sample.0);
+        latest.disks_info = Some(sample.1);
+    }
+}
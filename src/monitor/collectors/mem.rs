@@ -0,0 +1,66 @@
+use super::Collector;
+use crate::monitor::clock::SharedClock;
+use crate::monitor::models::{
+    get_hardware_info::HardwareMemInfo,
+    get_mem_status::{MemFrameStatus, SingleMemInfo},
+};
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::SystemSource;
+
+pub(super) struct MemCollector {
+    source: Box<dyn SystemSource>,
+    clock: SharedClock,
+}
+
+impl MemCollector {
+    pub fn new(source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self { source, clock }
+    }
+}
+
+impl Collector for MemCollector {
+    type Sample = (MemFrameStatus, Vec<HardwareMemInfo>);
+
+    fn name(&self) -> &'static str {
+        "mem"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        let mem = self.source.read_mem();
+        let last_check = self.clock.now_millis();
+
+        let mem_info: Vec<HardwareMemInfo> = vec![HardwareMemInfo {
+            id: -1,
+            mem_id: "1".to_string(),
+            last_check,
+            total_space: mem.total as i64,
+            valid_from: last_check,
+            valid_to: None,
+        }];
+
+        let mem_usage: MemFrameStatus = MemFrameStatus {
+            id: -1,
+            last_check,
+            mems_usage: vec![SingleMemInfo {
+                id: -1,
+                frame_id: -1,
+                // constant, as there's only one mem
+                mem_id: "1".to_string(),
+                // sqlx doesn't support u64
+                available: mem.free as i64,
+            }],
+        };
+
+        (mem_usage, mem_info)
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        Some(StatusFrame::Mem(sample.0.clone()))
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.mem_usage = Some(sample.0);
+        latest.mem_info = Some(sample.1);
+    }
+}
@@ -0,0 +1,44 @@
+use super::Collector;
+use crate::monitor::clock::SharedClock;
+use crate::monitor::models::get_pressure_status::PressureFrameStatus;
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::SystemSource;
+
+// pressure stall information, only on linux kernels that support it
+pub(super) struct PressureCollector {
+    source: Box<dyn SystemSource>,
+    clock: SharedClock,
+}
+
+impl PressureCollector {
+    pub fn new(source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self { source, clock }
+    }
+}
+
+impl Collector for PressureCollector {
+    type Sample = Option<PressureFrameStatus>;
+
+    fn name(&self) -> &'static str {
+        "pressure"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        self.source
+            .read_pressure()
+            .map(|pressures| PressureFrameStatus {
+                id: -1,
+                last_check: self.clock.now_millis(),
+                pressures,
+            })
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        sample.clone().map(StatusFrame::Pressure)
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.pressure_usage = sample;
+    }
+}
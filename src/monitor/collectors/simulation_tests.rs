@@ -0,0 +1,207 @@
+// runs the collectors against recorded readings and a fake clock,
+// from the readings to the notifications the devices would get
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::cpu::CpuCollector;
+use super::disk::DiskCollector;
+use super::mem::MemCollector;
+use super::pressure::PressureCollector;
+use super::temperature::TemperatureCollector;
+use super::Collector;
+use crate::monitor::clock::{FakeClock, SharedClock};
+use crate::monitor::config_exceeds::check_config_thresholds;
+use crate::monitor::diskstats::parse_diskstats;
+use crate::monitor::hardware_changes::get_hardware_diff;
+use crate::monitor::models::{
+    get_hardware_info::HardwareInfo, get_pressure_status::SinglePressureInfo, MonitorConfig,
+};
+use crate::monitor::notifier::RecordingNotifier;
+use crate::monitor::system_monitor::{LatestSamples, ThresholdStatus};
+use crate::monitor::system_source::{
+    ComponentReading, CpuReading, DiskReading, MemReading, ScriptedSource, SystemReading,
+};
+use crate::persistence::notification_logs::NotificationType;
+
+const START: i64 = 1_700_000_000_000;
+const STEP: Duration = Duration::from_secs(10);
+// an hour of samples, one every 10 seconds
+const STEPS: usize = 360;
+
+fn get_reading(step: usize) -> SystemReading {
+    // the cpu is busy from the 20th to the 40th minute
+    let usage = if (120..240).contains(&step) {
+        95.0
+    } else {
+        20.0
+    };
+    let cpu = |usage| CpuReading {
+        vendor_id: "GenuineIntel".to_string(),
+        brand: "Intel(R) Xeon(R) CPU".to_string(),
+        frequency: 2400,
+        usage,
+    };
+
+    // 100 sectors are read every step
+    let sectors_read = 1000 + step * 100;
+    let diskstats = format!(
+        "259 0 nvme0n1 {} 0 {} 0 0 0 0 0 0 {} 0\n",
+        step, sectors_read, step
+    );
+
+    SystemReading {
+        cpus: vec![cpu(usage), cpu(usage)],
+        mem: MemReading {
+            total: 16 * 1024 * 1024 * 1024,
+            free: 8 * 1024 * 1024 * 1024,
+        },
+        disks: vec![DiskReading {
+            name: "/dev/nvme0n1".to_string(),
+            file_system: "ext4".to_string(),
+            kind: "SSD".to_string(),
+            is_removable: false,
+            mount_point: "/".to_string(),
+            total_space: 512 * 1024 * 1024 * 1024,
+            available_space: 256 * 1024 * 1024 * 1024,
+            inodes: Some((1000, 500)),
+        }],
+        diskstats: Some(parse_diskstats(&diskstats)),
+        components: vec![ComponentReading {
+            label: "coretemp Package id 0".to_string(),
+            temperature: 50.0,
+            max: 60.0,
+            critical: Some(100.0),
+        }],
+        pressure: Some(vec![SinglePressureInfo {
+            id: -1,
+            frame_id: -1,
+            resource: "cpu".to_string(),
+            kind: "some".to_string(),
+            avg10: 1.0,
+            avg60: 1.0,
+            avg300: 1.0,
+            total: step as i64,
+        }]),
+        cgroups: None,
+        processes: vec![],
+    }
+}
+
+fn get_config() -> MonitorConfig {
+    MonitorConfig {
+        id: 1,
+        device_id: "device".to_string(),
+        cpu_threshold: 90.0,
+        mem_threshold: 100.0,
+        disk_threshold: 100.0,
+        inode_threshold: None,
+        cpu_pressure_threshold: None,
+        mem_pressure_threshold: None,
+        io_pressure_threshold: None,
+        cgroup_mem_threshold: None,
+        fcm_token: "token".to_string(),
+        updated_at: START,
+    }
+}
+
+fn collect<C: Collector>(collector: &mut C, latest: &mut LatestSamples) {
+    let sample = collector.collect();
+    collector.update_latest(sample, latest);
+}
+
+fn get_hardware_info(latest: &LatestSamples) -> HardwareInfo {
+    HardwareInfo {
+        cpu_info: latest.cpu_info.clone().unwrap(),
+        disks_info: latest.disks_info.clone().unwrap(),
+        mem_info: latest.mem_info.clone().unwrap(),
+        components_info: latest.components_info.clone().unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn simulate_an_hour_test() {
+    let readings: Vec<SystemReading> = (0..STEPS).map(get_reading).collect();
+    let fake_clock = Arc::new(FakeClock::new(START));
+    let clock: SharedClock = fake_clock.clone();
+    let source = || Box::new(ScriptedSource::new(readings.clone()));
+
+    let mut cpu = CpuCollector::new(source(), Arc::clone(&clock));
+    let mut mem = MemCollector::new(source(), Arc::clone(&clock));
+    let mut disk = DiskCollector::new(source(), Arc::clone(&clock));
+    let mut temperature = TemperatureCollector::new(source(), Arc::clone(&clock));
+    let mut pressure = PressureCollector::new(source(), Arc::clone(&clock));
+    let notifier = RecordingNotifier::new(Arc::clone(&clock));
+
+    let config = get_config();
+    let mut latest = LatestSamples::default();
+    let mut first_inventory: Option<HardwareInfo> = None;
+
+    for step in 0..STEPS {
+        collect(&mut cpu, &mut latest);
+        collect(&mut mem, &mut latest);
+        collect(&mut disk, &mut latest);
+        collect(&mut temperature, &mut latest);
+        collect(&mut pressure, &mut latest);
+
+        let now = clock.now_millis();
+        assert_eq!(latest.cpu_usage.as_ref().unwrap().last_check, now);
+        assert_eq!(latest.disk_usage.as_ref().unwrap().last_check, now);
+        assert_eq!(latest.temperature_usage.as_ref().unwrap().last_check, now);
+        assert_eq!(latest.pressure_usage.as_ref().unwrap().last_check, now);
+
+        // the ids are derived from the hardware, so the inventory never changes
+        let inventory = get_hardware_info(&latest);
+        match &first_inventory {
+            Some(first) => assert!(get_hardware_diff(first, &inventory).is_empty()),
+            None => first_inventory = Some(inventory),
+        }
+
+        // the I/O rates need a previous sample
+        let disks_io = &latest.disk_usage.as_ref().unwrap().disks_io;
+        if step == 0 {
+            assert!(disks_io.is_empty());
+        } else {
+            assert_eq!(disks_io.len(), 1);
+            assert_eq!(disks_io[0].read_bytes_per_sec, 100.0 * 512.0 / 10.0);
+        }
+
+        let status = ThresholdStatus::from(latest.clone());
+        check_config_thresholds(
+            &config,
+            &[],
+            &[],
+            &status.get_check_data(&[]),
+            &notifier,
+            &clock,
+        )
+        .await;
+
+        fake_clock.advance(STEP);
+    }
+
+    let first = first_inventory.unwrap();
+    assert_eq!(first.cpu_info.len(), 1);
+    assert_eq!(first.cpu_info[0].core_count, 2);
+    assert_eq!(first.disks_info.len(), 1);
+    assert_eq!(first.components_info.len(), 1);
+    assert_eq!(clock.now_millis(), START + 60 * 60 * 1000);
+
+    // once the cpu gets busy, then once every 5 minutes while it stays busy
+    let minutes = |m: i64| START + m * 60 * 1000;
+    let notifications = notifier.get_sent();
+    let sent_at: Vec<i64> = notifications.iter().map(|n| n.sent_at).collect();
+    assert_eq!(
+        sent_at,
+        vec![minutes(20), minutes(25), minutes(30), minutes(35)]
+    );
+    assert!(notifications.iter().all(|n| n.device_id == "device"
+        && n.notification_type == NotificationType::StatusLimitsExceeding));
+    assert_eq!(
+        notifications[0].title,
+        "IMPORTANT: Your config limits are exceeded"
+    );
+    assert_eq!(
+        notifications[0].body,
+        "the thresholds exceeded for: cpu with 95%"
+    );
+}
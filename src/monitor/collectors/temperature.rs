@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use blake3::Hasher;
+
+use super::Collector;
+use crate::monitor::clock::SharedClock;
+use crate::monitor::models::{
+    get_hardware_info::HardwareComponentInfo,
+    get_temperature_status::{SingleTemperatureInfo, TemperatureFrameStatus},
+};
+use crate::monitor::persistence::StatusFrame;
+use crate::monitor::system_monitor::LatestSamples;
+use crate::monitor::system_source::SystemSource;
+
+// the labels aren't unique, there's an "nvme Composite" for every drive and a
+// "coretemp Core 0" for every socket, so the sensors after the first one with a
+// label also hash their position among those. the first one keeps the id of the
+// label alone, the id it had before
+fn get_component_ids<'a>(labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+
+    labels
+        .into_iter()
+        .map(|label| {
+            let position = positions.entry(label).or_default();
+
+            let mut hasher = Hasher::new();
+            hasher.update(label.as_bytes());
+            if *position > 0 {
+                hasher.update(&[0]);
+                hasher.update(position.to_string().as_bytes());
+            }
+            *position += 1;
+
+            hasher.finalize().to_string()
+        })
+        .collect()
+}
+
+// sysinfo reports NaN for the values it failed to read
+fn get_sensor_value(val: f32) -> Option<f64> {
+    if val.is_nan() {
+        None
+    } else {
+        Some(val as f64)
+    }
+}
+
+pub(super) struct TemperatureCollector {
+    source: Box<dyn SystemSource>,
+    clock: SharedClock,
+}
+
+impl TemperatureCollector {
+    pub fn new(source: Box<dyn SystemSource>, clock: SharedClock) -> Self {
+        Self { source, clock }
+    }
+}
+
+impl Collector for TemperatureCollector {
+    type Sample = (TemperatureFrameStatus, Vec<HardwareComponentInfo>);
+
+    fn name(&self) -> &'static str {
+        "temperature"
+    }
+
+    fn collect(&mut self) -> Self::Sample {
+        // some hosts, like most VMs, don't expose any sensors, in which case this is empty
+        let components = self.source.read_components();
+        let last_check = self.clock.now_millis();
+
+        let mut temperature_usage: TemperatureFrameStatus = TemperatureFrameStatus {
+            id: -1,
+            last_check,
+            components_temperature: vec![],
+        };
+        let mut components_info: Vec<HardwareComponentInfo> = vec![];
+        // of every component, the unreadable ones too, so the positions don't shift
+        let component_ids = get_component_ids(components.iter().map(|c| c.label.as_str()));
+        for (component, component_id) in components.iter().zip(component_ids) {
+            // a component without a readable temperature has nothing to record
+            let temperature = match get_sensor_value(component.temperature) {
+                Some(temperature) => temperature,
+                None => continue,
+            };
+
+            let critical = component.critical.and_then(get_sensor_value);
+
+            temperature_usage
+                .components_temperature
+                .push(SingleTemperatureInfo {
+                    id: -1,
+                    frame_id: -1,
+                    component_id: component_id.to_string(),
+                    temperature,
+                    max: get_sensor_value(component.max),
+                    critical,
+                });
+
+            components_info.push(HardwareComponentInfo {
+                id: -1,
+                component_id,
+                label: component.label.to_string(),
+                critical,
+                last_check,
+                valid_from: last_check,
+                valid_to: None,
+            });
+        }
+
+        (temperature_usage, components_info)
+    }
+
+    fn get_frame(&self, sample: &Self::Sample) -> Option<StatusFrame> {
+        if sample.0.components_temperature.is_empty() {
+            return None;
+        }
+
+        Some(StatusFrame::Temperature(sample.0.clone()))
+    }
+
+    fn update_latest(&self, sample: Self::Sample, latest: &mut LatestSamples) {
+        latest.temperature_usage = Some(sample.0);
+        latest.components_info = Some(sample.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_component_ids_test() {
+        let ids = get_component_ids([
+            "nvme Composite",
+            "coretemp Core 0",
+            "nvme Composite",
+            "coretemp Core 0",
+            "nvme Composite",
+        ]);
+
+        // the first sensor with a label keeps the id of the label alone
+        assert_eq!(ids[0], blake3::hash(b"nvme Composite").to_string());
+        assert_eq!(ids[1], blake3::hash(b"coretemp Core 0").to_string());
+
+        let unique = ids.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), ids.len());
+
+        // the same sensors get the same ids on the next sample
+        assert_eq!(
+            get_component_ids(["nvme Composite", "coretemp Core 0", "nvme Composite"]),
+            ids[..3]
+        );
+    }
+}
@@ -1,26 +1,53 @@
+use crate::monitor::models::get_cgroup_status::{CgroupStatusData, CgroupStatusDataTrait};
 use crate::monitor::models::get_cpu_status::CpuFrameStatusTrait;
+use crate::monitor::models::get_custom_metrics::{CustomMetric, CustomMetricThreshold};
 
-use crate::monitor::models::get_hardware_info::{HardwareDiskInfo, HardwareMemInfo};
+use crate::monitor::models::get_hardware_info::{
+    HardwareComponentInfo, HardwareDiskInfo, HardwareMemInfo,
+};
 use crate::monitor::models::get_mem_status::MemStatusData;
-use crate::monitor::persistence::fetch_monitor_configs;
-use crate::notification_service::{self, NotificationMessage};
-use crate::persistence::notification_logs::{self, NotificationType};
+use crate::monitor::models::get_pressure_status::{PressureStatusData, PressureStatusDataTrait};
+use crate::monitor::models::get_temperature_status::{
+    TemperatureStatusData, TemperatureStatusDataTrait, TemperatureThreshold,
+};
+use crate::monitor::persistence::{
+    fetch_custom_metric_thresholds, fetch_monitor_configs, fetch_temperature_thresholds,
+};
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
 use chrono::Duration;
 use log::{error, info, warn};
 use std::collections::HashMap;
 use std::vec;
 
+use super::clock::SharedClock;
 use super::models::get_cpu_status::CpuStatusData;
 use super::models::get_disk_status::{DiskStatusData, DiskStatusDataTrait};
 use super::models::get_mem_status::MemStatusDataTrait;
 use super::models::MonitorConfig;
+use super::notifier::Notifier;
+
+// everything the thresholds of the configs are checked against
+pub(super) struct ThresholdCheckData<'a> {
+    pub cpu_status: &'a CpuStatusData,
+    pub mem_status: &'a MemStatusData,
+    pub mems_info: &'a Vec<HardwareMemInfo>,
+    pub disk_status: &'a DiskStatusData,
+    pub disks_info: &'a Vec<HardwareDiskInfo>,
+    pub temperature_status: &'a TemperatureStatusData,
+    pub components_info: &'a Vec<HardwareComponentInfo>,
+    // empty where the kernel doesn't support PSI
+    pub pressure_status: &'a PressureStatusData,
+    // empty where there's no cgroup v2 hierarchy
+    pub cgroup_status: &'a CgroupStatusData,
+    // the values of the latest successful run of every custom probe
+    pub custom_metrics: &'a [CustomMetric],
+}
 
 pub(super) async fn check_thresholds(
-    cpu_status: &CpuStatusData,
-    mem_status: &MemStatusData,
-    mems_info: &Vec<HardwareMemInfo>,
-    disk_status: &DiskStatusData,
-    disks_info: &Vec<HardwareDiskInfo>,
+    data: &ThresholdCheckData<'_>,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
 ) {
     let configs = fetch_monitor_configs().await.unwrap_or_else(|e| {
         error!("failed to fetch monitor configs: {}", e);
@@ -28,80 +55,88 @@ pub(super) async fn check_thresholds(
     });
 
     for config in configs {
-        let (cpu, mem, disk) = statuses_exceeds(
+        let temperature_thresholds = fetch_temperature_thresholds(&config.device_id)
+            .await
+            .unwrap_or_else(|e| {
+                error!("failed to fetch temperature thresholds: {}", e);
+                vec![]
+            });
+
+        let custom_metric_thresholds = fetch_custom_metric_thresholds(&config.device_id)
+            .await
+            .unwrap_or_else(|e| {
+                error!("failed to fetch custom metric thresholds: {}", e);
+                vec![]
+            });
+
+        check_config_thresholds(
             &config,
-            cpu_status,
-            mem_status,
-            mems_info,
-            disk_status,
-            disks_info,
-        );
-
-        let any_exceeds = cpu.is_some() || mem.is_some() || disk.is_some();
-
-        if any_exceeds {
-            let mut exceeding_msgs: Vec<String> = vec![];
-
-            match cpu {
-                Some(cpu) => {
-                    exceeding_msgs.push(format!("cpu with {}%", cpu));
-                }
-                None => {}
-            }
-
-            match mem {
-                Some(mem) => {
-                    exceeding_msgs.push(format!("mem with {}%", mem));
-                }
-                None => {}
-            }
-
-            match disk {
-                Some(disk) => {
-                    exceeding_msgs.push(format!("disk with {}%", disk));
-                }
-                None => {}
-            }
-
-            let result = exceeding_msgs.join(", ");
+            &temperature_thresholds,
+            &custom_metric_thresholds,
+            data,
+            notifier,
+            clock,
+        )
+        .await;
+    }
+}
 
-            let warn_msg = format!(
-                "the config for device id {} thresholds exceeded for: {}",
-                config.device_id, result
-            );
+// checks the thresholds of a single device, and notifies it about the exceeding ones
+pub(super) async fn check_config_thresholds(
+    config: &MonitorConfig,
+    temperature_thresholds: &[TemperatureThreshold],
+    custom_metric_thresholds: &[CustomMetricThreshold],
+    data: &ThresholdCheckData<'_>,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let exceeding_msgs = get_exceeding_msgs(
+        config,
+        temperature_thresholds,
+        custom_metric_thresholds,
+        data,
+    );
+
+    if !exceeding_msgs.is_empty() {
+        let result = exceeding_msgs.join(", ");
+
+        let warn_msg = format!(
+            "the config for device id {} thresholds exceeded for: {}",
+            config.device_id, result
+        );
 
-            warn!("{}", warn_msg);
+        warn!("{}", warn_msg);
 
-            send_notification_to_exceeding_device(&config, cpu, mem, disk).await;
-        }
+        send_notification_to_exceeding_device(config, &exceeding_msgs, notifier, clock).await;
     }
 }
 
 // TODO(adnanjpg): make it configurable
-fn get_send_notification_interval() -> Duration {
+pub(super) fn get_send_notification_interval() -> Duration {
     Duration::seconds(5 * 60)
 }
 
-async fn should_send_notification_to_exceeding_device(config: &MonitorConfig) -> bool {
-    let latest_record: Result<Option<notification_logs::NotificationLog>, sqlx::Error> =
-        notification_logs::fetch_single_latest_for_device_id_and_type(
-            &config.device_id,
-            &NotificationType::StatusLimitsExceeding,
-        )
-        .await;
-
-    let should_send = match latest_record {
-        Ok(val) => match val {
-            Some(v) => {
-                let now_millis = chrono::Utc::now().timestamp_millis();
+pub(super) fn is_cooldown_over(last_sent_at: i64, now_millis: i64) -> bool {
+    let mss = get_send_notification_interval().num_milliseconds();
+    let earliest_date_to_send = last_sent_at + mss;
 
-                let mss = get_send_notification_interval().num_milliseconds();
-                let earliest_date_to_send = v.sent_at + mss;
+    earliest_date_to_send <= now_millis
+}
 
-                let res = earliest_date_to_send <= now_millis;
+// whether the cooldown since the last notification of this type to the device has passed
+pub(super) async fn should_send_notification(
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+    device_id: &str,
+    notification_type: &NotificationType,
+) -> bool {
+    let last_sent_at = notifier
+        .fetch_last_sent_at(device_id, notification_type)
+        .await;
 
-                res
-            }
+    let should_send = match last_sent_at {
+        Ok(val) => match val {
+            Some(sent_at) => is_cooldown_over(sent_at, clock.now_millis()),
             None => true,
         },
         Err(e) => {
@@ -114,13 +149,27 @@ async fn should_send_notification_to_exceeding_device(config: &MonitorConfig) ->
     should_send
 }
 
+async fn should_send_notification_to_exceeding_device(
+    config: &MonitorConfig,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) -> bool {
+    should_send_notification(
+        notifier,
+        clock,
+        &config.device_id,
+        &NotificationType::StatusLimitsExceeding,
+    )
+    .await
+}
+
 async fn send_notification_to_exceeding_device(
     config: &MonitorConfig,
-    cpu: Option<f64>,
-    mem: Option<f64>,
-    disk: Option<f64>,
+    exceeding_msgs: &[String],
+    notifier: &impl Notifier,
+    clock: &SharedClock,
 ) -> bool {
-    let should_send = should_send_notification_to_exceeding_device(&config).await;
+    let should_send = should_send_notification_to_exceeding_device(config, notifier, clock).await;
 
     if !should_send {
         warn!("did not send notification to exceeding device because a notification has already been sent in the last {} seconds", get_send_notification_interval().num_seconds());
@@ -130,29 +179,6 @@ async fn send_notification_to_exceeding_device(
 
     let title = "IMPORTANT: Your config limits are exceeded";
 
-    let mut exceeding_msgs: Vec<String> = vec![];
-
-    match cpu {
-        Some(cpu) => {
-            exceeding_msgs.push(format!("cpu with {}%", cpu));
-        }
-        None => {}
-    }
-
-    match mem {
-        Some(mem) => {
-            exceeding_msgs.push(format!("mem with {}%", mem));
-        }
-        None => {}
-    }
-
-    match disk {
-        Some(disk) => {
-            exceeding_msgs.push(format!("disk with {}%", disk));
-        }
-        None => {}
-    }
-
     let result = exceeding_msgs.join(", ");
     // TODO(adnanjpg): include server ip
     let body = format!("the thresholds exceeded for: {}", result);
@@ -166,13 +192,14 @@ async fn send_notification_to_exceeding_device(
     // TODO(adnanjpg): currently the notifications are sent silently
     // this has to have a higher priority
     // add a priority field to the send_notification_to_single function
-    let not_res = notification_service::send_notification_to_single(
-        &config.device_id,
-        &fcm_token,
-        &message,
-        &NotificationType::StatusLimitsExceeding,
-    )
-    .await;
+    let not_res = notifier
+        .send(
+            &config.device_id,
+            &fcm_token,
+            &message,
+            &NotificationType::StatusLimitsExceeding,
+        )
+        .await;
 
     if let Err(not_res) = not_res {
         error!(
@@ -290,31 +317,190 @@ type StatusExceedsReturn = Option<
     f64,
 >;
 
-fn statuses_exceeds(
+fn inode_status_exceeds(config: &MonitorConfig, status: &DiskStatusData) -> StatusExceedsReturn {
+    let threshold = config.inode_threshold?;
+
+    let means = status.disks_inode_usage_means_percentages();
+
+    // like the disk usage, return the biggest exceeding percentage
+    means
+        .into_values()
+        .filter(|&val| val >= threshold)
+        .reduce(f64::max)
+}
+
+// the resource and the "some" avg10 stall percentage of every
+// resource that exceeds its pressure threshold
+fn pressure_status_exceeds(
     config: &MonitorConfig,
-    cpu_status: &CpuStatusData,
-    mem_status: &MemStatusData,
-    mems_info: &Vec<HardwareMemInfo>,
-    disk_status: &DiskStatusData,
-    disks_info: &Vec<HardwareDiskInfo>,
-) -> (
-    StatusExceedsReturn,
-    StatusExceedsReturn,
-    StatusExceedsReturn,
-) {
-    (
-        cpu_status_exceeds(config, cpu_status),
-        mem_status_exceeds(config, mem_status, mems_info),
-        disk_status_exceeds(config, disk_status, disks_info),
-    )
+    status: &PressureStatusData,
+) -> Vec<(String, f64)> {
+    [
+        ("cpu", config.cpu_pressure_threshold),
+        ("memory", config.mem_pressure_threshold),
+        ("io", config.io_pressure_threshold),
+    ]
+    .into_iter()
+    .filter_map(|(resource, threshold)| {
+        let threshold = threshold?;
+        let pressure = status.max_some_avg10(resource)?;
+
+        if pressure < threshold {
+            return None;
+        }
+
+        Some((resource.to_string(), pressure))
+    })
+    .collect()
+}
+
+// the path and the memory.max usage percentage of every
+// cgroup that exceeds the threshold, sorted by path
+fn cgroup_status_exceeds(config: &MonitorConfig, status: &CgroupStatusData) -> Vec<(String, f64)> {
+    let threshold = match config.cgroup_mem_threshold {
+        Some(threshold) => threshold,
+        None => return vec![],
+    };
+
+    let mut exceeding: Vec<(String, f64)> = status
+        .cgroups_memory_usage_max_percentages()
+        .into_iter()
+        .filter(|(_, perc)| *perc >= threshold)
+        .collect();
+
+    exceeding.sort_by(|a, b| a.0.cmp(&b.0));
+
+    exceeding
+}
+
+// the label and the mean temperature of every sensor that
+// exceeds the threshold the device set for it
+fn temperature_status_exceeds(
+    thresholds: &[TemperatureThreshold],
+    status: &TemperatureStatusData,
+    components_info: &[HardwareComponentInfo],
+) -> Vec<(String, f64)> {
+    let means = status.components_temperature_means();
+
+    let mut exceeding: Vec<(String, f64)> = thresholds
+        .iter()
+        .filter_map(|threshold| {
+            let mean = *means.get(&threshold.component_id)?;
+
+            if mean < threshold.threshold {
+                return None;
+            }
+
+            let label = components_info
+                .iter()
+                .find(|c| c.component_id == threshold.component_id)
+                .map(|c| c.label.to_string())
+                .unwrap_or(threshold.component_id.to_string());
+
+            Some((label, mean))
+        })
+        .collect();
+
+    exceeding.sort_by(|a, b| a.0.cmp(&b.0));
+
+    exceeding
+}
+
+// the probe, the key and the value of every custom metric
+// that exceeds the threshold the device set for it
+fn custom_metric_status_exceeds(
+    thresholds: &[CustomMetricThreshold],
+    metrics: &[CustomMetric],
+) -> Vec<(String, String, f64)> {
+    let mut exceeding: Vec<(String, String, f64)> = thresholds
+        .iter()
+        .filter_map(|threshold| {
+            let value = metrics
+                .iter()
+                .find(|m| {
+                    m.probe == threshold.probe && m.key.as_deref() == Some(threshold.key.as_str())
+                })?
+                .value?;
+
+            if value < threshold.threshold {
+                return None;
+            }
+
+            Some((
+                threshold.probe.to_string(),
+                threshold.key.to_string(),
+                value,
+            ))
+        })
+        .collect();
+
+    exceeding.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+    exceeding
+}
+
+fn get_exceeding_msgs(
+    config: &MonitorConfig,
+    temperature_thresholds: &[TemperatureThreshold],
+    custom_metric_thresholds: &[CustomMetricThreshold],
+    data: &ThresholdCheckData,
+) -> Vec<String> {
+    let mut exceeding_msgs: Vec<String> = vec![];
+
+    if let Some(cpu) = cpu_status_exceeds(config, data.cpu_status) {
+        exceeding_msgs.push(format!("cpu with {}%", cpu));
+    }
+
+    if let Some(mem) = mem_status_exceeds(config, data.mem_status, data.mems_info) {
+        exceeding_msgs.push(format!("mem with {}%", mem));
+    }
+
+    if let Some(disk) = disk_status_exceeds(config, data.disk_status, data.disks_info) {
+        exceeding_msgs.push(format!("disk with {}%", disk));
+    }
+
+    if let Some(inode) = inode_status_exceeds(config, data.disk_status) {
+        exceeding_msgs.push(format!("disk inodes with {:.1}%", inode));
+    }
+
+    for (resource, pressure) in pressure_status_exceeds(config, data.pressure_status) {
+        exceeding_msgs.push(format!("{} pressure with {:.1}%", resource, pressure));
+    }
+
+    for (path, perc) in cgroup_status_exceeds(config, data.cgroup_status) {
+        exceeding_msgs.push(format!(
+            "cgroup {} memory with {:.1}% of its limit",
+            path, perc
+        ));
+    }
+
+    for (label, temperature) in temperature_status_exceeds(
+        temperature_thresholds,
+        data.temperature_status,
+        data.components_info,
+    ) {
+        exceeding_msgs.push(format!("{} with {:.1}°C", label, temperature));
+    }
+
+    for (probe, key, value) in
+        custom_metric_status_exceeds(custom_metric_thresholds, data.custom_metrics)
+    {
+        exceeding_msgs.push(format!("{} {} with {}", probe, key, value));
+    }
+
+    exceeding_msgs
 }
 
 #[cfg(test)]
 mod tests {
     use crate::monitor::models::{
+        get_cgroup_status::{CgroupFrameStatus, SingleCgroupInfo},
         get_cpu_status::{CpuCoreInfo, CpuFrameStatus},
+        get_custom_metrics::CustomMetricStatus,
         get_disk_status::{DiskFrameStatus, SingleDiskInfo},
         get_mem_status::{MemFrameStatus, SingleMemInfo},
+        get_pressure_status::{PressureFrameStatus, SinglePressureInfo},
+        get_temperature_status::{SingleTemperatureInfo, TemperatureFrameStatus},
     };
 
     use super::*;
@@ -389,6 +575,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     disk_threshold: 0.0,
                     mem_threshold: 0.0,
                     cpu_threshold: 60.0,
@@ -404,6 +595,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     disk_threshold: 0.0,
                     mem_threshold: 0.0,
                     cpu_threshold: 30.0
@@ -423,6 +619,8 @@ mod tests {
             HardwareDiskInfo {
                 id: -1,
                 last_check: -1,
+                valid_from: -1,
+                valid_to: None,
                 name: "".to_string(),
                 fs_type: "".to_string(),
                 kind: "".to_string(),
@@ -434,6 +632,8 @@ mod tests {
             HardwareDiskInfo {
                 id: -1,
                 last_check: -1,
+                valid_from: -1,
+                valid_to: None,
                 name: "".to_string(),
                 fs_type: "".to_string(),
                 kind: "".to_string(),
@@ -456,6 +656,8 @@ mod tests {
                             disk_id: disk1id.to_string(),
                             // usage: 200
                             available: 80,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                         SingleDiskInfo {
                             id: -1,
@@ -463,8 +665,11 @@ mod tests {
                             disk_id: disk2id.to_string(),
                             // usage: 10
                             available: 110,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                     ],
+                    disks_io: vec![],
                 },
                 DiskFrameStatus {
                     id: -1,
@@ -476,6 +681,8 @@ mod tests {
                             disk_id: disk1id.to_string(),
                             // usage: 60
                             available: 220,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                         SingleDiskInfo {
                             id: -1,
@@ -483,8 +690,11 @@ mod tests {
                             disk_id: disk2id.to_string(),
                             // usage: 90
                             available: 30,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                     ],
+                    disks_io: vec![],
                 },
             ],
         };
@@ -499,6 +709,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     mem_threshold: 0.0,
                     disk_threshold: 46.1,
@@ -515,6 +730,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     mem_threshold: 0.0,
                     disk_threshold: 45.0,
@@ -531,6 +751,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     mem_threshold: 0.0,
                     disk_threshold: 33.0,
@@ -551,12 +776,16 @@ mod tests {
             HardwareMemInfo {
                 id: -1,
                 last_check: -1,
+                valid_from: -1,
+                valid_to: None,
                 total_space: 280,
                 mem_id: mem1id.to_string(),
             },
             HardwareMemInfo {
                 id: -1,
                 last_check: -1,
+                valid_from: -1,
+                valid_to: None,
 
                 total_space: 120,
                 mem_id: mem2id.to_string(),
@@ -618,6 +847,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     disk_threshold: 0.0,
                     mem_threshold: 46.1,
@@ -634,6 +868,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     disk_threshold: 0.0,
                     mem_threshold: 45.0,
@@ -650,6 +889,11 @@ mod tests {
                     device_id: "".to_string(),
                     fcm_token: "".to_string(),
                     updated_at: -1,
+                    inode_threshold: None,
+                    cpu_pressure_threshold: None,
+                    mem_pressure_threshold: None,
+                    io_pressure_threshold: None,
+                    cgroup_mem_threshold: None,
                     cpu_threshold: 0.0,
                     disk_threshold: 0.0,
                     mem_threshold: 33.0,
@@ -660,4 +904,261 @@ mod tests {
             Some(46.0),
         );
     }
+
+    #[test]
+    fn inode_status_exceeds_test() {
+        let single = |disk_id: &str, inodes_free: i64| SingleDiskInfo {
+            id: -1,
+            frame_id: -1,
+            disk_id: disk_id.to_string(),
+            available: 0,
+            inodes_total: Some(1000),
+            inodes_free: Some(inodes_free),
+        };
+
+        let data = DiskStatusData {
+            frames: vec![DiskFrameStatus {
+                id: -1,
+                last_check: -1,
+                disks_usage: vec![
+                    // 95% used
+                    single("disk1id", 50),
+                    // 40% used
+                    single("disk2id", 600),
+                ],
+                disks_io: vec![],
+            }],
+        };
+
+        let config = |inode_threshold: Option<f64>| MonitorConfig {
+            id: -1,
+            device_id: "".to_string(),
+            fcm_token: "".to_string(),
+            updated_at: -1,
+            cpu_threshold: 0.0,
+            mem_threshold: 0.0,
+            disk_threshold: 0.0,
+            inode_threshold,
+            cpu_pressure_threshold: None,
+            mem_pressure_threshold: None,
+            io_pressure_threshold: None,
+            cgroup_mem_threshold: None,
+        };
+
+        assert_eq!(inode_status_exceeds(&config(None), &data), None);
+        assert_eq!(inode_status_exceeds(&config(Some(96.0)), &data), None);
+        assert_eq!(inode_status_exceeds(&config(Some(90.0)), &data), Some(95.0));
+        assert_eq!(inode_status_exceeds(&config(Some(30.0)), &data), Some(95.0));
+    }
+
+    #[test]
+    fn temperature_status_exceeds_test() {
+        let components = vec![HardwareComponentInfo {
+            id: -1,
+            component_id: "cpuid".to_string(),
+            label: "coretemp Package id 0".to_string(),
+            critical: Some(100.0),
+            last_check: -1,
+            valid_from: -1,
+            valid_to: None,
+        }];
+
+        let data = TemperatureStatusData {
+            frames: vec![TemperatureFrameStatus {
+                id: -1,
+                last_check: -1,
+                components_temperature: vec![
+                    SingleTemperatureInfo {
+                        id: -1,
+                        frame_id: -1,
+                        component_id: "cpuid".to_string(),
+                        temperature: 82.0,
+                        max: Some(90.0),
+                        critical: Some(100.0),
+                    },
+                    SingleTemperatureInfo {
+                        id: -1,
+                        frame_id: -1,
+                        component_id: "nvmeid".to_string(),
+                        temperature: 55.0,
+                        max: None,
+                        critical: None,
+                    },
+                ],
+            }],
+        };
+
+        let threshold = |component_id: &str, threshold: f64| TemperatureThreshold {
+            id: -1,
+            device_id: "".to_string(),
+            component_id: component_id.to_string(),
+            threshold,
+        };
+
+        assert_eq!(
+            temperature_status_exceeds(
+                &[threshold("cpuid", 85.0), threshold("nvmeid", 60.0)],
+                &data,
+                &components,
+            ),
+            vec![],
+        );
+        // sensors without a known label fall back to their id,
+        // and sensors without a threshold are never reported
+        assert_eq!(
+            temperature_status_exceeds(
+                &[threshold("cpuid", 80.0), threshold("nvmeid", 50.0)],
+                &data,
+                &components,
+            ),
+            vec![
+                ("coretemp Package id 0".to_string(), 82.0),
+                ("nvmeid".to_string(), 55.0),
+            ],
+        );
+        assert_eq!(temperature_status_exceeds(&[], &data, &components), vec![]);
+    }
+
+    #[test]
+    fn pressure_status_exceeds_test() {
+        let single = |resource: &str, kind: &str, avg10: f64| SinglePressureInfo {
+            id: -1,
+            frame_id: -1,
+            resource: resource.to_string(),
+            kind: kind.to_string(),
+            avg10,
+            avg60: 0.0,
+            avg300: 0.0,
+            total: 0,
+        };
+
+        let data = PressureStatusData {
+            frames: vec![PressureFrameStatus {
+                id: -1,
+                last_check: -1,
+                pressures: vec![
+                    single("cpu", "some", 25.0),
+                    single("memory", "some", 5.0),
+                    single("memory", "full", 40.0),
+                    single("io", "some", 60.0),
+                ],
+            }],
+        };
+
+        let config = MonitorConfig {
+            id: -1,
+            device_id: "".to_string(),
+            fcm_token: "".to_string(),
+            updated_at: -1,
+            inode_threshold: None,
+            cpu_pressure_threshold: Some(20.0),
+            mem_pressure_threshold: Some(10.0),
+            io_pressure_threshold: None,
+            cgroup_mem_threshold: None,
+            disk_threshold: 0.0,
+            mem_threshold: 0.0,
+            cpu_threshold: 0.0,
+        };
+
+        // memory only exceeds with its "full" line, and io has no threshold
+        assert_eq!(
+            pressure_status_exceeds(&config, &data),
+            vec![("cpu".to_string(), 25.0)]
+        );
+
+        // kernels without PSI have nothing to exceed
+        assert!(
+            pressure_status_exceeds(&config, &PressureStatusData { frames: vec![] }).is_empty()
+        );
+    }
+
+    #[test]
+    fn cgroup_status_exceeds_test() {
+        let single = |path: &str, memory_current: i64| SingleCgroupInfo {
+            id: -1,
+            frame_id: -1,
+            path: path.to_string(),
+            cpu_usage_percent: None,
+            memory_current: Some(memory_current),
+            memory_max: Some(1000),
+            io_read_bytes_per_sec: None,
+            io_write_bytes_per_sec: None,
+            pids_current: None,
+            pids_max: None,
+        };
+
+        let data = CgroupStatusData {
+            frames: vec![CgroupFrameStatus {
+                id: -1,
+                last_check: -1,
+                cgroups: vec![
+                    single("/web", 500),
+                    single("/db", 950),
+                    single("/cache", 900),
+                ],
+            }],
+        };
+
+        let config = |cgroup_mem_threshold: Option<f64>| MonitorConfig {
+            id: -1,
+            device_id: "".to_string(),
+            fcm_token: "".to_string(),
+            updated_at: -1,
+            inode_threshold: None,
+            cpu_pressure_threshold: None,
+            mem_pressure_threshold: None,
+            io_pressure_threshold: None,
+            cgroup_mem_threshold,
+            disk_threshold: 0.0,
+            mem_threshold: 0.0,
+            cpu_threshold: 0.0,
+        };
+
+        assert!(cgroup_status_exceeds(&config(None), &data).is_empty());
+        assert_eq!(
+            cgroup_status_exceeds(&config(Some(90.0)), &data),
+            vec![("/cache".to_string(), 90.0), ("/db".to_string(), 95.0)]
+        );
+    }
+
+    #[test]
+    fn custom_metric_status_exceeds_test() {
+        let metric = |probe: &str, key: &str, value: f64| CustomMetric {
+            id: -1,
+            probe: probe.to_string(),
+            key: Some(key.to_string()),
+            value: Some(value),
+            status: CustomMetricStatus::Ok,
+            message: None,
+            last_check: -1,
+        };
+        let threshold = |probe: &str, key: &str, threshold: f64| CustomMetricThreshold {
+            id: -1,
+            device_id: "".to_string(),
+            probe: probe.to_string(),
+            key: key.to_string(),
+            threshold,
+        };
+
+        let metrics = vec![
+            metric("queue", "size", 120.0),
+            metric("queue", "failed", 2.0),
+            metric("backups", "value", 30.0),
+        ];
+        let thresholds = vec![
+            threshold("queue", "size", 100.0),
+            threshold("queue", "failed", 5.0),
+            threshold("backups", "value", 30.0),
+            // the probe didn't report it, or failed
+            threshold("certs", "value", 1.0),
+        ];
+
+        assert_eq!(
+            custom_metric_status_exceeds(&thresholds, &metrics),
+            vec![
+                ("backups".to_string(), "value".to_string(), 30.0),
+                ("queue".to_string(), "size".to_string(), 120.0),
+            ]
+        );
+    }
 }
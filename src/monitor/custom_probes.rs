@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+use tokio::process::Command;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+use super::clock::SharedClock;
+use super::models::get_custom_metrics::{CustomMetric, CustomMetricStatus, CustomProbe};
+use super::persistence::insert_custom_metrics;
+use super::scheduler::Schedule;
+
+// the key of the metric when the probe printed a single number
+const SINGLE_VALUE_KEY: &str = "value";
+
+// probe name -> the successful metrics of its latest run,
+// shared with the monitor loop so the thresholds can be checked
+pub type LatestCustomMetrics = Arc<Mutex<HashMap<String, Vec<CustomMetric>>>>;
+
+// the output is either a single number, or `key=value` lines
+pub fn parse_probe_output(output: &str) -> Result<Vec<(String, f64)>, String> {
+    let output = output.trim();
+
+    if let Ok(value) = output.parse::<f64>() {
+        return Ok(vec![(SINGLE_VALUE_KEY.to_string(), value)]);
+    }
+
+    let values = output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(format!("expected a number or key=value, got \"{}\"", line))?;
+
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("the value of {} is not a number", key.trim()))?;
+
+            Ok((key.trim().to_string(), value))
+        })
+        .collect::<Result<Vec<(String, f64)>, String>>()?;
+
+    if values.is_empty() {
+        return Err("the output is empty".to_string());
+    }
+
+    Ok(values)
+}
+
+fn get_probe_command(probe: &CustomProbe) -> Command {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(&probe.command);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&probe.command);
+        command
+    };
+
+    // so the timed out commands don't keep running
+    command.kill_on_drop(true);
+
+    command
+}
+
+fn failed_metric(
+    probe: &CustomProbe,
+    status: CustomMetricStatus,
+    message: String,
+    last_check: i64,
+) -> Vec<CustomMetric> {
+    warn!("custom probe {} failed: {}", probe.name, message);
+
+    vec![CustomMetric {
+        id: -1,
+        probe: probe.name.to_string(),
+        key: None,
+        value: None,
+        status,
+        message: Some(message),
+        last_check,
+    }]
+}
+
+pub async fn run_probe(probe: &CustomProbe, clock: &SharedClock) -> Vec<CustomMetric> {
+    let last_check = clock.now_millis();
+
+    let output = time::timeout(
+        Duration::from_secs(probe.timeout_secs),
+        get_probe_command(probe).output(),
+    )
+    .await;
+
+    let output = match output {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return failed_metric(
+                probe,
+                CustomMetricStatus::Failed,
+                format!("failed to run: {}", e),
+                last_check,
+            );
+        }
+        Err(_) => {
+            return failed_metric(
+                probe,
+                CustomMetricStatus::TimedOut,
+                format!("timed out after {} seconds", probe.timeout_secs),
+                last_check,
+            );
+        }
+    };
+
+    if !output.status.success() {
+        return failed_metric(
+            probe,
+            CustomMetricStatus::Failed,
+            format!(
+                "exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            last_check,
+        );
+    }
+
+    match parse_probe_output(&String::from_utf8_lossy(&output.stdout)) {
+        Ok(values) => values
+            .into_iter()
+            .map(|(key, value)| CustomMetric {
+                id: -1,
+                probe: probe.name.to_string(),
+                key: Some(key),
+                value: Some(value),
+                status: CustomMetricStatus::Ok,
+                message: None,
+                last_check,
+            })
+            .collect(),
+        Err(e) => failed_metric(probe, CustomMetricStatus::InvalidOutput, e, last_check),
+    }
+}
+
+// every probe runs on its own task, so a slow one doesn't hold the others back
+pub(super) fn spawn_custom_probes(
+    probes: &[CustomProbe],
+    latest: &LatestCustomMetrics,
+    token: &CancellationToken,
+    clock: &SharedClock,
+) {
+    for probe in probes {
+        let probe = probe.clone();
+        let latest = Arc::clone(latest);
+        let clock = Arc::clone(clock);
+        let mut schedule = Schedule::new(
+            &format!("custom_probe:{}", probe.name),
+            Duration::from_secs(probe.interval_secs),
+            token,
+        );
+
+        tokio::spawn(async move {
+            while schedule.tick().await {
+                let started = Instant::now();
+
+                let metrics = run_probe(&probe, &clock).await;
+
+                if let Err(e) = insert_custom_metrics(&metrics).await {
+                    error!("failed to insert custom metrics: {}", e);
+                }
+
+                // a failed run has no values to check the thresholds against
+                let ok_metrics = metrics
+                    .into_iter()
+                    .filter(|m| m.status == CustomMetricStatus::Ok)
+                    .collect();
+                latest
+                    .lock()
+                    .unwrap()
+                    .insert(probe.name.to_string(), ok_metrics);
+
+                schedule.record(started.elapsed());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::clock::FakeClock;
+
+    #[test]
+    fn parse_probe_output_test() {
+        assert_eq!(
+            parse_probe_output("42.5\n"),
+            Ok(vec![("value".to_string(), 42.5)])
+        );
+        assert_eq!(
+            parse_probe_output("queue_size=12\n\n failed_jobs = 3\n"),
+            Ok(vec![
+                ("queue_size".to_string(), 12.0),
+                ("failed_jobs".to_string(), 3.0),
+            ])
+        );
+
+        assert!(parse_probe_output("").is_err());
+        assert!(parse_probe_output("hello").is_err());
+        assert!(parse_probe_output("queue_size=twelve").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_probe_test() {
+        let probe = |command: &str, timeout_secs: u64| CustomProbe {
+            name: "test".to_string(),
+            command: command.to_string(),
+            interval_secs: 60,
+            timeout_secs,
+        };
+        let clock: SharedClock = Arc::new(FakeClock::new(1000));
+
+        let metrics = run_probe(&probe("echo a=1; echo b=2", 5), &clock).await;
+        assert_eq!(
+            metrics
+                .iter()
+                .map(|m| (m.key.clone().unwrap(), m.value.unwrap()))
+                .collect::<Vec<(String, f64)>>(),
+            vec![("a".to_string(), 1.0), ("b".to_string(), 2.0)]
+        );
+        assert!(metrics.iter().all(|m| m.last_check == 1000));
+
+        let failed = run_probe(&probe("echo oops >&2; exit 3", 5), &clock).await;
+        assert_eq!(failed[0].status, CustomMetricStatus::Failed);
+        assert!(failed[0].message.as_ref().unwrap().contains("oops"));
+
+        let timed_out = run_probe(&probe("sleep 5", 1), &clock).await;
+        assert_eq!(timed_out[0].status, CustomMetricStatus::TimedOut);
+        assert_eq!(timed_out[0].value, None);
+
+        let invalid = run_probe(&probe("echo hello", 5), &clock).await;
+        assert_eq!(invalid[0].status, CustomMetricStatus::InvalidOutput);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::models::get_disk_status::SingleDiskIoInfo;
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+// the kernel always counts sectors as 512 bytes, no matter the device
+// https://www.kernel.org/doc/Documentation/block/stat.txt
+const SECTOR_SIZE: u64 = 512;
+
+// virtual devices that only add noise
+const IGNORED_DEVICE_PREFIXES: [&str; 2] = ["loop", "ram"];
+
+// the cumulative counters of a single block device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskStatsCounters {
+    pub reads_completed: u64,
+    pub sectors_read: u64,
+    pub writes_completed: u64,
+    pub sectors_written: u64,
+    // the time spent doing I/Os, in milliseconds
+    pub io_ticks: u64,
+}
+
+// device name, e.g. "sda1" -> its counters
+pub type DiskStatsMap = HashMap<String, DiskStatsCounters>;
+
+// https://www.kernel.org/doc/Documentation/ABI/testing/procfs-diskstats
+pub fn parse_diskstats(content: &str) -> DiskStatsMap {
+    let mut res = HashMap::new() as DiskStatsMap;
+
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        // major, minor, name and at least the 11 original counters
+        if fields.len() < 14 {
+            continue;
+        }
+
+        let name = fields[2];
+        if IGNORED_DEVICE_PREFIXES.iter().any(|p| name.starts_with(p)) {
+            continue;
+        }
+
+        let field = |i: usize| fields[i].parse::<u64>().ok();
+
+        let counters = match (field(3), field(5), field(7), field(9), field(12)) {
+            (
+                Some(reads_completed),
+                Some(sectors_read),
+                Some(writes_completed),
+                Some(sectors_written),
+                Some(io_ticks),
+            ) => DiskStatsCounters {
+                reads_completed,
+                sectors_read,
+                writes_completed,
+                sectors_written,
+                io_ticks,
+            },
+            _ => continue,
+        };
+
+        res.insert(name.to_string(), counters);
+    }
+
+    res
+}
+
+// returns None on systems without procfs, like windows and macos
+pub fn read_diskstats() -> Option<DiskStatsMap> {
+    let content = std::fs::read_to_string(DISKSTATS_PATH).ok()?;
+
+    Some(parse_diskstats(&content))
+}
+
+// sysinfo names the disks by their device path, like "/dev/sda1"
+// while diskstats only has the "sda1" part
+pub fn get_device_name(disk_name: &str) -> String {
+    disk_name.trim_start_matches("/dev/").to_string()
+}
+
+fn per_second(delta: u64, elapsed: &Duration) -> f64 {
+    delta as f64 / elapsed.as_secs_f64()
+}
+
+// computes the throughput of each device between the two samples.
+// `disk_ids` maps the device names to the disk_id of the mounted disks
+pub fn compute_disks_io(
+    previous: &DiskStatsMap,
+    current: &DiskStatsMap,
+    elapsed: &Duration,
+    disk_ids: &HashMap<String, String>,
+) -> Vec<SingleDiskIoInfo> {
+    if elapsed.is_zero() {
+        return vec![];
+    }
+
+    let mut res: Vec<SingleDiskIoInfo> = current
+        .iter()
+        .filter_map(|(device_name, cur)| {
+            let prev = previous.get(device_name)?;
+
+            // the counters went back, so the device was replaced
+            // or they wrapped around, either way the delta is meaningless
+            let reads = cur.reads_completed.checked_sub(prev.reads_completed)?;
+            let sectors_read = cur.sectors_read.checked_sub(prev.sectors_read)?;
+            let writes = cur.writes_completed.checked_sub(prev.writes_completed)?;
+            let sectors_written = cur.sectors_written.checked_sub(prev.sectors_written)?;
+            let io_ticks = cur.io_ticks.checked_sub(prev.io_ticks)?;
+
+            let busy_percent = (io_ticks as f64 / elapsed.as_millis() as f64) * 100.0;
+
+            Some(SingleDiskIoInfo {
+                id: -1,
+                frame_id: -1,
+                device_name: device_name.to_string(),
+                disk_id: disk_ids.get(device_name).map(|id| id.to_string()),
+                read_bytes_per_sec: per_second(sectors_read * SECTOR_SIZE, elapsed),
+                write_bytes_per_sec: per_second(sectors_written * SECTOR_SIZE, elapsed),
+                read_iops: per_second(reads, elapsed),
+                write_iops: per_second(writes, elapsed),
+                busy_percent: busy_percent.min(100.0),
+            })
+        })
+        .collect();
+
+    res.sort_by(|a, b| a.device_name.cmp(&b.device_name));
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::hashmap;
+
+    const DISKSTATS_T0: &str = include_str!("../../tests/fixtures/diskstats/t0");
+    const DISKSTATS_T1: &str = include_str!("../../tests/fixtures/diskstats/t1");
+
+    #[test]
+    fn parse_diskstats_test() {
+        let stats = parse_diskstats(DISKSTATS_T0);
+
+        assert!(!stats.contains_key("loop0"));
+        assert!(!stats.contains_key("loop1"));
+        assert_eq!(stats.len(), 6);
+        assert_eq!(
+            stats.get("nvme0n1"),
+            Some(&DiskStatsCounters {
+                reads_completed: 188912,
+                sectors_read: 12386606,
+                writes_completed: 543212,
+                sectors_written: 30918712,
+                io_ticks: 402310,
+            })
+        );
+
+        // malformed and truncated lines are skipped
+        assert_eq!(
+            parse_diskstats("8 0 sda 1 2 3\n   8 1 sda1 x 0 0 0 0 0 0 0 0 0 0\n").len(),
+            0
+        );
+    }
+
+    #[test]
+    fn compute_disks_io_test() {
+        let previous = parse_diskstats(DISKSTATS_T0);
+        let current = parse_diskstats(DISKSTATS_T1);
+
+        let disk_ids = hashmap! {
+            "nvme0n1p2".to_string() => "rootdiskid".to_string(),
+        };
+
+        let io = compute_disks_io(&previous, &current, &Duration::from_secs(10), &disk_ids);
+
+        // sda's counters were reset, so it's skipped for this tick
+        assert_eq!(
+            io.iter()
+                .map(|d| d.device_name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["dm-0", "nvme0n1", "nvme0n1p1", "nvme0n1p2"]
+        );
+
+        let root = io.iter().find(|d| d.device_name == "nvme0n1p2").unwrap();
+        assert_eq!(root.disk_id, Some("rootdiskid".to_string()));
+        // 40960 sectors read, 204800 written in 10 seconds
+        assert_eq!(root.read_bytes_per_sec, 2097152.0);
+        assert_eq!(root.write_bytes_per_sec, 10485760.0);
+        assert_eq!(root.read_iops, 20.0);
+        assert_eq!(root.write_iops, 100.0);
+        // 5000ms of io in 10 seconds
+        assert_eq!(root.busy_percent, 50.0);
+
+        let idle = io.iter().find(|d| d.device_name == "nvme0n1p1").unwrap();
+        assert_eq!(idle.disk_id, None);
+        assert_eq!(idle.read_iops, 0.0);
+        assert_eq!(idle.busy_percent, 0.0);
+
+        assert!(compute_disks_io(&previous, &current, &Duration::ZERO, &disk_ids).is_empty());
+    }
+
+    #[test]
+    fn get_device_name_test() {
+        assert_eq!(get_device_name("/dev/nvme0n1p2"), "nvme0n1p2");
+        assert_eq!(get_device_name("sda1"), "sda1");
+    }
+}
@@ -0,0 +1,410 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use tokio::net::TcpStream;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+use super::clock::SharedClock;
+use super::models::get_endpoint_checks::{EndpointCheck, EndpointProbe, EndpointTarget};
+use super::notifier::{send_to_all_devices, FcmNotifier, Notifier};
+use super::persistence::insert_endpoint_check;
+use super::scheduler::Schedule;
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+// the outcome of a check, before it's stamped with the probe name and time
+#[derive(Debug, PartialEq)]
+struct CheckOutcome {
+    is_up: bool,
+    latency_ms: Option<f64>,
+    status_code: Option<i64>,
+    message: Option<String>,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+async fn check_http(
+    client: &reqwest::Client,
+    url: &str,
+    expected_status: Option<u16>,
+    body_contains: Option<&str>,
+    timeout: Duration,
+) -> CheckOutcome {
+    let start = Instant::now();
+
+    let response = match client.get(url).timeout(timeout).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return CheckOutcome {
+                is_up: false,
+                latency_ms: None,
+                status_code: None,
+                message: Some(format!("request failed: {}", e)),
+            };
+        }
+    };
+
+    let status = response.status();
+    let status_matches = match expected_status {
+        Some(expected) => status.as_u16() == expected,
+        None => status.is_success(),
+    };
+
+    if !status_matches {
+        return CheckOutcome {
+            is_up: false,
+            latency_ms: Some(elapsed_ms(start)),
+            status_code: Some(status.as_u16() as i64),
+            message: Some(format!("unexpected status {}", status.as_u16())),
+        };
+    }
+
+    // the latency includes reading the body only when it has to be checked
+    let message = match body_contains {
+        Some(needle) => match response.text().await {
+            Ok(body) if body.contains(needle) => None,
+            Ok(_) => Some(format!("the body doesn't contain \"{}\"", needle)),
+            Err(e) => Some(format!("failed to read the body: {}", e)),
+        },
+        None => None,
+    };
+
+    CheckOutcome {
+        is_up: message.is_none(),
+        latency_ms: Some(elapsed_ms(start)),
+        status_code: Some(status.as_u16() as i64),
+        message,
+    }
+}
+
+async fn check_tcp(address: &str, timeout: Duration) -> CheckOutcome {
+    let start = Instant::now();
+
+    let (is_up, message) = match time::timeout(timeout, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => (true, None),
+        Ok(Err(e)) => (false, Some(format!("connection failed: {}", e))),
+        Err(_) => (false, Some("timed out".to_string())),
+    };
+
+    CheckOutcome {
+        is_up,
+        latency_ms: if is_up { Some(elapsed_ms(start)) } else { None },
+        status_code: None,
+        message,
+    }
+}
+
+pub async fn run_endpoint_probe(
+    client: &reqwest::Client,
+    probe: &EndpointProbe,
+    clock: &SharedClock,
+) -> EndpointCheck {
+    let last_check = clock.now_millis();
+    let timeout = Duration::from_secs(probe.timeout_secs);
+
+    let outcome = match &probe.target {
+        EndpointTarget::Http {
+            url,
+            expected_status,
+            body_contains,
+        } => {
+            check_http(
+                client,
+                url,
+                *expected_status,
+                body_contains.as_deref(),
+                timeout,
+            )
+            .await
+        }
+        EndpointTarget::Tcp { address } => check_tcp(address, timeout).await,
+    };
+
+    EndpointCheck {
+        id: -1,
+        name: probe.name.to_string(),
+        is_up: outcome.is_up,
+        latency_ms: outcome.latency_ms,
+        status_code: outcome.status_code,
+        message: outcome.message,
+        last_check,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum EndpointTransition {
+    None,
+    // failed `failure_threshold` times in a row
+    WentDown,
+    // up again after being reported as down
+    CameBack,
+}
+
+// counts the consecutive failures, so a single failed check doesn't notify
+#[derive(Debug, Default)]
+struct FailureTracker {
+    consecutive_failures: i64,
+    reported_down: bool,
+}
+
+impl FailureTracker {
+    fn register(&mut self, is_up: bool, failure_threshold: i64) -> EndpointTransition {
+        if is_up {
+            self.consecutive_failures = 0;
+
+            if self.reported_down {
+                self.reported_down = false;
+                return EndpointTransition::CameBack;
+            }
+
+            return EndpointTransition::None;
+        }
+
+        self.consecutive_failures += 1;
+
+        if !self.reported_down && self.consecutive_failures >= failure_threshold {
+            self.reported_down = true;
+            return EndpointTransition::WentDown;
+        }
+
+        EndpointTransition::None
+    }
+}
+
+async fn send_endpoint_transition_notification(
+    probe: &EndpointProbe,
+    check: &EndpointCheck,
+    transition: &EndpointTransition,
+    notifier: &impl Notifier,
+) {
+    let (message, notification_type) = match transition {
+        EndpointTransition::WentDown => {
+            warn!("endpoint {} is down", probe.name);
+
+            (
+                NotificationMessage {
+                    title: format!("IMPORTANT: {} is down", probe.name),
+                    body: format!(
+                        "the endpoint check {} failed {} times in a row: {}",
+                        probe.name,
+                        probe.failure_threshold,
+                        check.message.as_deref().unwrap_or_default()
+                    ),
+                },
+                NotificationType::EndpointDown,
+            )
+        }
+        EndpointTransition::CameBack => {
+            info!("endpoint {} is back up", probe.name);
+
+            (
+                NotificationMessage {
+                    title: format!("{} is back up", probe.name),
+                    body: format!("the endpoint check {} succeeded again", probe.name),
+                },
+                NotificationType::EndpointUp,
+            )
+        }
+        EndpointTransition::None => return,
+    };
+
+    let not_res = send_to_all_devices(notifier, &message, &notification_type).await;
+
+    if let Err(not_res) = not_res {
+        error!(
+            "Sending endpoint check notification resulted with the following error: {}",
+            not_res
+        );
+    }
+}
+
+// every probe runs on its own task, so a slow endpoint doesn't hold the others back
+pub(super) fn spawn_endpoint_probes(
+    probes: &[EndpointProbe],
+    token: &CancellationToken,
+    clock: &SharedClock,
+) {
+    if probes.is_empty() {
+        return;
+    }
+
+    let client = reqwest::Client::new();
+
+    for probe in probes {
+        let probe = probe.clone();
+        let client = client.clone();
+        let clock = Arc::clone(clock);
+        let mut schedule = Schedule::new(
+            &format!("endpoint_probe:{}", probe.name),
+            Duration::from_secs(probe.interval_secs),
+            token,
+        );
+
+        tokio::spawn(async move {
+            let mut tracker = FailureTracker::default();
+
+            while schedule.tick().await {
+                let started = Instant::now();
+
+                let check = run_endpoint_probe(&client, &probe, &clock).await;
+
+                if let Err(e) = insert_endpoint_check(&check).await {
+                    error!("failed to insert endpoint check: {}", e);
+                }
+
+                let transition = tracker.register(check.is_up, probe.failure_threshold);
+                send_endpoint_transition_notification(&probe, &check, &transition, &FcmNotifier)
+                    .await;
+
+                schedule.record(started.elapsed());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::clock::FakeClock;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+
+    // a stand-in for the checked service, on a random local port
+    async fn start_test_server() -> SocketAddr {
+        async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let res = match req.uri().path() {
+                "/health" => Response::new(Body::from("status: all good")),
+                _ => Response::builder()
+                    .status(404)
+                    .body(Body::from("not found"))
+                    .unwrap(),
+            };
+
+            Ok(res)
+        }
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(handle))
+        }));
+        let addr = server.local_addr();
+
+        tokio::spawn(server);
+
+        addr
+    }
+
+    fn probe(target: EndpointTarget) -> EndpointProbe {
+        EndpointProbe {
+            name: "test".to_string(),
+            target,
+            interval_secs: 30,
+            timeout_secs: 2,
+            failure_threshold: 3,
+        }
+    }
+
+    fn http(
+        url: String,
+        expected_status: Option<u16>,
+        body_contains: Option<&str>,
+    ) -> EndpointTarget {
+        EndpointTarget::Http {
+            url,
+            expected_status,
+            body_contains: body_contains.map(|b| b.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn run_endpoint_probe_test() {
+        let addr = start_test_server().await;
+        let client = reqwest::Client::new();
+        let clock: SharedClock = Arc::new(FakeClock::new(1000));
+        let health_url = format!("http://{}/health", addr);
+
+        let ok = run_endpoint_probe(
+            &client,
+            &probe(http(health_url.clone(), None, Some("all good"))),
+            &clock,
+        )
+        .await;
+        assert!(ok.is_up);
+        assert_eq!(ok.status_code, Some(200));
+        assert!(ok.latency_ms.is_some());
+        assert_eq!(ok.last_check, 1000);
+
+        let wrong_body = run_endpoint_probe(
+            &client,
+            &probe(http(health_url.clone(), None, Some("degraded"))),
+            &clock,
+        )
+        .await;
+        assert!(!wrong_body.is_up);
+
+        let missing = run_endpoint_probe(
+            &client,
+            &probe(http(format!("http://{}/missing", addr), None, None)),
+            &clock,
+        )
+        .await;
+        assert!(!missing.is_up);
+        assert_eq!(missing.status_code, Some(404));
+
+        let expected_missing = run_endpoint_probe(
+            &client,
+            &probe(http(format!("http://{}/missing", addr), Some(404), None)),
+            &clock,
+        )
+        .await;
+        assert!(expected_missing.is_up);
+
+        let tcp = run_endpoint_probe(
+            &client,
+            &probe(EndpointTarget::Tcp {
+                address: addr.to_string(),
+            }),
+            &clock,
+        )
+        .await;
+        assert!(tcp.is_up);
+
+        // a port nothing listens on
+        let closed_addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let closed = run_endpoint_probe(
+            &client,
+            &probe(EndpointTarget::Tcp {
+                address: closed_addr.to_string(),
+            }),
+            &clock,
+        )
+        .await;
+        assert!(!closed.is_up);
+        assert_eq!(closed.latency_ms, None);
+    }
+
+    #[test]
+    fn failure_tracker_test() {
+        let mut tracker = FailureTracker::default();
+
+        assert_eq!(tracker.register(true, 3), EndpointTransition::None);
+        assert_eq!(tracker.register(false, 3), EndpointTransition::None);
+        assert_eq!(tracker.register(false, 3), EndpointTransition::None);
+        assert_eq!(tracker.register(false, 3), EndpointTransition::WentDown);
+        // only reported once
+        assert_eq!(tracker.register(false, 3), EndpointTransition::None);
+        assert_eq!(tracker.register(true, 3), EndpointTransition::CameBack);
+
+        // a failure streak that's shorter than the threshold is not reported
+        assert_eq!(tracker.register(false, 3), EndpointTransition::None);
+        assert_eq!(tracker.register(true, 3), EndpointTransition::None);
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use log::error;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use super::models::get_collector_stats::WriteStats;
+use super::persistence::{insert_status_frames, StatusFrame};
+
+// the frames waiting to be written, the collectors wait for room once it's full
+const FRAME_QUEUE_SIZE: usize = 256;
+
+// the collectors that run on the same tick start together, so their
+// frames arrive within this window and are written in the same batch
+const BATCH_WINDOW: Duration = Duration::from_millis(100);
+
+pub(super) type FrameSender = mpsc::Sender<StatusFrame>;
+
+static WRITE_STATS: OnceLock<Mutex<WriteStats>> = OnceLock::new();
+
+fn get_stats() -> &'static Mutex<WriteStats> {
+    WRITE_STATS.get_or_init(|| Mutex::new(WriteStats::default()))
+}
+
+pub fn get_write_stats() -> WriteStats {
+    get_stats().lock().unwrap().clone()
+}
+
+fn record_write(
+    stats: &mut WriteStats,
+    frames: usize,
+    rows: Option<usize>,
+    latency: Duration,
+    last_write: i64,
+) {
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+
+    stats.avg_latency_ms =
+        (stats.avg_latency_ms * stats.batches as f64 + latency_ms) / (stats.batches + 1) as f64;
+    stats.batches += 1;
+    stats.last_latency_ms = latency_ms;
+    stats.max_latency_ms = stats.max_latency_ms.max(latency_ms);
+    stats.last_write = last_write;
+
+    match rows {
+        Some(rows) => {
+            stats.frames += frames as i64;
+            stats.rows += rows as i64;
+        }
+        None => stats.failures += 1,
+    }
+}
+
+async fn write_frames(frames: &[StatusFrame]) {
+    let started = Instant::now();
+
+    let rows = match insert_status_frames(frames).await {
+        Ok(rows) => Some(rows),
+        Err(e) => {
+            error!("failed to insert {} status frames: {}", frames.len(), e);
+            None
+        }
+    };
+
+    record_write(
+        &mut get_stats().lock().unwrap(),
+        frames.len(),
+        rows,
+        started.elapsed(),
+        Utc::now().timestamp_millis(),
+    );
+}
+
+fn drain(receiver: &mut mpsc::Receiver<StatusFrame>, frames: &mut Vec<StatusFrame>) {
+    while let Ok(frame) = receiver.try_recv() {
+        frames.push(frame);
+    }
+}
+
+// writes the frames the collectors send in batches, one transaction each
+pub(super) fn spawn_frame_writer(token: &CancellationToken) -> FrameSender {
+    let (sender, mut receiver) = mpsc::channel(FRAME_QUEUE_SIZE);
+    let token = token.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let first = tokio::select! {
+                _ = token.cancelled() => break,
+                frame = receiver.recv() => match frame {
+                    Some(frame) => frame,
+                    None => break,
+                },
+            };
+
+            tokio::time::sleep(BATCH_WINDOW).await;
+
+            let mut frames = vec![first];
+            drain(&mut receiver, &mut frames);
+
+            write_frames(&frames).await;
+        }
+
+        // the frames sent before the monitor was stopped
+        receiver.close();
+        let mut frames = vec![];
+        drain(&mut receiver, &mut frames);
+
+        if !frames.is_empty() {
+            write_frames(&frames).await;
+        }
+    });
+
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_write_test() {
+        let mut stats = WriteStats::default();
+
+        record_write(&mut stats, 3, Some(12), Duration::from_millis(20), 1);
+        record_write(&mut stats, 2, None, Duration::from_millis(50), 2);
+        record_write(&mut stats, 1, Some(5), Duration::from_millis(20), 3);
+
+        assert_eq!(stats.batches, 3);
+        assert_eq!(stats.frames, 4);
+        assert_eq!(stats.rows, 17);
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.last_latency_ms, 20.0);
+        assert_eq!(stats.max_latency_ms, 50.0);
+        assert_eq!(stats.avg_latency_ms, 30.0);
+        assert_eq!(stats.last_write, 3);
+    }
+}
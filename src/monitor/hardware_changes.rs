@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+
+use log::{error, warn};
+
+use super::models::get_hardware_changes::{
+    HardwareChangeEvent, HardwareChangeKind, HardwareInfoDiff, InventoryDiff,
+};
+use super::models::get_hardware_info::{
+    HardwareComponentInfo, HardwareCpuInfo, HardwareDiskInfo, HardwareInfo, HardwareMemInfo,
+};
+use super::notifier::{send_to_all_devices, Notifier};
+use super::persistence::insert_hardware_info_changes;
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+// the reported total memory changes slightly between kernel versions,
+// so smaller differences than this are not counted as a change
+const MEM_TOTAL_TOLERANCE: f64 = 0.01;
+
+trait InventoryItem: Clone {
+    fn get_hardware_id(&self) -> &str;
+    // whether the two are the same version of the same hardware,
+    // the ids, the last checks and the validity are ignored
+    fn is_same_version(&self, other: &Self) -> bool;
+}
+
+impl InventoryItem for HardwareCpuInfo {
+    fn get_hardware_id(&self) -> &str {
+        &self.cpu_id
+    }
+
+    fn is_same_version(&self, other: &Self) -> bool {
+        self.core_count == other.core_count
+            && self.vendor_id == other.vendor_id
+            && self.brand == other.brand
+    }
+}
+
+impl InventoryItem for HardwareDiskInfo {
+    fn get_hardware_id(&self) -> &str {
+        &self.disk_id
+    }
+
+    fn is_same_version(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.fs_type == other.fs_type
+            && self.kind == other.kind
+            && self.is_removable == other.is_removable
+            && self.mount_point == other.mount_point
+            && self.total_space == other.total_space
+    }
+}
+
+impl InventoryItem for HardwareMemInfo {
+    fn get_hardware_id(&self) -> &str {
+        &self.mem_id
+    }
+
+    fn is_same_version(&self, other: &Self) -> bool {
+        let difference = (self.total_space - other.total_space).abs() as f64;
+
+        difference <= self.total_space.max(other.total_space) as f64 * MEM_TOTAL_TOLERANCE
+    }
+}
+
+impl InventoryItem for HardwareComponentInfo {
+    fn get_hardware_id(&self) -> &str {
+        &self.component_id
+    }
+
+    fn is_same_version(&self, other: &Self) -> bool {
+        self.label == other.label && self.critical == other.critical
+    }
+}
+
+fn get_inventory_diff<T: InventoryItem>(previous: &[T], current: &[T]) -> InventoryDiff<T> {
+    let previous_by_id: HashMap<&str, &T> =
+        previous.iter().map(|i| (i.get_hardware_id(), i)).collect();
+    let current_by_id: HashMap<&str, &T> =
+        current.iter().map(|i| (i.get_hardware_id(), i)).collect();
+
+    let mut diff = InventoryDiff {
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+    };
+
+    for item in current {
+        match previous_by_id.get(item.get_hardware_id()) {
+            Some(prev) if prev.is_same_version(item) => {}
+            Some(prev) => diff.changed.push(((*prev).clone(), item.clone())),
+            None => diff.added.push(item.clone()),
+        }
+    }
+
+    diff.removed = previous
+        .iter()
+        .filter(|i| !current_by_id.contains_key(i.get_hardware_id()))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+pub fn get_hardware_diff(previous: &HardwareInfo, current: &HardwareInfo) -> HardwareInfoDiff {
+    HardwareInfoDiff {
+        cpu_info: get_inventory_diff(&previous.cpu_info, &current.cpu_info),
+        disks_info: get_inventory_diff(&previous.disks_info, &current.disks_info),
+        mem_info: get_inventory_diff(&previous.mem_info, &current.mem_info),
+        components_info: get_inventory_diff(&previous.components_info, &current.components_info),
+    }
+}
+
+fn bytes_to_gb(bytes: i64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0 / 1024.0
+}
+
+fn describe_cpu(info: &HardwareCpuInfo) -> String {
+    format!("{} with {} cores", info.brand, info.core_count)
+}
+
+fn describe_disk(info: &HardwareDiskInfo) -> String {
+    format!("{} mounted at {}", info.name, info.mount_point)
+}
+
+fn describe_mem(info: &HardwareMemInfo) -> String {
+    format!("{:.1} GB of memory", bytes_to_gb(info.total_space))
+}
+
+// the events of a single kind of hardware
+fn get_inventory_events<T: InventoryItem>(
+    diff: &InventoryDiff<T>,
+    kinds: [HardwareChangeKind; 3],
+    describe: impl Fn(&T) -> String,
+    describe_change: impl Fn(&T, &T) -> String,
+    last_check: i64,
+) -> Vec<HardwareChangeEvent> {
+    let [added_kind, removed_kind, changed_kind] = kinds;
+
+    let event = |kind: &HardwareChangeKind, item: &T, description: String| HardwareChangeEvent {
+        id: -1,
+        kind: kind.clone(),
+        hardware_id: item.get_hardware_id().to_string(),
+        description,
+        last_check,
+    };
+
+    diff.removed
+        .iter()
+        .map(|i| event(&removed_kind, i, describe(i)))
+        .chain(
+            diff.added
+                .iter()
+                .map(|i| event(&added_kind, i, describe(i))),
+        )
+        .chain(
+            diff.changed
+                .iter()
+                .map(|(prev, cur)| event(&changed_kind, cur, describe_change(prev, cur))),
+        )
+        .collect()
+}
+
+pub fn get_change_events(diff: &HardwareInfoDiff, last_check: i64) -> Vec<HardwareChangeEvent> {
+    let mut events = get_inventory_events(
+        &diff.cpu_info,
+        [
+            HardwareChangeKind::CpuAdded,
+            HardwareChangeKind::CpuRemoved,
+            HardwareChangeKind::CpuChanged,
+        ],
+        describe_cpu,
+        |prev, cur| {
+            format!(
+                "{} went from {} to {} cores",
+                cur.brand, prev.core_count, cur.core_count
+            )
+        },
+        last_check,
+    );
+
+    events.extend(get_inventory_events(
+        &diff.disks_info,
+        [
+            HardwareChangeKind::DiskAdded,
+            HardwareChangeKind::DiskRemoved,
+            HardwareChangeKind::DiskChanged,
+        ],
+        describe_disk,
+        |prev, cur| {
+            if prev.total_space != cur.total_space {
+                format!(
+                    "{} went from {:.1} GB to {:.1} GB",
+                    describe_disk(cur),
+                    bytes_to_gb(prev.total_space),
+                    bytes_to_gb(cur.total_space)
+                )
+            } else {
+                describe_disk(cur)
+            }
+        },
+        last_check,
+    ));
+
+    events.extend(get_inventory_events(
+        &diff.mem_info,
+        [
+            HardwareChangeKind::MemAdded,
+            HardwareChangeKind::MemRemoved,
+            HardwareChangeKind::MemChanged,
+        ],
+        describe_mem,
+        |prev, cur| {
+            format!(
+                "memory went from {:.1} GB to {:.1} GB",
+                bytes_to_gb(prev.total_space),
+                bytes_to_gb(cur.total_space)
+            )
+        },
+        last_check,
+    ));
+
+    events.extend(get_inventory_events(
+        &diff.components_info,
+        [
+            HardwareChangeKind::ComponentAdded,
+            HardwareChangeKind::ComponentRemoved,
+            HardwareChangeKind::ComponentChanged,
+        ],
+        |c| c.label.to_string(),
+        |prev, cur| {
+            format!(
+                "{} critical temperature went from {:?} to {:?}",
+                cur.label, prev.critical, cur.critical
+            )
+        },
+        last_check,
+    ));
+
+    events
+}
+
+// the removed disks get their own notification, the cpu and the memory changes share one,
+// the added disks and the sensors are only recorded
+fn get_notification_messages(
+    events: &[HardwareChangeEvent],
+) -> Vec<(NotificationMessage, NotificationType)> {
+    let mut messages = vec![];
+
+    let removed_disks = events
+        .iter()
+        .filter(|e| e.kind == HardwareChangeKind::DiskRemoved)
+        .map(|e| e.description.as_str())
+        .collect::<Vec<&str>>();
+    if !removed_disks.is_empty() {
+        let title = if removed_disks.len() == 1 {
+            "IMPORTANT: a disk was removed".to_string()
+        } else {
+            format!("IMPORTANT: {} disks were removed", removed_disks.len())
+        };
+
+        messages.push((
+            NotificationMessage {
+                title,
+                body: removed_disks.join(", "),
+            },
+            NotificationType::DiskRemoved,
+        ));
+    }
+
+    let hardware_changes = events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e.kind,
+                HardwareChangeKind::CpuAdded
+                    | HardwareChangeKind::CpuRemoved
+                    | HardwareChangeKind::CpuChanged
+                    | HardwareChangeKind::MemAdded
+                    | HardwareChangeKind::MemRemoved
+                    | HardwareChangeKind::MemChanged
+            )
+        })
+        .map(|e| e.description.as_str())
+        .collect::<Vec<&str>>();
+    if !hardware_changes.is_empty() {
+        messages.push((
+            NotificationMessage {
+                title: "IMPORTANT: the hardware changed".to_string(),
+                body: hardware_changes.join(", "),
+            },
+            NotificationType::HardwareChanged,
+        ));
+    }
+
+    messages
+}
+
+// stores the new inventory versions with their change events, and notifies about them.
+// the first inventory of a new install is only stored, as there's nothing it changed from
+pub async fn handle_hardware_changes(
+    previous: &HardwareInfo,
+    diff: &HardwareInfoDiff,
+    notifier: &impl Notifier,
+    changed_at: i64,
+) -> Result<(), sqlx::Error> {
+    let is_first_inventory = previous.cpu_info.is_empty()
+        && previous.disks_info.is_empty()
+        && previous.mem_info.is_empty()
+        && previous.components_info.is_empty();
+
+    let events = if is_first_inventory {
+        vec![]
+    } else {
+        get_change_events(diff, changed_at)
+    };
+
+    insert_hardware_info_changes(diff, &events, changed_at).await?;
+
+    if !events.is_empty() {
+        warn!("the hardware changed: {:?}", events);
+    }
+
+    for (message, notification_type) in get_notification_messages(&events) {
+        let not_res = send_to_all_devices(notifier, &message, &notification_type).await;
+
+        if let Err(not_res) = not_res {
+            error!(
+                "Sending hardware change notification resulted with the following error: {}",
+                not_res
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: i64 = 1024 * 1024 * 1024;
+
+    fn cpu(cpu_id: &str, core_count: i32) -> HardwareCpuInfo {
+        HardwareCpuInfo {
+            id: -1,
+            cpu_id: cpu_id.to_string(),
+            core_count,
+            vendor_id: "GenuineIntel".to_string(),
+            brand: format!("cpu {}", cpu_id),
+            last_check: -1,
+            valid_from: -1,
+            valid_to: None,
+        }
+    }
+
+    fn disk(disk_id: &str, name: &str, mount_point: &str) -> HardwareDiskInfo {
+        HardwareDiskInfo {
+            id: -1,
+            disk_id: disk_id.to_string(),
+            name: name.to_string(),
+            fs_type: "ext4".to_string(),
+            kind: "SSD".to_string(),
+            is_removable: false,
+            mount_point: mount_point.to_string(),
+            total_space: 256 * GB,
+            last_check: -1,
+            valid_from: -1,
+            valid_to: None,
+        }
+    }
+
+    fn mem(total_space: i64) -> HardwareMemInfo {
+        HardwareMemInfo {
+            id: -1,
+            mem_id: "1".to_string(),
+            total_space,
+            last_check: -1,
+            valid_from: -1,
+            valid_to: None,
+        }
+    }
+
+    #[test]
+    fn get_hardware_diff_test() {
+        let previous = HardwareInfo {
+            cpu_info: vec![cpu("a", 8)],
+            disks_info: vec![
+                disk("a", "/dev/sda1", "/"),
+                disk("b", "/dev/sdb1", "/mnt/backup"),
+            ],
+            mem_info: vec![mem(32 * GB)],
+            components_info: vec![],
+        };
+        let current = HardwareInfo {
+            cpu_info: vec![cpu("a", 8)],
+            disks_info: vec![
+                disk("a", "/dev/sda1", "/"),
+                disk("c", "/dev/sdc1", "/media/usb"),
+            ],
+            mem_info: vec![mem(16 * GB)],
+            components_info: vec![],
+        };
+
+        let diff = get_hardware_diff(&previous, &current);
+        assert!(diff.cpu_info.is_empty());
+
+        let events = get_change_events(&diff, 1);
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.kind.clone(), e.description.as_str()))
+                .collect::<Vec<(HardwareChangeKind, &str)>>(),
+            vec![
+                (
+                    HardwareChangeKind::DiskRemoved,
+                    "/dev/sdb1 mounted at /mnt/backup"
+                ),
+                (
+                    HardwareChangeKind::DiskAdded,
+                    "/dev/sdc1 mounted at /media/usb"
+                ),
+                (
+                    HardwareChangeKind::MemChanged,
+                    "memory went from 32.0 GB to 16.0 GB"
+                ),
+            ]
+        );
+
+        let messages = get_notification_messages(&events);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].0.title, "IMPORTANT: a disk was removed");
+        assert_eq!(messages[1].0.body, "memory went from 32.0 GB to 16.0 GB");
+
+        // a small difference in the total memory is not a change
+        let current = HardwareInfo {
+            mem_info: vec![mem(32 * GB - 1024 * 1024)],
+            ..previous.clone()
+        };
+        assert!(get_hardware_diff(&previous, &current).is_empty());
+
+        // a resized disk keeps its id
+        let current = HardwareInfo {
+            disks_info: vec![
+                HardwareDiskInfo {
+                    total_space: 512 * GB,
+                    ..disk("a", "/dev/sda1", "/")
+                },
+                disk("b", "/dev/sdb1", "/mnt/backup"),
+            ],
+            ..previous.clone()
+        };
+        let events = get_change_events(&get_hardware_diff(&previous, &current), 1);
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.kind.clone(), e.description.as_str()))
+                .collect::<Vec<(HardwareChangeKind, &str)>>(),
+            vec![(
+                HardwareChangeKind::DiskChanged,
+                "/dev/sda1 mounted at / went from 256.0 GB to 512.0 GB"
+            )]
+        );
+
+        let current = HardwareInfo {
+            cpu_info: vec![cpu("b", 16)],
+            ..previous.clone()
+        };
+        let events = get_change_events(&get_hardware_diff(&previous, &current), 1);
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| e.kind.clone())
+                .collect::<Vec<HardwareChangeKind>>(),
+            vec![HardwareChangeKind::CpuRemoved, HardwareChangeKind::CpuAdded]
+        );
+    }
+}
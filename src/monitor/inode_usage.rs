@@ -0,0 +1,32 @@
+use std::path::Path;
+
+// the total and the free inode count of the filesystem mounted at the given path
+#[cfg(unix)]
+pub fn get_inode_usage(mount_point: &Path) -> Option<(i64, i64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(mount_point.as_os_str().as_bytes()).ok()?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid nul terminated string and `stat` is a valid out pointer
+    let res = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+
+    if res != 0 {
+        return None;
+    }
+
+    // filesystems that allocate inodes dynamically, like btrfs, report 0
+    if stat.f_files == 0 {
+        return None;
+    }
+
+    // sqlx doesn't support u64
+    Some((stat.f_files as i64, stat.f_ffree as i64))
+}
+
+// windows doesn't have inodes
+#[cfg(not(unix))]
+pub fn get_inode_usage(_mount_point: &Path) -> Option<(i64, i64)> {
+    None
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use regex::Regex;
+use tokio_util::sync::CancellationToken;
+
+use super::clock::SharedClock;
+use super::config_exceeds::{get_send_notification_interval, is_cooldown_over};
+use super::models::get_log_matches::{LogMatch, LogMatchCount, LogWatch};
+use super::notifier::{FcmNotifier, Notifier};
+use super::persistence::insert_log_matches;
+use super::scheduler::Schedule;
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+// a log that grew by more than this in a single interval is read in several intervals
+const MAX_READ_BYTES: u64 = 8 * 1024 * 1024;
+
+// the matched line is cut to this many characters in the notification
+const MAX_NOTIFICATION_LINE_LEN: usize = 200;
+
+#[cfg(unix)]
+fn get_file_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.ino())
+}
+
+// there are no inodes on windows, so only the truncation is detected
+#[cfg(not(unix))]
+fn get_file_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+// reads the lines appended to a file since the last read, following it
+// when it's rotated (replaced with a new file) or truncated
+pub struct LogTailer {
+    path: PathBuf,
+    offset: u64,
+    file_id: Option<u64>,
+    // the last line, until its newline is written
+    partial: String,
+}
+
+impl LogTailer {
+    // starts from the end of the file, the lines that are already there are not matched
+    pub fn new(path: &str) -> Self {
+        let (offset, file_id) = match fs::metadata(path) {
+            Ok(metadata) => (metadata.len(), get_file_id(&metadata)),
+            Err(_) => (0, None),
+        };
+
+        Self {
+            path: PathBuf::from(path),
+            offset,
+            file_id,
+            partial: String::new(),
+        }
+    }
+
+    pub fn read_new_lines(&mut self) -> std::io::Result<Vec<String>> {
+        let metadata = fs::metadata(&self.path)?;
+        let file_id = get_file_id(&metadata);
+
+        // the lines that were left unread in a rotated file are lost
+        if file_id != self.file_id || metadata.len() < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        self.file_id = file_id;
+
+        if metadata.len() == self.offset {
+            return Ok(vec![]);
+        }
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+
+        let mut buf = Vec::new();
+        file.take(MAX_READ_BYTES).read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+
+        let content = format!("{}{}", self.partial, String::from_utf8_lossy(&buf));
+        let mut lines: Vec<String> = content.split('\n').map(|l| l.to_string()).collect();
+
+        // whatever is after the last newline is not a complete line yet
+        self.partial = lines.pop().unwrap_or_default();
+
+        Ok(lines
+            .into_iter()
+            .map(|l| l.trim_end_matches('\r').to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+}
+
+// a line matching several patterns is recorded once for each of them
+pub fn match_lines(
+    watch: &str,
+    patterns: &[Regex],
+    lines: &[String],
+    last_check: i64,
+) -> (Vec<LogMatch>, Vec<LogMatchCount>) {
+    let mut matches: Vec<LogMatch> = vec![];
+    let mut counts: Vec<LogMatchCount> = vec![];
+
+    for pattern in patterns {
+        let matched: Vec<&String> = lines.iter().filter(|l| pattern.is_match(l)).collect();
+
+        if matched.is_empty() {
+            continue;
+        }
+
+        counts.push(LogMatchCount {
+            id: -1,
+            watch: watch.to_string(),
+            pattern: pattern.to_string(),
+            count: matched.len() as i64,
+            last_check,
+        });
+
+        matches.extend(matched.into_iter().map(|line| LogMatch {
+            id: -1,
+            watch: watch.to_string(),
+            pattern: pattern.to_string(),
+            line: line.to_string(),
+            last_check,
+        }));
+    }
+
+    (matches, counts)
+}
+
+fn get_notification_message(watch: &str, matches: &[LogMatch]) -> NotificationMessage {
+    let first = &matches[0];
+    let mut line: String = first.line.chars().take(MAX_NOTIFICATION_LINE_LEN).collect();
+    if line.len() < first.line.len() {
+        line.push_str("...");
+    }
+
+    let body = if matches.len() > 1 {
+        format!("{} (and {} more matches)", line, matches.len() - 1)
+    } else {
+        line
+    };
+
+    NotificationMessage {
+        title: format!("IMPORTANT: {} log matched \"{}\"", watch, first.pattern),
+        body,
+    }
+}
+
+// device id -> when the matches of a watch were last sent to it. every watch
+// has its own, so a noisy log doesn't hold back the matches of the others.
+// kept in memory, so after a restart the first match is sent again
+type LogWatchCooldowns = HashMap<String, i64>;
+
+// sent to every device whose cooldown for the watch has passed
+async fn send_log_match_notification(
+    watch: &str,
+    matches: &[LogMatch],
+    cooldowns: &mut LogWatchCooldowns,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let devices = match notifier.fetch_devices().await {
+        Ok(devices) => devices,
+        Err(e) => {
+            error!("failed to fetch monitor configs: {}", e);
+            return;
+        }
+    };
+
+    let message = get_notification_message(watch, matches);
+    let now = clock.now_millis();
+
+    for (device_id, fcm_token) in devices {
+        if let Some(&last_sent_at) = cooldowns.get(&device_id) {
+            if !is_cooldown_over(last_sent_at, now) {
+                warn!("did not send log match notification of {} to device {} because one has already been sent in the last {} seconds", watch, device_id, get_send_notification_interval().num_seconds());
+
+                continue;
+            }
+        }
+
+        let not_res = notifier
+            .send(
+                &device_id,
+                &fcm_token,
+                &message,
+                &NotificationType::LogMatch,
+            )
+            .await;
+
+        match not_res {
+            Ok(_) => {
+                cooldowns.insert(device_id, now);
+            }
+            Err(not_res) => error!(
+                "Sending log match notification resulted with the following error: {}",
+                not_res
+            ),
+        }
+    }
+}
+
+// reads the new lines of the log, and stores and notifies about the matching ones
+async fn check_log(
+    watch: &LogWatch,
+    patterns: &[Regex],
+    tailer: &mut LogTailer,
+    is_readable: &mut bool,
+    cooldowns: &mut LogWatchCooldowns,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let lines = match tailer.read_new_lines() {
+        Ok(lines) => {
+            if !*is_readable {
+                info!("the log {} is readable again", watch.path);
+                *is_readable = true;
+            }
+            lines
+        }
+        Err(e) => {
+            // so a missing file is only reported once, not every interval
+            if *is_readable {
+                warn!("failed to read the log {}: {}", watch.path, e);
+                *is_readable = false;
+            }
+            return;
+        }
+    };
+
+    let (matches, counts) = match_lines(&watch.name, patterns, &lines, clock.now_millis());
+
+    if matches.is_empty() {
+        return;
+    }
+
+    if let Err(e) = insert_log_matches(&watch.name, &matches, &counts, watch.max_history).await {
+        error!("failed to insert log matches: {}", e);
+    }
+
+    send_log_match_notification(&watch.name, &matches, cooldowns, notifier, clock).await;
+}
+
+// every watch runs on its own task, with its own interval
+pub(super) fn spawn_log_watches(
+    watches: &[LogWatch],
+    token: &CancellationToken,
+    clock: &SharedClock,
+) {
+    for watch in watches {
+        let patterns = match watch
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<Vec<Regex>, regex::Error>>()
+        {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                error!("invalid pattern in the log watch {}: {}", watch.name, e);
+                continue;
+            }
+        };
+
+        let watch = watch.clone();
+        let mut schedule = Schedule::new(
+            &format!("log_watch:{}", watch.name),
+            Duration::from_secs(watch.interval_secs),
+            token,
+        );
+
+        let clock = Arc::clone(clock);
+
+        tokio::spawn(async move {
+            let mut tailer = LogTailer::new(&watch.path);
+            let mut is_readable = true;
+            let mut cooldowns = LogWatchCooldowns::new();
+
+            while schedule.tick().await {
+                let started = Instant::now();
+
+                check_log(
+                    &watch,
+                    &patterns,
+                    &mut tailer,
+                    &mut is_readable,
+                    &mut cooldowns,
+                    &FcmNotifier,
+                    &clock,
+                )
+                .await;
+
+                schedule.record(started.elapsed());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use crate::monitor::clock::FakeClock;
+    use crate::monitor::notifier::RecordingNotifier;
+
+    fn temp_log_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("remon-{}-{}.log", name, std::process::id()))
+    }
+
+    fn append(path: &PathBuf, content: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn log_tailer_test() {
+        let path = temp_log_path("tailer");
+        let _ = fs::remove_file(&path);
+        append(&path, "old line\n");
+
+        let mut tailer = LogTailer::new(path.to_str().unwrap());
+
+        // the existing lines are skipped
+        assert!(tailer.read_new_lines().unwrap().is_empty());
+
+        append(&path, "first\nsecond\nthi");
+        assert_eq!(tailer.read_new_lines().unwrap(), vec!["first", "second"]);
+
+        // the partial line is completed
+        append(&path, "rd\r\n");
+        assert_eq!(tailer.read_new_lines().unwrap(), vec!["third"]);
+
+        // truncated, like with logrotate's copytruncate
+        fs::write(&path, "after truncate\n").unwrap();
+        assert_eq!(tailer.read_new_lines().unwrap(), vec!["after truncate"]);
+
+        fs::remove_file(&path).unwrap();
+        assert!(tailer.read_new_lines().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn log_tailer_rotation_test() {
+        let path = temp_log_path("rotation");
+        let rotated_path = temp_log_path("rotation-1");
+        let _ = fs::remove_file(&path);
+        append(
+            &path,
+            "before rotation, and a long line so the new file is shorter\n",
+        );
+
+        let mut tailer = LogTailer::new(path.to_str().unwrap());
+
+        // renamed, and a new file is created in its place,
+        // the new file being longer than the offset shouldn't matter
+        fs::rename(&path, &rotated_path).unwrap();
+        append(&path, "new file\n");
+        assert_eq!(tailer.read_new_lines().unwrap(), vec!["new file"]);
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&rotated_path).unwrap();
+    }
+
+    #[test]
+    fn match_lines_test() {
+        let patterns = vec![
+            Regex::new("Out of memory").unwrap(),
+            Regex::new(r"\bERROR\b").unwrap(),
+        ];
+        let lines = vec![
+            "kernel: Out of memory: Killed process 1234 (java)".to_string(),
+            "app: ERROR failed to connect".to_string(),
+            "app: NOERROR here".to_string(),
+            "app: ERROR Out of memory".to_string(),
+        ];
+
+        let (matches, counts) = match_lines("syslog", &patterns, &lines, 1);
+
+        assert_eq!(
+            counts
+                .iter()
+                .map(|c| (c.pattern.as_str(), c.count))
+                .collect::<Vec<(&str, i64)>>(),
+            vec![("Out of memory", 2), (r"\bERROR\b", 2)]
+        );
+        assert_eq!(matches.len(), 4);
+        assert_eq!(matches[0].line, lines[0]);
+
+        let message = get_notification_message("syslog", &matches);
+        assert_eq!(
+            message.body,
+            "kernel: Out of memory: Killed process 1234 (java) (and 3 more matches)"
+        );
+    }
+
+    #[tokio::test]
+    async fn send_log_match_notification_test() {
+        let fake_clock = Arc::new(FakeClock::new(0));
+        let clock: SharedClock = fake_clock.clone();
+        let notifier = RecordingNotifier::new(Arc::clone(&clock)).with_device("phone", "token");
+
+        let patterns = vec![Regex::new("ERROR").unwrap()];
+        let lines = vec!["ERROR failed".to_string()];
+        let (syslog_matches, _) = match_lines("syslog", &patterns, &lines, 0);
+        let (nginx_matches, _) = match_lines("nginx", &patterns, &lines, 0);
+
+        let mut syslog_cooldowns = LogWatchCooldowns::new();
+        let mut nginx_cooldowns = LogWatchCooldowns::new();
+
+        // the cooldown of one watch doesn't hold back the other
+        for _ in 0..2 {
+            send_log_match_notification(
+                "syslog",
+                &syslog_matches,
+                &mut syslog_cooldowns,
+                &notifier,
+                &clock,
+            )
+            .await;
+        }
+        send_log_match_notification(
+            "nginx",
+            &nginx_matches,
+            &mut nginx_cooldowns,
+            &notifier,
+            &clock,
+        )
+        .await;
+
+        fake_clock.advance(get_send_notification_interval().to_std().unwrap());
+        send_log_match_notification(
+            "syslog",
+            &syslog_matches,
+            &mut syslog_cooldowns,
+            &notifier,
+            &clock,
+        )
+        .await;
+
+        assert_eq!(
+            notifier
+                .get_sent()
+                .iter()
+                .map(|n| n.title.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "IMPORTANT: syslog log matched \"ERROR\"",
+                "IMPORTANT: nginx log matched \"ERROR\"",
+                "IMPORTANT: syslog log matched \"ERROR\"",
+            ]
+        );
+    }
+}
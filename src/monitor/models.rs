@@ -1,10 +1,25 @@
+pub mod get_boot_events;
+pub mod get_certificate_checks;
+pub mod get_cgroup_status;
+pub mod get_collector_stats;
 pub mod get_cpu_status;
+pub mod get_custom_metrics;
 pub mod get_disk_status;
+pub mod get_endpoint_checks;
+pub mod get_hardware_changes;
+pub mod get_log_matches;
 pub mod get_mem_status;
+pub mod get_pressure_status;
+pub mod get_process_watch;
+pub mod get_status_rollups;
+pub mod get_systemd_units;
+pub mod get_temperature_status;
 
 pub mod get_hardware_info;
 
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServerDescription {
@@ -17,7 +32,40 @@ pub struct UpdateInfoRequest {
     pub cpu_threshold: f64,
     pub mem_threshold: f64,
     pub disk_threshold: f64,
+    // the used inodes percentage of any disk. kept as it is if not sent,
+    // so the older clients don't clear it, and disabled if sent as null
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub inode_threshold: Option<Option<f64>>,
+    // the "some" avg10 pressure stall percentages, kept if not sent and
+    // disabled if sent as null, or if the kernel doesn't support PSI
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub cpu_pressure_threshold: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub mem_pressure_threshold: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub io_pressure_threshold: Option<Option<f64>>,
+    // the percentage of its memory.max any cgroup uses, kept if not sent
+    // and disabled if sent as null
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub cgroup_mem_threshold: Option<Option<f64>>,
     pub fcm_token: String,
+    // component_id -> threshold in celsius, if sent it replaces
+    // all of the device's temperature thresholds
+    #[serde(default)]
+    pub temperature_thresholds: Option<HashMap<String, f64>>,
+    // probe -> key -> threshold, if sent it replaces
+    // all of the device's custom metric thresholds
+    #[serde(default)]
+    pub custom_metric_thresholds: Option<HashMap<String, HashMap<String, f64>>>,
+}
+
+// a field sent as null is Some(None), one that wasn't sent is None with `#[serde(default)]`
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -27,6 +75,38 @@ pub struct MonitorConfig {
     pub cpu_threshold: f64,
     pub mem_threshold: f64,
     pub disk_threshold: f64,
+    pub inode_threshold: Option<f64>,
+    pub cpu_pressure_threshold: Option<f64>,
+    pub mem_pressure_threshold: Option<f64>,
+    pub io_pressure_threshold: Option<f64>,
+    pub cgroup_mem_threshold: Option<f64>,
     pub fcm_token: String,
     pub updated_at: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_inode_threshold(json: &str) -> Option<Option<f64>> {
+        serde_json::from_str::<UpdateInfoRequest>(json)
+            .unwrap()
+            .inode_threshold
+    }
+
+    #[test]
+    fn update_info_nullable_test() {
+        let base =
+            r#""cpu_threshold": 90, "mem_threshold": 90, "disk_threshold": 90, "fcm_token": "t""#;
+
+        assert_eq!(get_inode_threshold(&format!("{{{}}}", base)), None);
+        assert_eq!(
+            get_inode_threshold(&format!(r#"{{{}, "inode_threshold": null}}"#, base)),
+            Some(None)
+        );
+        assert_eq!(
+            get_inode_threshold(&format!(r#"{{{}, "inode_threshold": 80}}"#, base)),
+            Some(Some(80.0))
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetBootEventsRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+// a boot of the host, recorded when remon starts on a boot it hasn't seen before
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct BootEvent {
+    pub id: i64,
+    // from /proc/sys/kernel/random/boot_id, only on linux
+    pub boot_id: Option<String>,
+    // in milliseconds, like last_check
+    pub boot_time: i64,
+    // false for the first boot remon has seen on this host
+    pub is_reboot: bool,
+    // the time of the last sample before the reboot
+    pub last_sample: Option<i64>,
+    // the seconds between the last sample and the boot
+    pub downtime_secs: Option<i64>,
+    // the last sample was long before the boot, so the host likely
+    // crashed or lost power instead of shutting down
+    pub is_unclean: bool,
+    pub last_check: i64,
+}
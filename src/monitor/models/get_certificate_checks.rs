@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetCertificateChecksRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+    // only the checks of this file, all of them if not sent
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStatus {
+    Ok,
+    Missing,
+    // exists, but couldn't be read, e.g. because of its permissions
+    Unreadable,
+    // read, but doesn't contain a valid PEM certificate
+    Invalid,
+}
+
+// the result of checking a single certificate file,
+// the certificate fields are None unless the status is Ok
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct CertificateCheck {
+    pub id: i64,
+    pub path: String,
+    pub status: CertificateStatus,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    // in milliseconds, like last_check
+    pub not_after: Option<i64>,
+    // negative once expired
+    pub days_to_expiry: Option<f64>,
+    // the reason the file couldn't be checked, if any
+    pub message: Option<String>,
+    pub last_check: i64,
+}
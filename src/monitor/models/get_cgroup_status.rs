@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetCgroupStatusRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+// the usage of a single cgroup since the previous frame,
+// every field is None when the cgroup doesn't have the controller enabled
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct SingleCgroupInfo {
+    pub id: i64,
+    pub frame_id: i64,
+    // relative to the cgroup root, e.g. "/system.slice/docker-<id>.scope"
+    pub path: String,
+    // the percentage of a single core, so it can go above 100 on multi core hosts
+    pub cpu_usage_percent: Option<f64>,
+    pub memory_current: Option<i64>,
+    // None when the cgroup has no memory limit
+    pub memory_max: Option<i64>,
+    pub io_read_bytes_per_sec: Option<f64>,
+    pub io_write_bytes_per_sec: Option<f64>,
+    pub pids_current: Option<i64>,
+    // None when the cgroup has no pids limit
+    pub pids_max: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct CgroupFrameStatus {
+    pub id: i64,
+    pub last_check: i64,
+    pub cgroups: Vec<SingleCgroupInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CgroupStatusData {
+    pub frames: Vec<CgroupFrameStatus>,
+}
+
+impl SingleCgroupInfo {
+    pub fn get_memory_usage_percent(&self) -> Option<f64> {
+        let current = self.memory_current?;
+        let max = self.memory_max?;
+
+        if max <= 0 {
+            return None;
+        }
+
+        Some((current as f64 / max as f64) * 100.0)
+    }
+}
+
+// string is the cgroup path, f64 is the highest percentage of memory.max used
+pub type CgroupMemoryUsageMap = HashMap<String, f64>;
+pub trait CgroupStatusDataTrait {
+    fn cgroups_memory_usage_max_percentages(&self) -> CgroupMemoryUsageMap;
+}
+
+impl CgroupStatusDataTrait for CgroupStatusData {
+    fn cgroups_memory_usage_max_percentages(&self) -> CgroupMemoryUsageMap {
+        let mut res = HashMap::new() as CgroupMemoryUsageMap;
+
+        for frame in &self.frames {
+            for cgroup in &frame.cgroups {
+                let perc = match cgroup.get_memory_usage_percent() {
+                    Some(perc) => perc,
+                    None => continue,
+                };
+
+                let entry = res.entry(cgroup.path.to_string()).or_insert(perc);
+                *entry = entry.max(perc);
+            }
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::hashmap;
+
+    fn single(path: &str, memory_current: i64, memory_max: Option<i64>) -> SingleCgroupInfo {
+        SingleCgroupInfo {
+            id: -1,
+            frame_id: -1,
+            path: path.to_string(),
+            cpu_usage_percent: None,
+            memory_current: Some(memory_current),
+            memory_max,
+            io_read_bytes_per_sec: None,
+            io_write_bytes_per_sec: None,
+            pids_current: None,
+            pids_max: None,
+        }
+    }
+
+    #[test]
+    fn cgroups_memory_usage_max_percentages_test() {
+        let data = CgroupStatusData {
+            frames: vec![
+                CgroupFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    cgroups: vec![
+                        single("/db", 400, Some(1000)),
+                        single("/unlimited", 400, None),
+                    ],
+                },
+                CgroupFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    cgroups: vec![single("/db", 900, Some(1000))],
+                },
+            ],
+        };
+
+        // cgroups without a limit can't be near it
+        assert_eq!(
+            data.cgroups_memory_usage_max_percentages(),
+            hashmap! {
+                "/db".to_string() => 90.0,
+            }
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+// the timing of a single collector since the server started, kept in memory
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollectorStats {
+    pub name: String,
+    pub interval_ms: i64,
+    pub runs: i64,
+    // the runs that took longer than the interval, which delays the next one
+    pub overruns: i64,
+    pub last_duration_ms: f64,
+    pub max_duration_ms: f64,
+    pub avg_duration_ms: f64,
+    pub last_run: i64,
+}
+
+// the writes of the status frames since the server started, kept in memory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WriteStats {
+    // every batch is written in a single transaction
+    pub batches: i64,
+    pub frames: i64,
+    pub rows: i64,
+    // the batches that were rolled back, their frames are lost
+    pub failures: i64,
+    pub last_latency_ms: f64,
+    pub max_latency_ms: f64,
+    pub avg_latency_ms: f64,
+    pub last_write: i64,
+}
@@ -1,10 +1,5 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct GetCpuStatusRequest {
-    pub start_time: i64,
-    pub end_time: i64,
-}
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct CpuCoreInfo {
     pub id: i64,
@@ -14,7 +9,7 @@ pub struct CpuCoreInfo {
     pub freq: i64,
     pub usage: i64,
 }
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct CpuFrameStatus {
     pub id: i64,
     pub last_check: i64,
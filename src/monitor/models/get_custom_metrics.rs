@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetCustomMetricsRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+    // only the metrics of this probe, all of them if not sent
+    pub probe: Option<String>,
+}
+
+fn default_probe_interval_secs() -> u64 {
+    60
+}
+
+fn default_probe_timeout_secs() -> u64 {
+    10
+}
+
+// a user defined command in the server config, its stdout is either
+// a single number or `key=value` lines with a number as the value
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomProbe {
+    pub name: String,
+    // run with `sh -c`, or `cmd /C` on windows
+    pub command: String,
+    #[serde(default = "default_probe_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_probe_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomMetricStatus {
+    Ok,
+    // the command couldn't be run, or exited with a non zero code
+    Failed,
+    TimedOut,
+    // the command succeeded but its output isn't a number or `key=value` lines
+    InvalidOutput,
+}
+
+// a single value reported by a probe, or the reason it didn't report any
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct CustomMetric {
+    pub id: i64,
+    pub probe: String,
+    // "value" when the probe printed a single number, None when it failed
+    pub key: Option<String>,
+    pub value: Option<f64>,
+    pub status: CustomMetricStatus,
+    // the reason of the failure, if any
+    pub message: Option<String>,
+    pub last_check: i64,
+}
+
+// a device's threshold for a single key of a probe
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct CustomMetricThreshold {
+    pub id: i64,
+    pub device_id: String,
+    pub probe: String,
+    pub key: String,
+    pub threshold: f64,
+}
@@ -14,6 +14,11 @@ pub struct SingleDiskInfo {
     // the id of the disk, consists from key info like name, fs, etc.
     pub disk_id: String,
     pub available: i64,
+    // None where the filesystem has no fixed inode count, like btrfs, or on windows
+    #[serde(default)]
+    pub inodes_total: Option<i64>,
+    #[serde(default)]
+    pub inodes_free: Option<i64>,
 }
 
 trait SingleDiskInfoUsage {
@@ -30,20 +35,56 @@ impl SingleDiskInfoUsage for SingleDiskInfo {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+impl SingleDiskInfo {
+    fn get_inode_usage_percent(&self) -> Option<f64> {
+        let total = self.inodes_total?;
+        let free = self.inodes_free?;
+
+        if total <= 0 {
+            return None;
+        }
+
+        Some(((total - free) as f64 / total as f64) * 100.0)
+    }
+}
+
+// the throughput of a block device since the previous frame
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct SingleDiskIoInfo {
+    pub id: i64,
+    pub frame_id: i64,
+    // the kernel name of the block device, e.g. "sda1"
+    pub device_name: String,
+    // the disk mounted from this device, if any
+    pub disk_id: Option<String>,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    // the percentage of the time the device was busy doing I/O
+    pub busy_percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct DiskFrameStatus {
     pub id: i64,
     pub last_check: i64,
     // usage for each disk
     pub disks_usage: Vec<SingleDiskInfo>,
+    // throughput for each block device, empty where /proc/diskstats is not available
+    #[serde(default)]
+    pub disks_io: Vec<SingleDiskIoInfo>,
 }
 
 // string is disk_id, i64 is total space
 pub type DiskTotalSpaceMap = HashMap<String, i64>;
 pub type DiskUsageMeanMap = HashMap<String, i64>;
+// string is disk_id, f64 is the used inodes percentage
+pub type DiskInodeUsageMap = HashMap<String, f64>;
 pub trait DiskStatusDataTrait {
     fn disks_usage_means(&self, total_spaces: &DiskTotalSpaceMap) -> DiskUsageMeanMap;
     fn disks_usage_means_percentages(&self, total_spaces: &DiskTotalSpaceMap) -> DiskUsageMeanMap;
+    fn disks_inode_usage_means_percentages(&self) -> DiskInodeUsageMap;
 }
 
 impl DiskStatusDataTrait for DiskStatusData {
@@ -106,6 +147,28 @@ impl DiskStatusDataTrait for DiskStatusData {
 
         return new_map;
     }
+
+    fn disks_inode_usage_means_percentages(&self) -> DiskInodeUsageMap {
+        // disk_id -> (sum of percentages, count)
+        let mut sums: HashMap<String, (f64, i64)> = HashMap::new();
+
+        for frame in &self.frames {
+            for single in &frame.disks_usage {
+                let percentage = match single.get_inode_usage_percent() {
+                    Some(percentage) => percentage,
+                    None => continue,
+                };
+
+                let entry = sums.entry(single.disk_id.to_string()).or_insert((0.0, 0));
+                entry.0 += percentage;
+                entry.1 += 1;
+            }
+        }
+
+        sums.into_iter()
+            .map(|(disk_id, (sum, count))| (disk_id, sum / count as f64))
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -139,14 +202,19 @@ mod tests {
                             disk_id: disk1id.to_string(),
                             frame_id: -1,
                             available: 30,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                         SingleDiskInfo {
                             id: -1,
                             disk_id: disk3id.to_string(),
                             frame_id: -1,
                             available: 40,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                     ],
+                    disks_io: vec![],
                 },
                 DiskFrameStatus {
                     id: -1,
@@ -157,14 +225,19 @@ mod tests {
                             disk_id: disk1id.to_string(),
                             frame_id: -1,
                             available: 20,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                         SingleDiskInfo {
                             id: -1,
                             disk_id: disk2id.to_string(),
                             frame_id: -1,
                             available: 65,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                     ],
+                    disks_io: vec![],
                 },
                 DiskFrameStatus {
                     id: -1,
@@ -175,14 +248,19 @@ mod tests {
                             disk_id: disk2id.to_string(),
                             frame_id: -1,
                             available: 90,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                         SingleDiskInfo {
                             id: -1,
                             disk_id: disk3id.to_string(),
                             frame_id: -1,
                             available: 10,
+                            inodes_total: None,
+                            inodes_free: None,
                         },
                     ],
+                    disks_io: vec![],
                 },
             ],
         };
@@ -217,4 +295,49 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn disk_inode_usage_means_percentages_test() {
+        let single =
+            |disk_id: &str, inodes_total: Option<i64>, inodes_free: Option<i64>| SingleDiskInfo {
+                id: -1,
+                frame_id: -1,
+                disk_id: disk_id.to_string(),
+                available: 0,
+                inodes_total,
+                inodes_free,
+            };
+
+        let data = DiskStatusData {
+            frames: vec![
+                DiskFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    disks_usage: vec![
+                        // 90% used
+                        single("disk1id", Some(1000), Some(100)),
+                        // no inode info, like btrfs
+                        single("disk2id", None, None),
+                        // a filesystem that reports 0 inodes
+                        single("disk3id", Some(0), Some(0)),
+                    ],
+                    disks_io: vec![],
+                },
+                DiskFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    // 70% used
+                    disks_usage: vec![single("disk1id", Some(1000), Some(300))],
+                    disks_io: vec![],
+                },
+            ],
+        };
+
+        assert_eq!(
+            data.disks_inode_usage_means_percentages(),
+            hashmap! {
+                "disk1id".to_string() => 80.0,
+            }
+        );
+    }
 }
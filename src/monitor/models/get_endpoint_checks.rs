@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetEndpointChecksRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+    // only the checks of this probe, all of them if not sent
+    pub name: Option<String>,
+}
+
+fn default_endpoint_interval_secs() -> u64 {
+    30
+}
+
+fn default_endpoint_timeout_secs() -> u64 {
+    5
+}
+
+fn default_failure_threshold() -> i64 {
+    3
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EndpointTarget {
+    // up when the response has the expected status, any 2xx if not set,
+    // and its body contains `body_contains`, if set
+    Http {
+        url: String,
+        expected_status: Option<u16>,
+        body_contains: Option<String>,
+    },
+    // up when a connection can be opened to the address, e.g. "db.internal:5432"
+    Tcp {
+        address: String,
+    },
+}
+
+// a synthetic check in the server config
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EndpointProbe {
+    pub name: String,
+    #[serde(flatten)]
+    pub target: EndpointTarget,
+    #[serde(default = "default_endpoint_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_endpoint_timeout_secs")]
+    pub timeout_secs: u64,
+    // how many checks in a row have to fail before notifying
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: i64,
+}
+
+// the result of a single check
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct EndpointCheck {
+    pub id: i64,
+    pub name: String,
+    pub is_up: bool,
+    // None when no response was received
+    pub latency_ms: Option<f64>,
+    // only for http checks that received a response
+    pub status_code: Option<i64>,
+    // the reason the check failed, if it did
+    pub message: Option<String>,
+    pub last_check: i64,
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use super::get_hardware_info::{
+    HardwareComponentInfo, HardwareCpuInfo, HardwareDiskInfo, HardwareMemInfo,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetHardwareChangesRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HardwareChangeKind {
+    CpuAdded,
+    CpuRemoved,
+    CpuChanged,
+    DiskAdded,
+    DiskRemoved,
+    DiskChanged,
+    MemAdded,
+    MemRemoved,
+    MemChanged,
+    ComponentAdded,
+    ComponentRemoved,
+    ComponentChanged,
+}
+
+// a piece of hardware that appeared, disappeared or changed since the last inventory,
+// e.g. after a reboot or when a disk is unmounted
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct HardwareChangeEvent {
+    pub id: i64,
+    pub kind: HardwareChangeKind,
+    // the id of the changed hardware, e.g. the cpu_id or the disk_id
+    pub hardware_id: String,
+    // human readable, e.g. "/dev/sdb1 mounted at /mnt/backup" or "memory went from 32.0 GB to 16.0 GB"
+    pub description: String,
+    pub last_check: i64,
+}
+
+// the differences between two inventories of a single kind of hardware,
+// matched by their ids, e.g. the cpu_id
+#[derive(Debug, Clone)]
+pub struct InventoryDiff<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    // the previous and the current version
+    pub changed: Vec<(T, T)>,
+}
+
+impl<T> InventoryDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HardwareInfoDiff {
+    pub cpu_info: InventoryDiff<HardwareCpuInfo>,
+    pub disks_info: InventoryDiff<HardwareDiskInfo>,
+    pub mem_info: InventoryDiff<HardwareMemInfo>,
+    pub components_info: InventoryDiff<HardwareComponentInfo>,
+}
+
+impl HardwareInfoDiff {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_info.is_empty()
+            && self.disks_info.is_empty()
+            && self.mem_info.is_empty()
+            && self.components_info.is_empty()
+    }
+}
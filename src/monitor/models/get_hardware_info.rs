@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetHardwareHistoryRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct HardwareCpuInfo {
     pub id: i64,
@@ -9,11 +15,14 @@ pub struct HardwareCpuInfo {
     pub vendor_id: String,
     pub brand: String,
     pub last_check: i64,
+    // the time range this version of the hardware was in use, valid_to is None for the current one
+    pub valid_from: i64,
+    pub valid_to: Option<i64>,
 }
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct HardwareDiskInfo {
     pub id: i64,
-    // the id of the disk, consists from key info like name, fs, etc.
+    // the id of the disk, consists from its name and mount point
     pub disk_id: String,
     pub name: String,
     pub fs_type: String,
@@ -22,6 +31,8 @@ pub struct HardwareDiskInfo {
     pub mount_point: String,
     pub total_space: i64,
     pub last_check: i64,
+    pub valid_from: i64,
+    pub valid_to: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
@@ -30,11 +41,27 @@ pub struct HardwareMemInfo {
     pub mem_id: String,
     pub total_space: i64,
     pub last_check: i64,
+    pub valid_from: i64,
+    pub valid_to: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct HardwareComponentInfo {
+    pub id: i64,
+    // the id of the component, a hash of its label, and of its position among
+    // the components with the same label after the first one
+    pub component_id: String,
+    pub label: String,
+    pub critical: Option<f64>,
+    pub last_check: i64,
+    pub valid_from: i64,
+    pub valid_to: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone, Default)]
 pub struct HardwareInfo {
     pub cpu_info: Vec<HardwareCpuInfo>,
     pub disks_info: Vec<HardwareDiskInfo>,
     pub mem_info: Vec<HardwareMemInfo>,
+    pub components_info: Vec<HardwareComponentInfo>,
 }
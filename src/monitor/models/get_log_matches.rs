@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetLogMatchesRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+    // only the matches of this watch, all of them if not sent
+    pub watch: Option<String>,
+}
+
+fn default_log_watch_interval_secs() -> u64 {
+    10
+}
+
+fn default_max_history() -> i64 {
+    1000
+}
+
+// a log file in the server config, whose new lines are matched against the patterns
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogWatch {
+    pub name: String,
+    pub path: String,
+    // regexes, e.g. "Out of memory" or "\\bERROR\\b"
+    pub patterns: Vec<String>,
+    #[serde(default = "default_log_watch_interval_secs")]
+    pub interval_secs: u64,
+    // how many matched lines of this watch are kept, the older ones are deleted
+    #[serde(default = "default_max_history")]
+    pub max_history: i64,
+}
+
+// a line that matched one of the patterns
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct LogMatch {
+    pub id: i64,
+    pub watch: String,
+    pub pattern: String,
+    pub line: String,
+    pub last_check: i64,
+}
+
+// how many lines matched a pattern in a single interval,
+// the intervals without any matches are not stored
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct LogMatchCount {
+    pub id: i64,
+    pub watch: String,
+    pub pattern: String,
+    pub count: i64,
+    pub last_check: i64,
+}
@@ -8,12 +8,6 @@ pub struct GetCpuStatusRequest {
     pub end_time: i64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct GetMemStatusRequest {
-    pub start_time: i64,
-    pub end_time: i64,
-}
-
 // we actually only fetch a single mem data, but we're cloning this into 2 structs for convenience, so it would be read the same as the cpu and disk
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct SingleMemInfo {
@@ -23,7 +17,7 @@ pub struct SingleMemInfo {
     pub mem_id: String,
     pub available: i64,
 }
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
 pub struct MemFrameStatus {
     pub id: i64,
     pub last_check: i64,
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetPressureStatusRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+// a single line of a /proc/pressure file
+// https://docs.kernel.org/accounting/psi.html
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone, PartialEq)]
+pub struct SinglePressureInfo {
+    pub id: i64,
+    pub frame_id: i64,
+    // "cpu", "memory" or "io"
+    pub resource: String,
+    // "some" when at least one task was stalled, "full" when all of them were
+    pub kind: String,
+    // the percentage of the time tasks were stalled in the last 10, 60 and 300 seconds
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    // the total stall time in microseconds
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct PressureFrameStatus {
+    pub id: i64,
+    pub last_check: i64,
+    pub pressures: Vec<SinglePressureInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PressureStatusData {
+    pub frames: Vec<PressureFrameStatus>,
+}
+
+pub trait PressureStatusDataTrait {
+    // the highest "some" avg10 of the resource across the frames
+    fn max_some_avg10(&self, resource: &str) -> Option<f64>;
+}
+
+impl PressureStatusDataTrait for PressureStatusData {
+    fn max_some_avg10(&self, resource: &str) -> Option<f64> {
+        self.frames
+            .iter()
+            .flat_map(|frame| &frame.pressures)
+            .filter(|p| p.resource == resource && p.kind == "some")
+            .map(|p| p.avg10)
+            .reduce(f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(resource: &str, kind: &str, avg10: f64) -> SinglePressureInfo {
+        SinglePressureInfo {
+            id: -1,
+            frame_id: -1,
+            resource: resource.to_string(),
+            kind: kind.to_string(),
+            avg10,
+            avg60: 0.0,
+            avg300: 0.0,
+            total: 0,
+        }
+    }
+
+    #[test]
+    fn max_some_avg10_test() {
+        let data = PressureStatusData {
+            frames: vec![
+                PressureFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    pressures: vec![single("cpu", "some", 12.5), single("io", "full", 90.0)],
+                },
+                PressureFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    pressures: vec![single("cpu", "some", 30.0), single("io", "some", 4.0)],
+                },
+            ],
+        };
+
+        assert_eq!(data.max_some_avg10("cpu"), Some(30.0));
+        // "full" lines are not taken into account
+        assert_eq!(data.max_some_avg10("io"), Some(4.0));
+        assert_eq!(data.max_some_avg10("memory"), None);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetProcessWatchEventsRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+fn default_min_instances() -> i64 {
+    1
+}
+
+// a single entry of the process watchlist in the server config
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchedProcess {
+    // the name used in the notifications and the events table, e.g. "postgres"
+    pub name: String,
+    // matched exactly against the process name, or as a substring against
+    // the full command line if `match_cmdline` is set
+    pub pattern: String,
+    #[serde(default)]
+    pub match_cmdline: bool,
+    // the watched process is considered down when less than
+    // this many instances are running
+    #[serde(default = "default_min_instances")]
+    pub min_instances: i64,
+}
+
+// a running process, as seen by the monitor
+#[derive(Debug, Clone)]
+pub struct RunningProcess {
+    pub name: String,
+    pub cmdline: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProcessWatchStatus {
+    pub name: String,
+    pub pattern: String,
+    pub instance_count: i64,
+    pub min_instances: i64,
+    pub is_up: bool,
+}
+
+// an up/down transition of a watched process
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct ProcessWatchEvent {
+    pub id: i64,
+    pub name: String,
+    pub pattern: String,
+    pub instance_count: i64,
+    pub min_instances: i64,
+    pub is_up: bool,
+    pub last_check: i64,
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+// the resolution the status frames of a range are returned in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    Hour,
+}
+
+impl Resolution {
+    // from the finest to the coarsest
+    pub const ROLLUPS: [Resolution; 3] = [
+        Resolution::Minute,
+        Resolution::FifteenMinutes,
+        Resolution::Hour,
+    ];
+
+    // the length of a bucket, None for the raw frames
+    pub fn get_bucket_millis(&self) -> Option<i64> {
+        match self {
+            Resolution::Raw => None,
+            Resolution::Minute => Some(60 * 1000),
+            Resolution::FifteenMinutes => Some(15 * 60 * 1000),
+            Resolution::Hour => Some(60 * 60 * 1000),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Resolution::Raw => "raw",
+            Resolution::Minute => "1m",
+            Resolution::FifteenMinutes => "15m",
+            Resolution::Hour => "1h",
+        }
+    }
+}
+
+// the samples of a single cpu, mem or disk in a bucket
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone, PartialEq)]
+pub struct StatusRollup {
+    // the start of the bucket, the bucket ends where the next one starts
+    pub bucket_start: i64,
+    // the cpu_id, mem_id or disk_id
+    pub entity_id: String,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub avg_value: f64,
+    pub p95_value: f64,
+    pub samples: i64,
+}
+
+// how the samples of a bucket are combined into its value
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    Avg,
+    Min,
+    Max,
+    // the latest sample of the bucket
+    Last,
+    P95,
+}
+
+impl Aggregate {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "avg" => Some(Aggregate::Avg),
+            "min" => Some(Aggregate::Min),
+            "max" => Some(Aggregate::Max),
+            "last" => Some(Aggregate::Last),
+            "p95" => Some(Aggregate::P95),
+            _ => None,
+        }
+    }
+}
+
+// the range of a status endpoint and how it's bucketed
+#[derive(Debug, Clone, Default)]
+pub struct StatusQuery {
+    pub start_time: i64,
+    pub end_time: i64,
+    // the length of the buckets in seconds, computed from `max_points` when not set
+    pub step: Option<i64>,
+    pub max_points: Option<i64>,
+    pub aggregate: Option<Aggregate>,
+    // only the cpu, mem or disk with this id
+    pub entity_id: Option<String>,
+}
+
+// the value of a single cpu, mem or disk in a bucket
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone, PartialEq)]
+pub struct StatusBucket {
+    pub bucket_start: i64,
+    pub entity_id: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StatusHistory<F> {
+    // raw, or the rollups the buckets were computed from
+    pub resolution: Resolution,
+    // the length of the buckets in seconds, None for the raw frames
+    pub step: Option<i64>,
+    pub aggregate: Option<Aggregate>,
+    pub frames: Vec<F>,
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetSystemdUnitEventsRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+// the state of a unit, as reported by `systemctl show`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SystemdUnitState {
+    // e.g. "nginx.service"
+    pub unit: String,
+    // e.g. "active", "failed", "activating"
+    pub active_state: String,
+    // e.g. "running", "dead", "auto-restart"
+    pub sub_state: String,
+    // how many times systemd restarted the unit since it was last started manually
+    pub n_restarts: i64,
+}
+
+// a change in the state of a watched unit
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct SystemdUnitEvent {
+    pub id: i64,
+    pub unit: String,
+    pub active_state: String,
+    pub sub_state: String,
+    pub n_restarts: i64,
+    pub last_check: i64,
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetTemperatureStatusRequest {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct SingleTemperatureInfo {
+    pub id: i64,
+    pub frame_id: i64,
+    // the id of the component, a hash of its label, and of its position among
+    // the components with the same label after the first one
+    pub component_id: String,
+    // all of the temperatures are in celsius
+    pub temperature: f64,
+    // the highest temperature seen since the sensor was first read
+    pub max: Option<f64>,
+    // the temperature the component halts at, not every sensor reports it
+    pub critical: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct TemperatureFrameStatus {
+    pub id: i64,
+    pub last_check: i64,
+    // temperature for each component that has a sensor
+    pub components_temperature: Vec<SingleTemperatureInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TemperatureStatusData {
+    pub frames: Vec<TemperatureFrameStatus>,
+}
+
+// a device's threshold for a single sensor
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct TemperatureThreshold {
+    pub id: i64,
+    pub device_id: String,
+    pub component_id: String,
+    pub threshold: f64,
+}
+
+// string is component_id, f64 is the temperature
+pub type TemperatureMeanMap = HashMap<String, f64>;
+pub trait TemperatureStatusDataTrait {
+    fn components_temperature_means(&self) -> TemperatureMeanMap;
+}
+
+impl TemperatureStatusDataTrait for TemperatureStatusData {
+    fn components_temperature_means(&self) -> TemperatureMeanMap {
+        // component_id -> (sum, count)
+        let mut sums: HashMap<String, (f64, i64)> = HashMap::new();
+
+        for frame in &self.frames {
+            for single in &frame.components_temperature {
+                let entry = sums
+                    .entry(single.component_id.to_string())
+                    .or_insert((0.0, 0));
+                entry.0 += single.temperature;
+                entry.1 += 1;
+            }
+        }
+
+        sums.into_iter()
+            .map(|(component_id, (sum, count))| (component_id, sum / count as f64))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::hashmap;
+
+    fn single(component_id: &str, temperature: f64) -> SingleTemperatureInfo {
+        SingleTemperatureInfo {
+            id: -1,
+            frame_id: -1,
+            component_id: component_id.to_string(),
+            temperature,
+            max: None,
+            critical: None,
+        }
+    }
+
+    #[test]
+    fn components_temperature_means_test() {
+        let data = TemperatureStatusData {
+            frames: vec![
+                TemperatureFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    components_temperature: vec![single("cpu", 60.0), single("nvme", 40.0)],
+                },
+                TemperatureFrameStatus {
+                    id: -1,
+                    last_check: -1,
+                    components_temperature: vec![single("cpu", 70.0)],
+                },
+            ],
+        };
+
+        assert_eq!(
+            data.components_temperature_means(),
+            hashmap! {
+                "cpu".to_string() => 65.0,
+                "nvme".to_string() => 40.0,
+            }
+        );
+    }
+}
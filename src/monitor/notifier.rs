@@ -0,0 +1,171 @@
+use std::future::Future;
+
+use crate::monitor::persistence::fetch_monitor_configs;
+use crate::notification_service::{self, NotificationMessage};
+use crate::persistence::notification_logs::{self, NotificationType};
+
+// sends the notifications to the devices and keeps track of what was sent,
+// so the tests can check the notifications without fcm or the database
+pub(super) trait Notifier: Send + Sync {
+    // the device id and the fcm token of every device with a monitor config
+    fn fetch_devices(&self) -> impl Future<Output = Result<Vec<(String, String)>, String>> + Send;
+
+    // when the last notification of this type was sent to the device
+    fn fetch_last_sent_at(
+        &self,
+        device_id: &str,
+        notification_type: &NotificationType,
+    ) -> impl Future<Output = Result<Option<i64>, String>> + Send;
+
+    fn send(
+        &self,
+        device_id: &str,
+        fcm_token: &str,
+        message: &NotificationMessage,
+        notification_type: &NotificationType,
+    ) -> impl Future<Output = Result<bool, String>> + Send;
+}
+
+// fcm, with every sent notification logged in the database
+pub(super) struct FcmNotifier;
+
+impl Notifier for FcmNotifier {
+    async fn fetch_devices(&self) -> Result<Vec<(String, String)>, String> {
+        let configs = fetch_monitor_configs().await.map_err(|e| e.to_string())?;
+
+        Ok(configs
+            .into_iter()
+            .map(|c| (c.device_id, c.fcm_token))
+            .collect())
+    }
+
+    async fn fetch_last_sent_at(
+        &self,
+        device_id: &str,
+        notification_type: &NotificationType,
+    ) -> Result<Option<i64>, String> {
+        notification_logs::fetch_single_latest_for_device_id_and_type(device_id, notification_type)
+            .await
+            .map(|log| log.map(|l| l.sent_at))
+            .map_err(|e| e.to_string())
+    }
+
+    async fn send(
+        &self,
+        device_id: &str,
+        fcm_token: &str,
+        message: &NotificationMessage,
+        notification_type: &NotificationType,
+    ) -> Result<bool, String> {
+        notification_service::send_notification_to_single(
+            device_id,
+            fcm_token,
+            message,
+            notification_type,
+        )
+        .await
+    }
+}
+
+// sends the message to every device, used for host wide events
+// that are not tied to a device's thresholds
+pub(super) async fn send_to_all_devices(
+    notifier: &impl Notifier,
+    message: &NotificationMessage,
+    notification_type: &NotificationType,
+) -> Result<bool, String> {
+    let mut is_sent = true;
+
+    for (device_id, fcm_token) in notifier.fetch_devices().await? {
+        is_sent &= notifier
+            .send(&device_id, &fcm_token, message, notification_type)
+            .await?;
+    }
+
+    Ok(is_sent)
+}
+
+#[cfg(test)]
+pub(super) use self::recording::RecordingNotifier;
+
+#[cfg(test)]
+mod recording {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::monitor::clock::SharedClock;
+
+    #[derive(Debug, Clone)]
+    pub struct SentNotification {
+        pub device_id: String,
+        pub notification_type: NotificationType,
+        pub title: String,
+        pub body: String,
+        pub sent_at: i64,
+    }
+
+    // keeps the notifications in memory, sent at the time of the clock
+    pub struct RecordingNotifier {
+        clock: SharedClock,
+        devices: Vec<(String, String)>,
+        sent: Mutex<Vec<SentNotification>>,
+    }
+
+    impl RecordingNotifier {
+        pub fn new(clock: SharedClock) -> Self {
+            Self {
+                clock,
+                devices: vec![],
+                sent: Mutex::new(vec![]),
+            }
+        }
+
+        pub fn with_device(mut self, device_id: &str, fcm_token: &str) -> Self {
+            self.devices
+                .push((device_id.to_string(), fcm_token.to_string()));
+            self
+        }
+
+        pub fn get_sent(&self) -> Vec<SentNotification> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl Notifier for RecordingNotifier {
+        async fn fetch_devices(&self) -> Result<Vec<(String, String)>, String> {
+            Ok(self.devices.clone())
+        }
+
+        async fn fetch_last_sent_at(
+            &self,
+            device_id: &str,
+            notification_type: &NotificationType,
+        ) -> Result<Option<i64>, String> {
+            let sent = self.sent.lock().unwrap();
+
+            Ok(sent
+                .iter()
+                .filter(|n| n.device_id == device_id && n.notification_type == *notification_type)
+                .map(|n| n.sent_at)
+                .max())
+        }
+
+        async fn send(
+            &self,
+            device_id: &str,
+            _fcm_token: &str,
+            message: &NotificationMessage,
+            notification_type: &NotificationType,
+        ) -> Result<bool, String> {
+            self.sent.lock().unwrap().push(SentNotification {
+                device_id: device_id.to_string(),
+                notification_type: notification_type.clone(),
+                title: message.title.to_string(),
+                body: message.body.to_string(),
+                sent_at: self.clock.now_millis(),
+            });
+
+            Ok(true)
+        }
+    }
+}
@@ -1,49 +1,97 @@
 mod monitor_config;
-use self::monitor_config::create_monitor_configs_table;
-pub use self::monitor_config::{fetch_monitor_configs, insert_or_update_monitor_config};
+pub use self::monitor_config::{
+    fetch_monitor_config, fetch_monitor_configs, insert_or_update_monitor_config,
+};
 
 mod hardware_cpu_info;
-use self::hardware_cpu_info::create_hardware_cpu_infos_table;
 
 mod hardware_disk_info;
-use self::hardware_disk_info::create_hardware_disk_infos_table;
 
 mod hardware_mem_info;
-use self::hardware_mem_info::create_hardware_mem_infos_table;
+
+mod hardware_component_info;
 
 mod hardware_info;
-pub use self::hardware_info::{fetch_latest_hardware_info, insert_hardware_info};
+pub use self::hardware_info::{
+    fetch_latest_hardware_info, get_hardware_info_between_dates, insert_hardware_info_changes,
+};
 
 mod status_cpu;
-use self::status_cpu::{create_cpu_status_frame_cores_table, create_cpu_status_frames_table};
-pub use self::status_cpu::{get_cpu_status_between_dates, insert_cpu_status_frame};
+pub use self::status_cpu::get_cpu_status_between_dates;
+use self::status_cpu::insert_cpu_status_frame;
 
 mod status_disk;
-use self::status_disk::{create_disk_status_frame_singles_table, create_disk_status_frames_table};
-pub use self::status_disk::{get_disk_status_between_dates, insert_disk_status_frame};
+pub use self::status_disk::get_disk_status_between_dates;
+use self::status_disk::insert_disk_status_frame;
 
 mod status_mem;
-use self::status_mem::{create_mem_status_frame_singles_table, create_mem_status_frames_table};
-pub use self::status_mem::{get_mem_status_between_dates, insert_mem_status_frame};
+pub use self::status_mem::get_mem_status_between_dates;
+use self::status_mem::insert_mem_status_frame;
 
-use crate::persistence::SQLConnection;
-pub use crate::persistence::{get_default_sql_connection, get_sql_connection, FetchId};
+mod status_temperature;
+pub use self::status_temperature::get_temperature_status_between_dates;
+use self::status_temperature::insert_temperature_status_frame;
+
+mod status_cgroup;
+pub use self::status_cgroup::get_cgroup_status_between_dates;
+use self::status_cgroup::insert_cgroup_status_frame;
+
+mod status_pressure;
+pub use self::status_pressure::get_pressure_status_between_dates;
+use self::status_pressure::insert_pressure_status_frame;
+
+mod status_rollups;
+pub use self::status_rollups::{
+    get_status_buckets_between_dates, update_status_rollups, RollupSource, CPU_ROLLUP_SOURCE,
+    DISK_ROLLUP_SOURCE, MEM_ROLLUP_SOURCE, ROLLUP_SOURCES,
+};
+
+mod status_frames;
+pub use self::status_frames::{fetch_latest_status_check, insert_status_frames, StatusFrame};
 
-pub async fn init_db(conn: &SQLConnection) -> Result<(), sqlx::Error> {
-    create_monitor_configs_table(conn).await?;
+mod temperature_thresholds;
+pub use self::temperature_thresholds::{
+    fetch_temperature_thresholds, replace_temperature_thresholds,
+};
 
-    create_hardware_cpu_infos_table(conn).await?;
-    create_hardware_disk_infos_table(conn).await?;
-    create_hardware_mem_infos_table(conn).await?;
+mod process_watch_events;
+pub use self::process_watch_events::{
+    fetch_latest_process_watch_event, get_process_watch_events_between_dates,
+    insert_process_watch_event,
+};
 
-    create_cpu_status_frames_table(conn).await?;
-    create_cpu_status_frame_cores_table(conn).await?;
+mod systemd_unit_events;
+pub use self::systemd_unit_events::{
+    fetch_latest_systemd_unit_event, get_systemd_unit_events_between_dates,
+    insert_systemd_unit_event,
+};
 
-    create_disk_status_frames_table(conn).await?;
-    create_disk_status_frame_singles_table(conn).await?;
+mod custom_metrics;
+pub use self::custom_metrics::{get_custom_metrics_between_dates, insert_custom_metrics};
 
-    create_mem_status_frames_table(conn).await?;
-    create_mem_status_frame_singles_table(conn).await?;
+mod custom_metric_thresholds;
+pub use self::custom_metric_thresholds::{
+    fetch_custom_metric_thresholds, replace_custom_metric_thresholds,
+};
 
-    Ok(())
-}
+mod endpoint_checks;
+pub use self::endpoint_checks::{get_endpoint_checks_between_dates, insert_endpoint_check};
+
+mod log_matches;
+pub use self::log_matches::{get_log_matches_between_dates, insert_log_matches};
+
+mod certificate_checks;
+pub use self::certificate_checks::{
+    get_certificate_checks_between_dates, insert_certificate_check,
+};
+
+mod hardware_change_events;
+pub use self::hardware_change_events::get_hardware_change_events_between_dates;
+
+mod boot_events;
+pub use self::boot_events::{
+    fetch_latest_boot_event, get_boot_events_between_dates, insert_boot_event,
+};
+
+use crate::persistence::SQLConnection;
+pub use crate::persistence::{get_default_sql_connection, get_sql_connection, FetchId};
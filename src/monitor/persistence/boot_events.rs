@@ -0,0 +1,64 @@
+use crate::monitor::models::get_boot_events::BootEvent;
+
+use super::get_default_sql_connection;
+
+const BOOT_EVENTS_TABLE_NAME: &str = "boot_events";
+
+pub async fn insert_boot_event(event: &BootEvent) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (boot_id, boot_time, is_reboot, last_sample, downtime_secs, is_unclean, last_check)
+        VALUES (?, ?, ?, ?, ?, ?, ?)",
+        BOOT_EVENTS_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&event.boot_id)
+        .bind(&event.boot_time)
+        .bind(&event.is_reboot)
+        .bind(&event.last_sample)
+        .bind(&event.downtime_secs)
+        .bind(&event.is_unclean)
+        .bind(&event.last_check)
+        .execute(&conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_latest_boot_event() -> Result<Option<BootEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} ORDER BY id DESC LIMIT 1",
+        BOOT_EVENTS_TABLE_NAME
+    );
+    let event = sqlx::query_as::<_, BootEvent>(&statement)
+        .fetch_optional(&conn)
+        .await?;
+
+    Ok(event)
+}
+
+pub async fn get_boot_events_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<BootEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE boot_time BETWEEN ? AND ?
+        ORDER BY boot_time",
+        BOOT_EVENTS_TABLE_NAME
+    );
+    let events = sqlx::query_as::<_, BootEvent>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(events)
+}
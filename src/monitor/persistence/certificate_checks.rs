@@ -0,0 +1,55 @@
+use crate::monitor::models::get_certificate_checks::CertificateCheck;
+
+use super::get_default_sql_connection;
+
+const CERTIFICATE_CHECKS_TABLE_NAME: &str = "certificate_checks";
+
+pub async fn insert_certificate_check(check: &CertificateCheck) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (path, status, subject, issuer, not_after, days_to_expiry, message, last_check)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        CERTIFICATE_CHECKS_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&check.path)
+        .bind(&check.status)
+        .bind(&check.subject)
+        .bind(&check.issuer)
+        .bind(&check.not_after)
+        .bind(&check.days_to_expiry)
+        .bind(&check.message)
+        .bind(&check.last_check)
+        .execute(&conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_certificate_checks_between_dates(
+    start_date: i64,
+    end_date: i64,
+    path: Option<&str>,
+) -> Result<Vec<CertificateCheck>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        AND (? IS NULL OR path = ?)
+        ORDER BY last_check",
+        CERTIFICATE_CHECKS_TABLE_NAME
+    );
+    let checks = sqlx::query_as::<_, CertificateCheck>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(&path)
+        .bind(&path)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(checks)
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::monitor::models::get_custom_metrics::CustomMetricThreshold;
+
+use super::get_default_sql_connection;
+
+const CUSTOM_METRIC_THRESHOLDS_TABLE_NAME: &str = "custom_metric_thresholds";
+
+// replaces all of the device's thresholds with the given ones
+pub async fn replace_custom_metric_thresholds(
+    device_id: &str,
+    thresholds: &HashMap<String, HashMap<String, f64>>,
+) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let delete_statement = format!(
+        "DELETE FROM {} WHERE device_id = ?",
+        CUSTOM_METRIC_THRESHOLDS_TABLE_NAME
+    );
+    sqlx::query(&delete_statement)
+        .bind(&device_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let insert_statement = format!(
+        "INSERT INTO {} (device_id, probe, key, threshold) VALUES (?, ?, ?, ?)",
+        CUSTOM_METRIC_THRESHOLDS_TABLE_NAME
+    );
+    for (probe, keys) in thresholds {
+        for (key, threshold) in keys {
+            sqlx::query(&insert_statement)
+                .bind(&device_id)
+                .bind(&probe)
+                .bind(&key)
+                .bind(&threshold)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn fetch_custom_metric_thresholds(
+    device_id: &str,
+) -> Result<Vec<CustomMetricThreshold>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} WHERE device_id = ?",
+        CUSTOM_METRIC_THRESHOLDS_TABLE_NAME
+    );
+    let thresholds = sqlx::query_as::<_, CustomMetricThreshold>(&statement)
+        .bind(&device_id)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(thresholds)
+}
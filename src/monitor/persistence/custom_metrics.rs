@@ -0,0 +1,59 @@
+use crate::monitor::models::get_custom_metrics::CustomMetric;
+
+use super::get_default_sql_connection;
+
+const CUSTOM_METRIC_TABLE_NAME: &str = "custom_metric";
+
+// all of the metrics a single probe run reported
+pub async fn insert_custom_metrics(metrics: &[CustomMetric]) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (probe, key, value, status, message, last_check)
+        VALUES (?, ?, ?, ?, ?, ?)",
+        CUSTOM_METRIC_TABLE_NAME
+    );
+    for metric in metrics {
+        sqlx::query(&statement)
+            .bind(&metric.probe)
+            .bind(&metric.key)
+            .bind(&metric.value)
+            .bind(&metric.status)
+            .bind(&metric.message)
+            .bind(&metric.last_check)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_custom_metrics_between_dates(
+    start_date: i64,
+    end_date: i64,
+    probe: Option<&str>,
+) -> Result<Vec<CustomMetric>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        AND (? IS NULL OR probe = ?)
+        ORDER BY last_check",
+        CUSTOM_METRIC_TABLE_NAME
+    );
+    let metrics = sqlx::query_as::<_, CustomMetric>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(&probe)
+        .bind(&probe)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(metrics)
+}
@@ -0,0 +1,53 @@
+use crate::monitor::models::get_endpoint_checks::EndpointCheck;
+
+use super::get_default_sql_connection;
+
+const ENDPOINT_CHECKS_TABLE_NAME: &str = "endpoint_checks";
+
+pub async fn insert_endpoint_check(check: &EndpointCheck) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (name, is_up, latency_ms, status_code, message, last_check)
+        VALUES (?, ?, ?, ?, ?, ?)",
+        ENDPOINT_CHECKS_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&check.name)
+        .bind(&check.is_up)
+        .bind(&check.latency_ms)
+        .bind(&check.status_code)
+        .bind(&check.message)
+        .bind(&check.last_check)
+        .execute(&conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_endpoint_checks_between_dates(
+    start_date: i64,
+    end_date: i64,
+    name: Option<&str>,
+) -> Result<Vec<EndpointCheck>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        AND (? IS NULL OR name = ?)
+        ORDER BY last_check",
+        ENDPOINT_CHECKS_TABLE_NAME
+    );
+    let checks = sqlx::query_as::<_, EndpointCheck>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(&name)
+        .bind(&name)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(checks)
+}
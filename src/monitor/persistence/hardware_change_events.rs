@@ -0,0 +1,50 @@
+use sqlx::SqliteConnection;
+
+use crate::monitor::models::get_hardware_changes::HardwareChangeEvent;
+
+use super::get_default_sql_connection;
+
+const HARDWARE_CHANGE_EVENTS_TABLE_NAME: &str = "hardware_change_events";
+
+// written in the same transaction as the new hardware versions
+pub(super) async fn insert_hardware_change_events(
+    conn: &mut SqliteConnection,
+    events: &[HardwareChangeEvent],
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (kind, hardware_id, description, last_check) VALUES (?, ?, ?, ?)",
+        HARDWARE_CHANGE_EVENTS_TABLE_NAME
+    );
+    for event in events {
+        sqlx::query(&statement)
+            .bind(&event.kind)
+            .bind(&event.hardware_id)
+            .bind(&event.description)
+            .bind(&event.last_check)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_hardware_change_events_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<HardwareChangeEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        ORDER BY last_check",
+        HARDWARE_CHANGE_EVENTS_TABLE_NAME
+    );
+    let events = sqlx::query_as::<_, HardwareChangeEvent>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(events)
+}
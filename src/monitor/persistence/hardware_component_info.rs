@@ -0,0 +1,58 @@
+use sqlx::SqliteConnection;
+
+use crate::monitor::models::get_hardware_info::HardwareComponentInfo;
+
+use super::hardware_info::{
+    close_hardware_version, fetch_current_hardware_versions, fetch_hardware_versions_between_dates,
+};
+
+const HARDWARE_COMPONENT_INFOS_TABLE_NAME: &str = "component_infos";
+
+pub(super) async fn insert_hardware_component_info(
+    conn: &mut SqliteConnection,
+    info: &HardwareComponentInfo,
+    valid_from: i64,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (component_id, label, critical, last_check, valid_from) VALUES (?, ?, ?, ?, ?)",
+        HARDWARE_COMPONENT_INFOS_TABLE_NAME
+    );
+    sqlx::query(&statement)
+        .bind(&info.component_id)
+        .bind(&info.label)
+        .bind(&info.critical)
+        .bind(&info.last_check)
+        .bind(&valid_from)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub(super) async fn close_hardware_component_info(
+    conn: &mut SqliteConnection,
+    component_id: &str,
+    valid_to: i64,
+) -> Result<(), sqlx::Error> {
+    close_hardware_version(
+        conn,
+        HARDWARE_COMPONENT_INFOS_TABLE_NAME,
+        "component_id",
+        component_id,
+        valid_to,
+    )
+    .await
+}
+
+pub(super) async fn fetch_latest_hardware_components_info(
+) -> Result<Vec<HardwareComponentInfo>, sqlx::Error> {
+    fetch_current_hardware_versions(HARDWARE_COMPONENT_INFOS_TABLE_NAME).await
+}
+
+pub(super) async fn fetch_hardware_components_info_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<HardwareComponentInfo>, sqlx::Error> {
+    fetch_hardware_versions_between_dates(HARDWARE_COMPONENT_INFOS_TABLE_NAME, start_date, end_date)
+        .await
+}
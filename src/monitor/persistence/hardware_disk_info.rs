@@ -1,100 +1,62 @@
-use crate::{monitor::models::get_hardware_info::HardwareDiskInfo, persistence::SQLConnection};
+use sqlx::SqliteConnection;
 
-use super::{get_default_sql_connection, FetchId};
+use crate::monitor::models::get_hardware_info::HardwareDiskInfo;
 
-const HARDWARE_DISK_INFOS_TABLE_NAME: &str = "disk_infos";
+use super::hardware_info::{
+    close_hardware_version, fetch_current_hardware_versions, fetch_hardware_versions_between_dates,
+};
 
-pub(super) async fn insert_hardware_disk_info(info: &HardwareDiskInfo) -> Result<(), sqlx::Error> {
-    let conn = get_default_sql_connection().await?;
+const HARDWARE_DISK_INFOS_TABLE_NAME: &str = "disk_infos";
 
-    // check if a record with the same cpu_id already exists
-    let exists_record_check = format!(
-        "SELECT id FROM {} WHERE disk_id = ?",
+pub(super) async fn insert_hardware_disk_info(
+    conn: &mut SqliteConnection,
+    info: &HardwareDiskInfo,
+    valid_from: i64,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (disk_id, name, fs_type, kind, is_removable, mount_point, total_space, last_check, valid_from) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
         HARDWARE_DISK_INFOS_TABLE_NAME
     );
-
-    let exists_check_res = sqlx::query_as::<_, FetchId>(&exists_record_check)
+    sqlx::query(&statement)
         .bind(&info.disk_id)
-        .fetch_optional(&conn)
+        .bind(&info.name)
+        .bind(&info.fs_type)
+        .bind(&info.kind)
+        .bind(&info.is_removable)
+        .bind(&info.mount_point)
+        .bind(&info.total_space)
+        .bind(&info.last_check)
+        .bind(&valid_from)
+        .execute(&mut *conn)
         .await?;
 
-    // if exists, update it
-    match exists_check_res {
-        Some(value) => {
-            let statement = format!(
-                "UPDATE {} 
-        SET last_check = ?
-        WHERE id = ?",
-                HARDWARE_DISK_INFOS_TABLE_NAME
-            );
-
-            sqlx::query(&statement)
-                .bind(&info.last_check)
-                .bind(&value.id)
-                .execute(&conn)
-                .await?;
-        }
-        None => {
-            let statement = format!(
-                "INSERT INTO {} (disk_id, name, fs_type, kind, is_removable, mount_point, total_space, last_check) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                HARDWARE_DISK_INFOS_TABLE_NAME
-            );
-            sqlx::query(&statement)
-                .bind(&info.disk_id)
-                .bind(&info.name)
-                .bind(&info.fs_type)
-                .bind(&info.kind)
-                .bind(&info.is_removable)
-                .bind(&info.mount_point)
-                .bind(&info.total_space)
-                .bind(&info.last_check)
-                .execute(&conn)
-                .await?;
-        }
-    };
-
     Ok(())
 }
 
+pub(super) async fn close_hardware_disk_info(
+    conn: &mut SqliteConnection,
+    disk_id: &str,
+    valid_to: i64,
+) -> Result<(), sqlx::Error> {
+    close_hardware_version(
+        conn,
+        HARDWARE_DISK_INFOS_TABLE_NAME,
+        "disk_id",
+        disk_id,
+        valid_to,
+    )
+    .await
+}
+
 pub(super) async fn fetch_latest_hardware_disks_info() -> Result<Vec<HardwareDiskInfo>, sqlx::Error>
 {
-    let conn = get_default_sql_connection().await?;
-
-    // get all with distinct disk_id
-    let statement = format!(
-        "
-    SELECT *
-    FROM {}
-",
-        HARDWARE_DISK_INFOS_TABLE_NAME
-    );
-
-    let info = sqlx::query_as::<_, HardwareDiskInfo>(&statement)
-        .fetch_all(&conn)
-        .await?;
-
-    Ok(info)
+    fetch_current_hardware_versions(HARDWARE_DISK_INFOS_TABLE_NAME).await
 }
 
-pub(super) async fn create_hardware_disk_infos_table(
-    conn: &SQLConnection,
-) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        disk_id TEXT NOT NULL,
-        name TEXT NOT NULL,
-        fs_type TEXT NOT NULL,
-        kind TEXT NOT NULL,
-        is_removable INTEGER NOT NULL,
-        mount_point TEXT NOT NULL,
-        total_space INTEGER NOT NULL,
-        last_check INTEGER NOT NULL
-    )",
-        HARDWARE_DISK_INFOS_TABLE_NAME
-    );
-
-    sqlx::query(&statement).execute(conn).await?;
-
-    Ok(())
+pub(super) async fn fetch_hardware_disks_info_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<HardwareDiskInfo>, sqlx::Error> {
+    fetch_hardware_versions_between_dates(HARDWARE_DISK_INFOS_TABLE_NAME, start_date, end_date)
+        .await
 }
@@ -1,33 +1,178 @@
+use sqlx::{sqlite::SqliteRow, FromRow, SqliteConnection};
+
+use crate::monitor::models::get_hardware_changes::{HardwareChangeEvent, HardwareInfoDiff};
 use crate::monitor::models::get_hardware_info::HardwareInfo;
 
-use super::hardware_cpu_info::{fetch_latest_hardware_cpus_info, insert_hardware_cpu_info};
-use super::hardware_disk_info::{fetch_latest_hardware_disks_info, insert_hardware_disk_info};
-use super::hardware_mem_info::{fetch_latest_hardware_mems_info, insert_hardware_mem_info};
+use super::get_default_sql_connection;
+use super::hardware_change_events::insert_hardware_change_events;
+use super::hardware_component_info::{
+    close_hardware_component_info, fetch_hardware_components_info_between_dates,
+    fetch_latest_hardware_components_info, insert_hardware_component_info,
+};
+use super::hardware_cpu_info::{
+    close_hardware_cpu_info, fetch_hardware_cpus_info_between_dates,
+    fetch_latest_hardware_cpus_info, insert_hardware_cpu_info,
+};
+use super::hardware_disk_info::{
+    close_hardware_disk_info, fetch_hardware_disks_info_between_dates,
+    fetch_latest_hardware_disks_info, insert_hardware_disk_info,
+};
+use super::hardware_mem_info::{
+    close_hardware_mem_info, fetch_hardware_mems_info_between_dates,
+    fetch_latest_hardware_mems_info, insert_hardware_mem_info,
+};
+
+// writes the new versions of the changed hardware, and closes the previous ones,
+// every version is valid from `changed_at`
+pub async fn insert_hardware_info_changes(
+    diff: &HardwareInfoDiff,
+    events: &[HardwareChangeEvent],
+    changed_at: i64,
+) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let mut tx = conn.begin().await?;
 
-pub async fn insert_hardware_info(status: &HardwareInfo) -> Result<(), sqlx::Error> {
-    for cpu_info in status.cpu_info.iter() {
-        insert_hardware_cpu_info(cpu_info).await?;
+    for (previous, current) in diff.cpu_info.changed.iter() {
+        close_hardware_cpu_info(&mut tx, &previous.cpu_id, changed_at).await?;
+        insert_hardware_cpu_info(&mut tx, current, changed_at).await?;
+    }
+    for info in diff.cpu_info.removed.iter() {
+        close_hardware_cpu_info(&mut tx, &info.cpu_id, changed_at).await?;
+    }
+    for info in diff.cpu_info.added.iter() {
+        insert_hardware_cpu_info(&mut tx, info, changed_at).await?;
+    }
+
+    for (previous, current) in diff.disks_info.changed.iter() {
+        close_hardware_disk_info(&mut tx, &previous.disk_id, changed_at).await?;
+        insert_hardware_disk_info(&mut tx, current, changed_at).await?;
+    }
+    for info in diff.disks_info.removed.iter() {
+        close_hardware_disk_info(&mut tx, &info.disk_id, changed_at).await?;
+    }
+    for info in diff.disks_info.added.iter() {
+        insert_hardware_disk_info(&mut tx, info, changed_at).await?;
     }
 
-    for disk_info in status.disks_info.iter() {
-        insert_hardware_disk_info(disk_info).await?;
+    for (previous, current) in diff.mem_info.changed.iter() {
+        close_hardware_mem_info(&mut tx, &previous.mem_id, changed_at).await?;
+        insert_hardware_mem_info(&mut tx, current, changed_at).await?;
+    }
+    for info in diff.mem_info.removed.iter() {
+        close_hardware_mem_info(&mut tx, &info.mem_id, changed_at).await?;
+    }
+    for info in diff.mem_info.added.iter() {
+        insert_hardware_mem_info(&mut tx, info, changed_at).await?;
     }
 
-    for mem_info in status.mem_info.iter() {
-        insert_hardware_mem_info(mem_info).await?;
+    for (previous, current) in diff.components_info.changed.iter() {
+        close_hardware_component_info(&mut tx, &previous.component_id, changed_at).await?;
+        insert_hardware_component_info(&mut tx, current, changed_at).await?;
+    }
+    for info in diff.components_info.removed.iter() {
+        close_hardware_component_info(&mut tx, &info.component_id, changed_at).await?;
     }
+    for info in diff.components_info.added.iter() {
+        insert_hardware_component_info(&mut tx, info, changed_at).await?;
+    }
+
+    insert_hardware_change_events(&mut tx, events).await?;
+
+    tx.commit().await?;
 
     Ok(())
 }
 
+// the hardware in use right now
 pub async fn fetch_latest_hardware_info() -> Result<HardwareInfo, sqlx::Error> {
     let cpu_info = fetch_latest_hardware_cpus_info().await?;
     let disks_info = fetch_latest_hardware_disks_info().await?;
     let mem_info = fetch_latest_hardware_mems_info().await?;
+    let components_info = fetch_latest_hardware_components_info().await?;
+
+    Ok(HardwareInfo {
+        cpu_info,
+        disks_info,
+        mem_info,
+        components_info,
+    })
+}
+
+// every version of the hardware that was in use at some point between the dates
+pub async fn get_hardware_info_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<HardwareInfo, sqlx::Error> {
+    let cpu_info = fetch_hardware_cpus_info_between_dates(start_date, end_date).await?;
+    let disks_info = fetch_hardware_disks_info_between_dates(start_date, end_date).await?;
+    let mem_info = fetch_hardware_mems_info_between_dates(start_date, end_date).await?;
+    let components_info =
+        fetch_hardware_components_info_between_dates(start_date, end_date).await?;
 
     Ok(HardwareInfo {
         cpu_info,
         disks_info,
         mem_info,
+        components_info,
     })
 }
+
+pub(super) async fn close_hardware_version(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+    id_column: &str,
+    hardware_id: &str,
+    valid_to: i64,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "UPDATE {} SET valid_to = ? WHERE {} = ? AND valid_to IS NULL",
+        table_name, id_column
+    );
+
+    sqlx::query(&statement)
+        .bind(&valid_to)
+        .bind(&hardware_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub(super) async fn fetch_current_hardware_versions<T>(
+    table_name: &str,
+) -> Result<Vec<T>, sqlx::Error>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!("SELECT * FROM {} WHERE valid_to IS NULL", table_name);
+
+    sqlx::query_as::<_, T>(&statement).fetch_all(&conn).await
+}
+
+pub(super) async fn fetch_hardware_versions_between_dates<T>(
+    table_name: &str,
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<T>, sqlx::Error>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {}
+        WHERE valid_from <= ?
+        AND (valid_to IS NULL OR valid_to >= ?)
+        ORDER BY valid_from",
+        table_name
+    );
+
+    sqlx::query_as::<_, T>(&statement)
+        .bind(&end_date)
+        .bind(&start_date)
+        .fetch_all(&conn)
+        .await
+}
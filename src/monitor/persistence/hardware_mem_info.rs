@@ -1,89 +1,55 @@
-use crate::{monitor::models::get_hardware_info::HardwareMemInfo, persistence::SQLConnection};
+use sqlx::SqliteConnection;
 
-use super::{get_default_sql_connection, FetchId};
+use crate::monitor::models::get_hardware_info::HardwareMemInfo;
 
-const HARDWARE_MEM_INFOS_TABLE_NAME: &str = "mem_infos";
+use super::hardware_info::{
+    close_hardware_version, fetch_current_hardware_versions, fetch_hardware_versions_between_dates,
+};
 
-pub(super) async fn insert_hardware_mem_info(info: &HardwareMemInfo) -> Result<(), sqlx::Error> {
-    let conn = get_default_sql_connection().await?;
+const HARDWARE_MEM_INFOS_TABLE_NAME: &str = "mem_infos";
 
-    // check if a record with the same cpu_id already exists
-    let exists_record_check = format!(
-        "SELECT id FROM {} WHERE mem_id = ?",
+pub(super) async fn insert_hardware_mem_info(
+    conn: &mut SqliteConnection,
+    info: &HardwareMemInfo,
+    valid_from: i64,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (mem_id, total_space, last_check, valid_from) VALUES (?, ?, ?, ?)",
         HARDWARE_MEM_INFOS_TABLE_NAME
     );
-
-    let exists_check_res = sqlx::query_as::<_, FetchId>(&exists_record_check)
+    sqlx::query(&statement)
         .bind(&info.mem_id)
-        .fetch_optional(&conn)
+        .bind(&info.total_space)
+        .bind(&info.last_check)
+        .bind(&valid_from)
+        .execute(&mut *conn)
         .await?;
 
-    // if exists, update it
-    match exists_check_res {
-        Some(value) => {
-            let statement = format!(
-                "UPDATE {} 
-        SET last_check = ?
-        WHERE id = ?",
-                HARDWARE_MEM_INFOS_TABLE_NAME
-            );
-
-            sqlx::query(&statement)
-                .bind(&info.last_check)
-                .bind(&value.id)
-                .execute(&conn)
-                .await?;
-        }
-        None => {
-            let statement = format!(
-                "INSERT INTO {} (mem_id, total_space, last_check) VALUES (?, ?, ?)",
-                HARDWARE_MEM_INFOS_TABLE_NAME
-            );
-            sqlx::query(&statement)
-                .bind(&info.mem_id)
-                .bind(&info.total_space)
-                .bind(&info.last_check)
-                .execute(&conn)
-                .await?;
-        }
-    };
-
     Ok(())
 }
 
-pub(super) async fn fetch_latest_hardware_mems_info() -> Result<Vec<HardwareMemInfo>, sqlx::Error> {
-    let conn = get_default_sql_connection().await?;
-
-    // get all with distinct mem_id
-    let statement = format!(
-        "
-    SELECT *
-    FROM {}
-",
-        HARDWARE_MEM_INFOS_TABLE_NAME
-    );
-
-    let info = sqlx::query_as::<_, HardwareMemInfo>(&statement)
-        .fetch_all(&conn)
-        .await?;
-
-    Ok(info)
-}
-
-pub(super) async fn create_hardware_mem_infos_table(
-    conn: &SQLConnection,
+pub(super) async fn close_hardware_mem_info(
+    conn: &mut SqliteConnection,
+    mem_id: &str,
+    valid_to: i64,
 ) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        mem_id TEXT NOT NULL,
-        total_space INTEGER NOT NULL,
-        last_check INTEGER NOT NULL
-    )",
-        HARDWARE_MEM_INFOS_TABLE_NAME
-    );
+    close_hardware_version(
+        conn,
+        HARDWARE_MEM_INFOS_TABLE_NAME,
+        "mem_id",
+        mem_id,
+        valid_to,
+    )
+    .await
+}
 
-    sqlx::query(&statement).execute(conn).await?;
+pub(super) async fn fetch_latest_hardware_mems_info() -> Result<Vec<HardwareMemInfo>, sqlx::Error> {
+    fetch_current_hardware_versions(HARDWARE_MEM_INFOS_TABLE_NAME).await
+}
 
-    Ok(())
+pub(super) async fn fetch_hardware_mems_info_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<HardwareMemInfo>, sqlx::Error> {
+    fetch_hardware_versions_between_dates(HARDWARE_MEM_INFOS_TABLE_NAME, start_date, end_date).await
 }
@@ -0,0 +1,106 @@
+use crate::monitor::models::get_log_matches::{LogMatch, LogMatchCount};
+
+use super::get_default_sql_connection;
+
+const LOG_MATCHES_TABLE_NAME: &str = "log_matches";
+const LOG_MATCH_COUNTS_TABLE_NAME: &str = "log_match_counts";
+
+// inserts the matches of a single interval, and deletes the oldest
+// matches of the watch so only `max_history` of them are kept
+pub async fn insert_log_matches(
+    watch: &str,
+    matches: &[LogMatch],
+    counts: &[LogMatchCount],
+    max_history: i64,
+) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let match_statement = format!(
+        "INSERT INTO {} (watch, pattern, line, last_check) VALUES (?, ?, ?, ?)",
+        LOG_MATCHES_TABLE_NAME
+    );
+    for single in matches {
+        sqlx::query(&match_statement)
+            .bind(&single.watch)
+            .bind(&single.pattern)
+            .bind(&single.line)
+            .bind(&single.last_check)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let count_statement = format!(
+        "INSERT INTO {} (watch, pattern, count, last_check) VALUES (?, ?, ?, ?)",
+        LOG_MATCH_COUNTS_TABLE_NAME
+    );
+    for count in counts {
+        sqlx::query(&count_statement)
+            .bind(&count.watch)
+            .bind(&count.pattern)
+            .bind(&count.count)
+            .bind(&count.last_check)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let prune_statement = format!(
+        "DELETE FROM {0}
+        WHERE watch = ?
+        AND id NOT IN (
+            SELECT id FROM {0} WHERE watch = ? ORDER BY id DESC LIMIT ?
+        )",
+        LOG_MATCHES_TABLE_NAME
+    );
+    sqlx::query(&prune_statement)
+        .bind(&watch)
+        .bind(&watch)
+        .bind(&max_history)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_log_matches_between_dates(
+    start_date: i64,
+    end_date: i64,
+    watch: Option<&str>,
+) -> Result<(Vec<LogMatch>, Vec<LogMatchCount>), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let matches_statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        AND (? IS NULL OR watch = ?)
+        ORDER BY id",
+        LOG_MATCHES_TABLE_NAME
+    );
+    let matches = sqlx::query_as::<_, LogMatch>(&matches_statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(&watch)
+        .bind(&watch)
+        .fetch_all(&conn)
+        .await?;
+
+    let counts_statement = format!(
+        "SELECT * FROM {}
+        WHERE last_check BETWEEN ? AND ?
+        AND (? IS NULL OR watch = ?)
+        ORDER BY last_check",
+        LOG_MATCH_COUNTS_TABLE_NAME
+    );
+    let counts = sqlx::query_as::<_, LogMatchCount>(&counts_statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(&watch)
+        .bind(&watch)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok((matches, counts))
+}
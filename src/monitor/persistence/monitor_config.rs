@@ -1,4 +1,4 @@
-use crate::{monitor::models::MonitorConfig, persistence::SQLConnection};
+use crate::monitor::models::MonitorConfig;
 
 use super::{get_default_sql_connection, FetchId};
 
@@ -28,6 +28,11 @@ pub async fn insert_or_update_monitor_config(
                 cpu_threshold = ?, 
                 disk_threshold = ?,
                 mem_threshold = ?,
+                inode_threshold = ?,
+                cpu_pressure_threshold = ?,
+                mem_pressure_threshold = ?,
+                io_pressure_threshold = ?,
+                cgroup_mem_threshold = ?,
                 fcm_token = ?,
                 updated_at = ?
                 WHERE id = ?",
@@ -38,6 +43,11 @@ pub async fn insert_or_update_monitor_config(
                 .bind(&config.cpu_threshold)
                 .bind(&config.disk_threshold)
                 .bind(&config.mem_threshold)
+                .bind(&config.inode_threshold)
+                .bind(&config.cpu_pressure_threshold)
+                .bind(&config.mem_pressure_threshold)
+                .bind(&config.io_pressure_threshold)
+                .bind(&config.cgroup_mem_threshold)
                 .bind(&config.fcm_token)
                 .bind(&config.updated_at)
                 .bind(value.id)
@@ -47,8 +57,8 @@ pub async fn insert_or_update_monitor_config(
         None => {
             let statement = format!(
                 "INSERT INTO {} 
-            (device_id, cpu_threshold, mem_threshold, disk_threshold, fcm_token, updated_at) 
-            VALUES (?, ?, ?, ?, ?, ?)
+            (device_id, cpu_threshold, mem_threshold, disk_threshold, inode_threshold, cpu_pressure_threshold, mem_pressure_threshold, io_pressure_threshold, cgroup_mem_threshold, fcm_token, updated_at) 
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ",
                 MONITOR_CONFIGS_TABLE_NAME
             );
@@ -57,6 +67,11 @@ pub async fn insert_or_update_monitor_config(
                 .bind(&config.cpu_threshold)
                 .bind(&config.mem_threshold)
                 .bind(&config.disk_threshold)
+                .bind(&config.inode_threshold)
+                .bind(&config.cpu_pressure_threshold)
+                .bind(&config.mem_pressure_threshold)
+                .bind(&config.io_pressure_threshold)
+                .bind(&config.cgroup_mem_threshold)
                 .bind(&config.fcm_token)
                 .bind(&config.updated_at)
                 .execute(&conn)
@@ -67,6 +82,21 @@ pub async fn insert_or_update_monitor_config(
     Ok(())
 }
 
+pub async fn fetch_monitor_config(device_id: &str) -> Result<Option<MonitorConfig>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} WHERE device_id = ?",
+        MONITOR_CONFIGS_TABLE_NAME
+    );
+    let config = sqlx::query_as::<_, MonitorConfig>(&statement)
+        .bind(&device_id)
+        .fetch_optional(&conn)
+        .await?;
+
+    Ok(config)
+}
+
 pub async fn fetch_monitor_configs() -> Result<Vec<MonitorConfig>, sqlx::Error> {
     let conn = get_default_sql_connection().await?;
 
@@ -77,22 +107,3 @@ pub async fn fetch_monitor_configs() -> Result<Vec<MonitorConfig>, sqlx::Error>
 
     Ok(configs)
 }
-
-pub(super) async fn create_monitor_configs_table(conn: &SQLConnection) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY NOT NULL,
-        device_id TEXT NOT NULL,
-        cpu_threshold REAL NOT NULL,
-        mem_threshold REAL NOT NULL,
-        disk_threshold REAL NOT NULL,
-        fcm_token TEXT NOT NULL,
-        updated_at INTEGER NOT NULL
-    )",
-        MONITOR_CONFIGS_TABLE_NAME
-    );
-
-    sqlx::query(&statement).execute(conn).await?;
-
-    Ok(())
-}
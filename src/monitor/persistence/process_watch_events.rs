@@ -0,0 +1,69 @@
+use crate::monitor::models::get_process_watch::ProcessWatchEvent;
+
+use super::get_default_sql_connection;
+
+const PROCESS_WATCH_EVENTS_TABLE_NAME: &str = "process_watch_events";
+
+pub async fn insert_process_watch_event(event: &ProcessWatchEvent) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (name, pattern, instance_count, min_instances, is_up, last_check)
+        VALUES (?, ?, ?, ?, ?, ?)",
+        PROCESS_WATCH_EVENTS_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&event.name)
+        .bind(&event.pattern)
+        .bind(&event.instance_count)
+        .bind(&event.min_instances)
+        .bind(&event.is_up)
+        .bind(&event.last_check)
+        .execute(&conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_latest_process_watch_event(
+    name: &str,
+) -> Result<Option<ProcessWatchEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "
+        SELECT *
+        FROM {}
+        WHERE name = ?
+        ORDER BY last_check DESC
+        ",
+        PROCESS_WATCH_EVENTS_TABLE_NAME
+    );
+    let event = sqlx::query_as::<_, ProcessWatchEvent>(&statement)
+        .bind(&name)
+        .fetch_optional(&conn)
+        .await?;
+
+    Ok(event)
+}
+
+pub async fn get_process_watch_events_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<ProcessWatchEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} WHERE last_check BETWEEN ? AND ? ORDER BY last_check",
+        PROCESS_WATCH_EVENTS_TABLE_NAME
+    );
+    let events = sqlx::query_as::<_, ProcessWatchEvent>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(events)
+}
@@ -0,0 +1,71 @@
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::monitor::models::get_cgroup_status::{CgroupFrameStatus, SingleCgroupInfo};
+
+use super::get_default_sql_connection;
+use super::status_frames::{
+    get_frame_rows_between_dates, get_frames_between_dates, insert_frame_row, MAX_ROWS_PER_INSERT,
+};
+
+const CGROUP_STATUS_FRAME_TABLE_NAME: &str = "cgroup_status_frame";
+const CGROUP_STATUS_FRAME_SINGLE_TABLE_NAME: &str = "cgroup_status_frame_single";
+
+pub async fn insert_cgroup_status_frame(
+    conn: &mut SqliteConnection,
+    status: &CgroupFrameStatus,
+) -> Result<(), sqlx::Error> {
+    let frame_id =
+        insert_frame_row(conn, CGROUP_STATUS_FRAME_TABLE_NAME, status.last_check).await?;
+
+    for chunk in status.cgroups.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} (frame_id, path, cpu_usage_percent, memory_current, memory_max, io_read_bytes_per_sec, io_write_bytes_per_sec, pids_current, pids_max) ",
+            CGROUP_STATUS_FRAME_SINGLE_TABLE_NAME
+        ));
+        query.push_values(chunk, |mut row, single| {
+            row.push_bind(frame_id)
+                .push_bind(&single.path)
+                .push_bind(&single.cpu_usage_percent)
+                .push_bind(&single.memory_current)
+                .push_bind(&single.memory_max)
+                .push_bind(&single.io_read_bytes_per_sec)
+                .push_bind(&single.io_write_bytes_per_sec)
+                .push_bind(&single.pids_current)
+                .push_bind(&single.pids_max);
+        });
+        query.build().execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_cgroup_status_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<CgroupFrameStatus>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let frames =
+        get_frames_between_dates(&conn, CGROUP_STATUS_FRAME_TABLE_NAME, start_date, end_date)
+            .await?;
+    let mut singles = get_frame_rows_between_dates(
+        &conn,
+        CGROUP_STATUS_FRAME_TABLE_NAME,
+        CGROUP_STATUS_FRAME_SINGLE_TABLE_NAME,
+        start_date,
+        end_date,
+        |row: &SingleCgroupInfo| row.frame_id,
+    )
+    .await?;
+
+    let frames: Vec<CgroupFrameStatus> = frames
+        .into_iter()
+        .map(|(id, last_check)| CgroupFrameStatus {
+            id,
+            last_check,
+            cgroups: singles.remove(&id).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(frames)
+}
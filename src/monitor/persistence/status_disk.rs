@@ -1,54 +1,54 @@
-use crate::{
-    monitor::models::get_disk_status::{DiskFrameStatus, SingleDiskInfo},
-    persistence::SQLConnection,
-};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::monitor::models::get_disk_status::{DiskFrameStatus, SingleDiskInfo, SingleDiskIoInfo};
 
-use super::{get_default_sql_connection, FetchId};
+use super::get_default_sql_connection;
+use super::status_frames::{
+    get_frame_rows_between_dates, get_frames_between_dates, insert_frame_row, MAX_ROWS_PER_INSERT,
+};
 
 const DISK_STATUS_FRAME_TABLE_NAME: &str = "disk_status_frame";
 const DISK_STATUS_FRAME_SINGLE_TABLE_NAME: &str = "disk_status_frame_single";
+const DISK_STATUS_FRAME_IO_TABLE_NAME: &str = "disk_status_frame_io";
 
-pub async fn insert_disk_status_frame(status: &DiskFrameStatus) -> Result<(), sqlx::Error> {
-    let conn = get_default_sql_connection().await?;
-
-    let statement = format!(
-        "INSERT INTO {} 
-        (last_check) 
-        VALUES (?)
-        RETURNING id
-        ",
-        DISK_STATUS_FRAME_TABLE_NAME
-    );
-
-    let query_res = sqlx::query_as::<_, FetchId>(&statement)
-        .bind(&status.last_check)
-        .fetch_one(&conn)
-        .await?;
-
-    let frame_id = query_res.id;
-
-    let mut owned_cores_usage = status.disks_usage.to_owned();
-    for core in owned_cores_usage.iter_mut() {
-        core.frame_id = frame_id;
-        insert_disk_status_frame_single(&core).await?;
+pub async fn insert_disk_status_frame(
+    conn: &mut SqliteConnection,
+    status: &DiskFrameStatus,
+) -> Result<(), sqlx::Error> {
+    let frame_id = insert_frame_row(conn, DISK_STATUS_FRAME_TABLE_NAME, status.last_check).await?;
+
+    for chunk in status.disks_usage.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} (frame_id, disk_id, available, inodes_total, inodes_free) ",
+            DISK_STATUS_FRAME_SINGLE_TABLE_NAME
+        ));
+        query.push_values(chunk, |mut row, single| {
+            row.push_bind(frame_id)
+                .push_bind(&single.disk_id)
+                .push_bind(&single.available)
+                .push_bind(&single.inodes_total)
+                .push_bind(&single.inodes_free);
+        });
+        query.build().execute(&mut *conn).await?;
     }
 
-    Ok(())
-}
-
-async fn insert_disk_status_frame_single(status: &SingleDiskInfo) -> Result<(), sqlx::Error> {
-    let conn = get_default_sql_connection().await?;
-
-    let statement = format!(
-        "INSERT INTO {} (frame_id, disk_id, available) VALUES (?, ?, ?)",
-        DISK_STATUS_FRAME_SINGLE_TABLE_NAME
-    );
-    sqlx::query(&statement)
-        .bind(&status.frame_id)
-        .bind(&status.disk_id)
-        .bind(&status.available)
-        .execute(&conn)
-        .await?;
+    for chunk in status.disks_io.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} (frame_id, device_name, disk_id, read_bytes_per_sec, write_bytes_per_sec, read_iops, write_iops, busy_percent) ",
+            DISK_STATUS_FRAME_IO_TABLE_NAME
+        ));
+        query.push_values(chunk, |mut row, io| {
+            row.push_bind(frame_id)
+                .push_bind(&io.device_name)
+                .push_bind(&io.disk_id)
+                .push_bind(&io.read_bytes_per_sec)
+                .push_bind(&io.write_bytes_per_sec)
+                .push_bind(&io.read_iops)
+                .push_bind(&io.write_iops)
+                .push_bind(&io.busy_percent);
+        });
+        query.build().execute(&mut *conn).await?;
+    }
 
     Ok(())
 }
@@ -59,83 +59,36 @@ pub async fn get_disk_status_between_dates(
 ) -> Result<Vec<DiskFrameStatus>, sqlx::Error> {
     let conn = get_default_sql_connection().await?;
 
-    let frames_statement = format!(
-        "SELECT id, last_check FROM {} WHERE last_check BETWEEN ? AND ?",
-        DISK_STATUS_FRAME_TABLE_NAME
-    );
-    let frames_query = sqlx::query_as::<_, (i64, i64)>(&frames_statement)
-        .bind(&start_date)
-        .bind(&end_date)
-        .fetch_all(&conn)
-        .await?;
-
-    let frame_ids = frames_query
-        .iter()
-        .map(|frame| frame.0.to_string())
-        .collect::<Vec<String>>()
-        .join(",");
-    let singles_statement = format!(
-        "SELECT * FROM {} WHERE frame_id IN ({})",
-        DISK_STATUS_FRAME_SINGLE_TABLE_NAME, frame_ids
-    );
-
-    let singles_query = sqlx::query_as::<_, SingleDiskInfo>(&singles_statement)
-        .fetch_all(&conn)
-        .await?;
-
-    let frames: Vec<DiskFrameStatus> = frames_query
-        .iter()
-        .map(|frame| {
-            let id = frame.0;
-            let last_check = frame.1;
-
-            DiskFrameStatus {
-                id,
-                last_check,
-                disks_usage: singles_query
-                    .iter()
-                    .filter(|f| f.frame_id == id)
-                    .map(|s| s.clone())
-                    .collect(),
-            }
+    let frames =
+        get_frames_between_dates(&conn, DISK_STATUS_FRAME_TABLE_NAME, start_date, end_date).await?;
+    let mut singles = get_frame_rows_between_dates(
+        &conn,
+        DISK_STATUS_FRAME_TABLE_NAME,
+        DISK_STATUS_FRAME_SINGLE_TABLE_NAME,
+        start_date,
+        end_date,
+        |row: &SingleDiskInfo| row.frame_id,
+    )
+    .await?;
+    let mut ios = get_frame_rows_between_dates(
+        &conn,
+        DISK_STATUS_FRAME_TABLE_NAME,
+        DISK_STATUS_FRAME_IO_TABLE_NAME,
+        start_date,
+        end_date,
+        |row: &SingleDiskIoInfo| row.frame_id,
+    )
+    .await?;
+
+    let frames: Vec<DiskFrameStatus> = frames
+        .into_iter()
+        .map(|(id, last_check)| DiskFrameStatus {
+            id,
+            last_check,
+            disks_usage: singles.remove(&id).unwrap_or_default(),
+            disks_io: ios.remove(&id).unwrap_or_default(),
         })
         .collect();
 
     Ok(frames)
 }
-
-pub(super) async fn create_disk_status_frames_table(
-    conn: &SQLConnection,
-) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        last_check INTEGER NOT NULL
-    )",
-        DISK_STATUS_FRAME_TABLE_NAME
-    );
-
-    sqlx::query(&statement).execute(conn).await?;
-
-    Ok(())
-}
-
-pub(super) async fn create_disk_status_frame_singles_table(
-    conn: &SQLConnection,
-) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        frame_id INTEGER NOT NULL,
-        available INTEGER NOT NULL,
-        disk_id TEXT NOT NULL,
-        FOREIGN KEY (frame_id)
-            REFERENCES {} (id)
-    )",
-        DISK_STATUS_FRAME_SINGLE_TABLE_NAME, DISK_STATUS_FRAME_TABLE_NAME
-    );
-
-    sqlx::query(&statement).execute(conn).await?;
-
-    Ok(())
-}
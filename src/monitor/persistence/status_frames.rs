@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use sqlx::{sqlite::SqliteRow, FromRow, SqliteConnection};
+
+use crate::monitor::models::{
+    get_cgroup_status::CgroupFrameStatus, get_cpu_status::CpuFrameStatus,
+    get_disk_status::DiskFrameStatus, get_mem_status::MemFrameStatus,
+    get_pressure_status::PressureFrameStatus, get_temperature_status::TemperatureFrameStatus,
+};
+
+use super::{
+    get_default_sql_connection, insert_cgroup_status_frame, insert_cpu_status_frame,
+    insert_disk_status_frame, insert_mem_status_frame, insert_pressure_status_frame,
+    insert_temperature_status_frame, FetchId, SQLConnection,
+};
+
+// sqlite limits the bound values of a statement, 32766 since 3.32, so
+// the rows of a frame are inserted in chunks well below it
+pub(super) const MAX_ROWS_PER_INSERT: usize = 500;
+
+// a sample of any collector, as it's written to the database
+#[derive(Debug, Clone)]
+pub enum StatusFrame {
+    Cpu(CpuFrameStatus),
+    Mem(MemFrameStatus),
+    Disk(DiskFrameStatus),
+    Temperature(TemperatureFrameStatus),
+    Pressure(PressureFrameStatus),
+    Cgroup(CgroupFrameStatus),
+}
+
+impl StatusFrame {
+    // the frame row and the rows of every single item in it
+    pub fn get_row_count(&self) -> usize {
+        let rows = match self {
+            StatusFrame::Cpu(frame) => frame.cores_usage.len(),
+            StatusFrame::Mem(frame) => frame.mems_usage.len(),
+            StatusFrame::Disk(frame) => frame.disks_usage.len() + frame.disks_io.len(),
+            StatusFrame::Temperature(frame) => frame.components_temperature.len(),
+            StatusFrame::Pressure(frame) => frame.pressures.len(),
+            StatusFrame::Cgroup(frame) => frame.cgroups.len(),
+        };
+
+        rows + 1
+    }
+}
+
+// the frame row, the rows of the items in it reference its id
+pub(super) async fn insert_frame_row(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+    last_check: i64,
+) -> Result<i64, sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (last_check) VALUES (?) RETURNING id",
+        table_name
+    );
+
+    let query_res = sqlx::query_as::<_, FetchId>(&statement)
+        .bind(&last_check)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(query_res.id)
+}
+
+// writes the frames in a single transaction, so a crash never leaves a frame
+// without its rows. returns the number of rows written
+pub async fn insert_status_frames(frames: &[StatusFrame]) -> Result<usize, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+    let mut tx = conn.begin().await?;
+
+    for frame in frames {
+        match frame {
+            StatusFrame::Cpu(frame) => insert_cpu_status_frame(&mut tx, frame).await?,
+            StatusFrame::Mem(frame) => insert_mem_status_frame(&mut tx, frame).await?,
+            StatusFrame::Disk(frame) => insert_disk_status_frame(&mut tx, frame).await?,
+            StatusFrame::Temperature(frame) => {
+                insert_temperature_status_frame(&mut tx, frame).await?
+            }
+            StatusFrame::Pressure(frame) => insert_pressure_status_frame(&mut tx, frame).await?,
+            StatusFrame::Cgroup(frame) => insert_cgroup_status_frame(&mut tx, frame).await?,
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(frames.iter().map(|f| f.get_row_count()).sum())
+}
+
+// every collector writes its samples to one of these
+const FRAME_TABLE_NAMES: &[&str] = &[
+    "cpu_status_frame",
+    "mem_status_frame",
+    "disk_status_frame",
+    "temperature_status_frame",
+    "pressure_status_frame",
+    "cgroup_status_frame",
+];
+
+// the time of the newest frame of any collector, so it doesn't depend on
+// which of them are enabled
+async fn fetch_latest_frame_check(conn: &SQLConnection) -> Result<Option<i64>, sqlx::Error> {
+    let statement = format!(
+        "SELECT MAX(last_check) FROM ({})",
+        FRAME_TABLE_NAMES
+            .iter()
+            .map(|t| format!("SELECT MAX(last_check) AS last_check FROM {}", t))
+            .collect::<Vec<String>>()
+            .join(" UNION ALL ")
+    );
+
+    sqlx::query_scalar::<_, Option<i64>>(&statement)
+        .fetch_one(conn)
+        .await
+}
+
+pub async fn fetch_latest_status_check() -> Result<Option<i64>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    fetch_latest_frame_check(&conn).await
+}
+
+// the ids and the times of the frames between two dates, in the order they were taken
+pub(super) async fn get_frames_between_dates(
+    conn: &SQLConnection,
+    frame_table_name: &str,
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<(i64, i64)>, sqlx::Error> {
+    let statement = format!(
+        "SELECT id, last_check FROM {} WHERE last_check BETWEEN ? AND ? ORDER BY last_check",
+        frame_table_name
+    );
+
+    sqlx::query_as::<_, (i64, i64)>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(conn)
+        .await
+}
+
+// the rows of the frames between two dates, by the id of their frame. the rows
+// are joined to their frame, so a long range is still a single statement
+pub(super) async fn get_frame_rows_between_dates<T>(
+    conn: &SQLConnection,
+    frame_table_name: &str,
+    table_name: &str,
+    start_date: i64,
+    end_date: i64,
+    get_frame_id: impl Fn(&T) -> i64,
+) -> Result<HashMap<i64, Vec<T>>, sqlx::Error>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let statement = format!(
+        "SELECT s.* FROM {} s
+        JOIN {} f ON s.frame_id = f.id
+        WHERE f.last_check BETWEEN ? AND ?
+        ORDER BY s.id",
+        table_name, frame_table_name
+    );
+
+    let rows = sqlx::query_as::<_, T>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(conn)
+        .await?;
+
+    let mut frame_rows: HashMap<i64, Vec<T>> = HashMap::new();
+    for row in rows {
+        frame_rows.entry(get_frame_id(&row)).or_default().push(row);
+    }
+
+    Ok(frame_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::monitor::models::get_cpu_status::CpuCoreInfo;
+    use crate::persistence::migrations::run_migrations;
+
+    const START: i64 = 1_700_000_000_000;
+    const FRAME_INTERVAL: i64 = 10_000;
+
+    async fn get_db() -> SQLConnection {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        run_migrations(&conn).await.unwrap();
+
+        conn
+    }
+
+    // a cpu frame every 10 seconds from START, with `cores` cores each
+    async fn insert_cpu_frames(conn: &SQLConnection, frames: i64, cores: i64) {
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?)
+            INSERT INTO cpu_status_frame (id, last_check) SELECT i, ? + i * ? FROM n",
+        )
+        .bind(&frames)
+        .bind(&START)
+        .bind(&FRAME_INTERVAL)
+        .execute(conn)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i < ? - 1)
+            INSERT INTO cpu_status_frame_core (frame_id, cpu_id, freq, usage)
+            SELECT f.id, n.i, 2000, (f.id * n.i) % 100 FROM cpu_status_frame f, n
+            ORDER BY f.id, n.i",
+        )
+        .bind(&cores)
+        .execute(conn)
+        .await
+        .unwrap();
+    }
+
+    async fn get_cpu_cores(
+        conn: &SQLConnection,
+        start_date: i64,
+        end_date: i64,
+    ) -> HashMap<i64, Vec<CpuCoreInfo>> {
+        get_frame_rows_between_dates(
+            conn,
+            "cpu_status_frame",
+            "cpu_status_frame_core",
+            start_date,
+            end_date,
+            |row: &CpuCoreInfo| row.frame_id,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetch_latest_frame_check_test() {
+        let conn = get_db().await;
+        assert_eq!(fetch_latest_frame_check(&conn).await.unwrap(), None);
+
+        insert_cpu_frames(&conn, 10, 1).await;
+        assert_eq!(
+            fetch_latest_frame_check(&conn).await.unwrap(),
+            Some(START + 10 * FRAME_INTERVAL)
+        );
+
+        // the mem frames are newer, like when the cpu collector is disabled
+        sqlx::query("INSERT INTO mem_status_frame (last_check) VALUES (?)")
+            .bind(&(START + 100 * FRAME_INTERVAL))
+            .execute(&conn)
+            .await
+            .unwrap();
+        assert_eq!(
+            fetch_latest_frame_check(&conn).await.unwrap(),
+            Some(START + 100 * FRAME_INTERVAL)
+        );
+    }
+
+    #[tokio::test]
+    async fn get_frame_rows_between_dates_test() {
+        let conn = get_db().await;
+        insert_cpu_frames(&conn, 100, 4).await;
+
+        // the frames 10 to 19
+        let start_date = START + 10 * FRAME_INTERVAL;
+        let end_date = START + 19 * FRAME_INTERVAL;
+
+        let frames = get_frames_between_dates(&conn, "cpu_status_frame", start_date, end_date)
+            .await
+            .unwrap();
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[0], (10, start_date));
+
+        let cores = get_cpu_cores(&conn, start_date, end_date).await;
+        assert_eq!(cores.len(), 10);
+        let frame_cores = &cores[&10];
+        assert_eq!(
+            frame_cores
+                .iter()
+                .map(|c| c.cpu_id.as_str())
+                .collect::<Vec<_>>(),
+            ["0", "1", "2", "3"]
+        );
+
+        // the range is read through the indexes, not by scanning the tables
+        let plan = sqlx::query_as::<_, (i64, i64, i64, String)>(
+            "EXPLAIN QUERY PLAN
+            SELECT s.* FROM cpu_status_frame_core s
+            JOIN cpu_status_frame f ON s.frame_id = f.id
+            WHERE f.last_check BETWEEN ? AND ?
+            ORDER BY s.id",
+        )
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(&conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, _, _, detail)| detail)
+        .collect::<Vec<_>>()
+        .join("\n");
+        assert!(plan.contains("cpu_status_frame_last_check_idx"), "{}", plan);
+        assert!(
+            plan.contains("cpu_status_frame_core_frame_id_idx"),
+            "{}",
+            plan
+        );
+    }
+
+    // 600000 frames of 8 cores, about 70 days of samples every 10 seconds.
+    // cargo test --release range_read_bench -- --ignored --nocapture
+    #[tokio::test]
+    #[ignore]
+    async fn range_read_bench() {
+        let frames = 600_000;
+        let conn = get_db().await;
+
+        let started = Instant::now();
+        insert_cpu_frames(&conn, frames, 8).await;
+        println!(
+            "inserted {} frames and {} cores in {:?}",
+            frames,
+            frames * 8,
+            started.elapsed()
+        );
+
+        for (name, range_frames) in [("1h", 360), ("1d", 8640), ("7d", 60480)] {
+            // the end of the samples, where the reads usually are
+            let end_date = START + frames * FRAME_INTERVAL;
+            let start_date = end_date - (range_frames - 1) * FRAME_INTERVAL;
+
+            let started = Instant::now();
+            let read_frames =
+                get_frames_between_dates(&conn, "cpu_status_frame", start_date, end_date)
+                    .await
+                    .unwrap();
+            let cores = get_cpu_cores(&conn, start_date, end_date).await;
+            let elapsed = started.elapsed();
+
+            assert_eq!(read_frames.len() as i64, range_frames);
+            assert_eq!(cores.len() as i64, range_frames);
+            println!("read {} ({} frames) in {:?}", name, range_frames, elapsed);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::monitor::models::get_pressure_status::{PressureFrameStatus, SinglePressureInfo};
+
+use super::get_default_sql_connection;
+use super::status_frames::{
+    get_frame_rows_between_dates, get_frames_between_dates, insert_frame_row, MAX_ROWS_PER_INSERT,
+};
+
+const PRESSURE_STATUS_FRAME_TABLE_NAME: &str = "pressure_status_frame";
+const PRESSURE_STATUS_FRAME_SINGLE_TABLE_NAME: &str = "pressure_status_frame_single";
+
+pub async fn insert_pressure_status_frame(
+    conn: &mut SqliteConnection,
+    status: &PressureFrameStatus,
+) -> Result<(), sqlx::Error> {
+    let frame_id =
+        insert_frame_row(conn, PRESSURE_STATUS_FRAME_TABLE_NAME, status.last_check).await?;
+
+    for chunk in status.pressures.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} (frame_id, resource, kind, avg10, avg60, avg300, total) ",
+            PRESSURE_STATUS_FRAME_SINGLE_TABLE_NAME
+        ));
+        query.push_values(chunk, |mut row, single| {
+            row.push_bind(frame_id)
+                .push_bind(&single.resource)
+                .push_bind(&single.kind)
+                .push_bind(&single.avg10)
+                .push_bind(&single.avg60)
+                .push_bind(&single.avg300)
+                .push_bind(&single.total);
+        });
+        query.build().execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_pressure_status_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<PressureFrameStatus>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let frames = get_frames_between_dates(
+        &conn,
+        PRESSURE_STATUS_FRAME_TABLE_NAME,
+        start_date,
+        end_date,
+    )
+    .await?;
+    let mut singles = get_frame_rows_between_dates(
+        &conn,
+        PRESSURE_STATUS_FRAME_TABLE_NAME,
+        PRESSURE_STATUS_FRAME_SINGLE_TABLE_NAME,
+        start_date,
+        end_date,
+        |row: &SinglePressureInfo| row.frame_id,
+    )
+    .await?;
+
+    let frames: Vec<PressureFrameStatus> = frames
+        .into_iter()
+        .map(|(id, last_check)| PressureFrameStatus {
+            id,
+            last_check,
+            pressures: singles.remove(&id).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(frames)
+}
@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::monitor::models::get_status_rollups::{
+    Aggregate, Resolution, StatusBucket, StatusQuery, StatusRollup,
+};
+
+use super::status_frames::MAX_ROWS_PER_INSERT;
+use super::{get_default_sql_connection, SQLConnection};
+
+// table name of the rollup -> the end of the last bucket it has
+const STATUS_ROLLUP_STATE_TABLE_NAME: &str = "status_rollup_state";
+
+// the buckets of a day are rolled up in a single transaction, so catching
+// up on a large database doesn't block the frame writer for long. a multiple
+// of every bucket length, so the buckets never span two transactions
+const MAX_ROLLUP_SPAN_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+// a status frame table whose single items are rolled up by their id
+pub struct RollupSource {
+    // also the collector it's sampled by
+    pub name: &'static str,
+    pub frame_table_name: &'static str,
+    pub single_table_name: &'static str,
+    pub entity_column: &'static str,
+    pub value_column: &'static str,
+    // the singles of a frame share their id, like the cores of a cpu, so
+    // they're rolled up by their id and their position in the frame
+    pub has_positions: bool,
+}
+
+pub const CPU_ROLLUP_SOURCE: RollupSource = RollupSource {
+    name: "cpu",
+    frame_table_name: "cpu_status_frame",
+    single_table_name: "cpu_status_frame_core",
+    entity_column: "cpu_id",
+    value_column: "usage",
+    has_positions: true,
+};
+
+pub const MEM_ROLLUP_SOURCE: RollupSource = RollupSource {
+    name: "mem",
+    frame_table_name: "mem_status_frame",
+    single_table_name: "mem_status_frame_single",
+    entity_column: "mem_id",
+    value_column: "available",
+    has_positions: false,
+};
+
+pub const DISK_ROLLUP_SOURCE: RollupSource = RollupSource {
+    name: "disk",
+    frame_table_name: "disk_status_frame",
+    single_table_name: "disk_status_frame_single",
+    entity_column: "disk_id",
+    value_column: "available",
+    has_positions: false,
+};
+
+pub const ROLLUP_SOURCES: [&RollupSource; 3] =
+    [&CPU_ROLLUP_SOURCE, &MEM_ROLLUP_SOURCE, &DISK_ROLLUP_SOURCE];
+
+impl RollupSource {
+    // e.g. cpu_status_rollup_15m
+    pub fn get_table_name(&self, resolution: Resolution) -> String {
+        format!("{}_status_rollup_{}", self.name, resolution.get_name())
+    }
+
+    // the id of a single in its frame, e.g. `<cpu_id>:0003` for the fourth
+    // core. the position is padded, so the cores are ordered by it
+    fn get_entity_expression(&self) -> String {
+        if !self.has_positions {
+            return format!("s.{}", self.entity_column);
+        }
+
+        format!(
+            "printf('%s:%04d', s.{0}, ROW_NUMBER() OVER (PARTITION BY s.frame_id, s.{0} ORDER BY s.id) - 1)",
+            self.entity_column
+        )
+    }
+
+    // the id of the cpu, mem or disk of a bucket, without its position
+    pub fn get_entity_id<'a>(&self, bucket_entity_id: &'a str) -> &'a str {
+        if !self.has_positions {
+            return bucket_entity_id;
+        }
+
+        bucket_entity_id
+            .rsplit_once(':')
+            .map_or(bucket_entity_id, |(entity_id, _)| entity_id)
+    }
+}
+
+// the nearest rank percentile of sorted values
+fn get_percentile(sorted_values: &[f64], percentile: f64) -> f64 {
+    let rank = (percentile / 100.0 * sorted_values.len() as f64).ceil() as usize;
+
+    sorted_values[rank.clamp(1, sorted_values.len()) - 1]
+}
+
+// the (last_check, entity_id, value) samples in buckets of `bucket_millis`
+fn compute_rollups(values: &[(i64, String, f64)], bucket_millis: i64) -> Vec<StatusRollup> {
+    let mut buckets: BTreeMap<(i64, &str), Vec<f64>> = BTreeMap::new();
+
+    for (last_check, entity_id, value) in values {
+        let bucket_start = last_check - last_check.rem_euclid(bucket_millis);
+
+        buckets
+            .entry((bucket_start, entity_id))
+            .or_default()
+            .push(*value);
+    }
+
+    buckets
+        .into_iter()
+        .map(|((bucket_start, entity_id), mut values)| {
+            values.sort_by(|a, b| a.total_cmp(b));
+
+            StatusRollup {
+                bucket_start,
+                entity_id: entity_id.to_string(),
+                min_value: values[0],
+                max_value: values[values.len() - 1],
+                avg_value: values.iter().sum::<f64>() / values.len() as f64,
+                p95_value: get_percentile(&values, 95.0),
+                samples: values.len() as i64,
+            }
+        })
+        .collect()
+}
+
+async fn fetch_rolled_up_to(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let statement = format!(
+        "SELECT rolled_up_to FROM {} WHERE table_name = ?",
+        STATUS_ROLLUP_STATE_TABLE_NAME
+    );
+
+    sqlx::query_scalar::<_, i64>(&statement)
+        .bind(&table_name)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+async fn save_rolled_up_to(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+    rolled_up_to: i64,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT OR REPLACE INTO {} (table_name, rolled_up_to) VALUES (?, ?)",
+        STATUS_ROLLUP_STATE_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&table_name)
+        .bind(&rolled_up_to)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn fetch_first_check(
+    conn: &mut SqliteConnection,
+    source: &RollupSource,
+) -> Result<Option<i64>, sqlx::Error> {
+    let statement = format!("SELECT MIN(last_check) FROM {}", source.frame_table_name);
+
+    sqlx::query_scalar::<_, Option<i64>>(&statement)
+        .fetch_one(&mut *conn)
+        .await
+}
+
+async fn fetch_raw_values(
+    conn: &mut SqliteConnection,
+    source: &RollupSource,
+    start: i64,
+    end: i64,
+) -> Result<Vec<(i64, String, f64)>, sqlx::Error> {
+    let statement = format!(
+        "SELECT f.last_check, {}, CAST(s.{} AS REAL)
+        FROM {} s
+        JOIN {} f ON s.frame_id = f.id
+        WHERE f.last_check >= ? AND f.last_check < ?",
+        source.get_entity_expression(),
+        source.value_column,
+        source.single_table_name,
+        source.frame_table_name
+    );
+
+    sqlx::query_as::<_, (i64, String, f64)>(&statement)
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+async fn insert_rollups(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+    rollups: &[StatusRollup],
+) -> Result<(), sqlx::Error> {
+    for chunk in rollups.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT OR REPLACE INTO {} (bucket_start, entity_id, min_value, max_value, avg_value, p95_value, samples) ",
+            table_name
+        ));
+        query.push_values(chunk, |mut row, rollup| {
+            row.push_bind(rollup.bucket_start)
+                .push_bind(&rollup.entity_id)
+                .push_bind(rollup.min_value)
+                .push_bind(rollup.max_value)
+                .push_bind(rollup.avg_value)
+                .push_bind(rollup.p95_value)
+                .push_bind(rollup.samples);
+        });
+        query.build().execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+// rolls up the buckets that ended before `until` and aren't rolled up yet,
+// returns the number of rollups written
+pub async fn update_status_rollups(
+    source: &RollupSource,
+    resolution: Resolution,
+    until: i64,
+) -> Result<usize, sqlx::Error> {
+    let Some(bucket_millis) = resolution.get_bucket_millis() else {
+        return Ok(0);
+    };
+
+    let table_name = source.get_table_name(resolution);
+    let conn = get_default_sql_connection().await?;
+
+    let rolled_up_to = {
+        let mut conn = conn.acquire().await?;
+
+        match fetch_rolled_up_to(&mut conn, &table_name).await? {
+            Some(rolled_up_to) => rolled_up_to,
+            None => match fetch_first_check(&mut conn, source).await? {
+                Some(first_check) => first_check - first_check.rem_euclid(bucket_millis),
+                None => return Ok(0),
+            },
+        }
+    };
+
+    let until = until - until.rem_euclid(bucket_millis);
+    let mut start = rolled_up_to;
+    let mut rows = 0;
+
+    while start < until {
+        let end = (start + MAX_ROLLUP_SPAN_MILLIS).min(until);
+
+        let mut tx = conn.begin().await?;
+
+        let values = fetch_raw_values(&mut tx, source, start, end).await?;
+        let rollups = compute_rollups(&values, bucket_millis);
+        insert_rollups(&mut tx, &table_name, &rollups).await?;
+        save_rolled_up_to(&mut tx, &table_name, end).await?;
+
+        tx.commit().await?;
+
+        rows += rollups.len();
+        start = end;
+    }
+
+    Ok(rows)
+}
+
+// the samples of the range as (bucket_start, entity_id, sampled_at, weight,
+// min_value, max_value, avg_value, p95_value), the raw frames are a single
+// sample each, the rollups are weighted by their sample count
+fn get_samples_statement(
+    source: &RollupSource,
+    resolution: Resolution,
+    has_entity_filter: bool,
+) -> String {
+    match resolution {
+        Resolution::Raw => format!(
+            "SELECT (f.last_check / ?) * ? AS bucket_start, {entity} AS entity_id,
+                f.last_check AS sampled_at, 1 AS weight,
+                CAST(s.{value} AS REAL) AS min_value, CAST(s.{value} AS REAL) AS max_value,
+                CAST(s.{value} AS REAL) AS avg_value, CAST(s.{value} AS REAL) AS p95_value
+            FROM {single} s
+            JOIN {frame} f ON s.frame_id = f.id
+            WHERE f.last_check BETWEEN ? AND ? {filter}",
+            entity = source.get_entity_expression(),
+            value = source.value_column,
+            single = source.single_table_name,
+            frame = source.frame_table_name,
+            filter = if has_entity_filter {
+                format!("AND s.{} = ?", source.entity_column)
+            } else {
+                String::new()
+            },
+        ),
+        resolution => format!(
+            "SELECT (r.bucket_start / ?) * ? AS bucket_start, r.entity_id AS entity_id,
+                r.bucket_start AS sampled_at, r.samples AS weight,
+                r.min_value, r.max_value, r.avg_value, r.p95_value
+            FROM {} r
+            WHERE r.bucket_start BETWEEN ? AND ? {}",
+            source.get_table_name(resolution),
+            match (has_entity_filter, source.has_positions) {
+                (false, _) => "",
+                (true, false) => "AND r.entity_id = ?",
+                // without the `:0000` of the position
+                (true, true) => "AND substr(r.entity_id, 1, length(r.entity_id) - 5) = ?",
+            },
+        ),
+    }
+}
+
+// the p95 of rollups is the p95 of their p95s, an estimate unlike the one of the raw frames
+fn get_aggregate_statement(aggregate: Aggregate) -> &'static str {
+    match aggregate {
+        Aggregate::Avg => {
+            "SELECT bucket_start, entity_id, SUM(avg_value * weight) / SUM(weight) AS value
+            FROM samples GROUP BY bucket_start, entity_id"
+        }
+        Aggregate::Min => {
+            "SELECT bucket_start, entity_id, MIN(min_value) AS value
+            FROM samples GROUP BY bucket_start, entity_id"
+        }
+        Aggregate::Max => {
+            "SELECT bucket_start, entity_id, MAX(max_value) AS value
+            FROM samples GROUP BY bucket_start, entity_id"
+        }
+        Aggregate::Last => {
+            "SELECT bucket_start, entity_id, avg_value AS value FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY bucket_start, entity_id ORDER BY sampled_at DESC
+                ) AS position
+                FROM samples
+            ) WHERE position = 1"
+        }
+        Aggregate::P95 => {
+            "SELECT bucket_start, entity_id, p95_value AS value FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY bucket_start, entity_id ORDER BY p95_value
+                ) AS position,
+                COUNT(*) OVER (PARTITION BY bucket_start, entity_id) AS sample_count
+                FROM samples
+            ) WHERE position = MAX(1, (sample_count * 95 + 99) / 100)"
+        }
+    }
+}
+
+// the samples of the range in buckets of `step_millis`, aligned to the epoch
+// like the rollups, ordered by the bucket, then the id
+pub async fn get_status_buckets_between_dates(
+    source: &RollupSource,
+    resolution: Resolution,
+    query: &StatusQuery,
+    step_millis: i64,
+    aggregate: Aggregate,
+) -> Result<Vec<StatusBucket>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    fetch_status_buckets(&conn, source, resolution, query, step_millis, aggregate).await
+}
+
+async fn fetch_status_buckets(
+    conn: &SQLConnection,
+    source: &RollupSource,
+    resolution: Resolution,
+    query: &StatusQuery,
+    step_millis: i64,
+    aggregate: Aggregate,
+) -> Result<Vec<StatusBucket>, sqlx::Error> {
+    let statement = format!(
+        "WITH samples AS ({}) {} ORDER BY bucket_start, entity_id",
+        get_samples_statement(source, resolution, query.entity_id.is_some()),
+        get_aggregate_statement(aggregate)
+    );
+
+    let mut buckets_query = sqlx::query_as::<_, StatusBucket>(&statement)
+        .bind(&step_millis)
+        .bind(&step_millis)
+        .bind(&query.start_time)
+        .bind(&query.end_time);
+
+    if let Some(entity_id) = &query.entity_id {
+        buckets_query = buckets_query.bind(entity_id);
+    }
+
+    buckets_query.fetch_all(conn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::persistence::migrations::run_migrations;
+
+    #[test]
+    fn compute_rollups_test() {
+        let minute = 60 * 1000;
+        let mut values: Vec<(i64, String, f64)> = (1..=20)
+            .map(|i| (i * 1000, "cpu0".to_string(), i as f64))
+            .collect();
+        values.push((minute + 1000, "cpu0".to_string(), 50.0));
+        values.push((2000, "cpu1".to_string(), 7.0));
+
+        let rollups = compute_rollups(&values, minute);
+
+        assert_eq!(
+            rollups,
+            vec![
+                StatusRollup {
+                    bucket_start: 0,
+                    entity_id: "cpu0".to_string(),
+                    min_value: 1.0,
+                    max_value: 20.0,
+                    avg_value: 10.5,
+                    p95_value: 19.0,
+                    samples: 20,
+                },
+                StatusRollup {
+                    bucket_start: 0,
+                    entity_id: "cpu1".to_string(),
+                    min_value: 7.0,
+                    max_value: 7.0,
+                    avg_value: 7.0,
+                    p95_value: 7.0,
+                    samples: 1,
+                },
+                StatusRollup {
+                    bucket_start: minute,
+                    entity_id: "cpu0".to_string(),
+                    min_value: 50.0,
+                    max_value: 50.0,
+                    avg_value: 50.0,
+                    p95_value: 50.0,
+                    samples: 1,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cpu_rollups_per_core_test() {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        run_migrations(&conn).await.unwrap();
+
+        // two frames of a cpu with two cores, the first one idle and the second one busy
+        sqlx::query(
+            "INSERT INTO cpu_status_frame (id, last_check) VALUES (1, 1000), (2, 2000);
+            INSERT INTO cpu_status_frame_core (frame_id, cpu_id, freq, usage)
+            VALUES (1, 'cpu', 2000, 10), (1, 'cpu', 2000, 90), (2, 'cpu', 2000, 20), (2, 'cpu', 2000, 80);",
+        )
+        .execute(&conn)
+        .await
+        .unwrap();
+
+        let mut tx = conn.begin().await.unwrap();
+        let values = fetch_raw_values(&mut tx, &CPU_ROLLUP_SOURCE, 0, 60_000)
+            .await
+            .unwrap();
+        let rollups = compute_rollups(&values, 60_000);
+        insert_rollups(&mut tx, "cpu_status_rollup_1m", &rollups)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let query = StatusQuery {
+            start_time: 0,
+            end_time: 60_000,
+            entity_id: Some("cpu".to_string()),
+            ..Default::default()
+        };
+
+        // the raw frames and the rollups are bucketed the same way
+        for resolution in [Resolution::Raw, Resolution::Minute] {
+            let buckets = fetch_status_buckets(
+                &conn,
+                &CPU_ROLLUP_SOURCE,
+                resolution,
+                &query,
+                60_000,
+                Aggregate::Avg,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                buckets
+                    .iter()
+                    .map(|b| (b.entity_id.as_str(), b.value))
+                    .collect::<Vec<(&str, f64)>>(),
+                vec![("cpu:0000", 15.0), ("cpu:0001", 85.0)],
+                "{:?}",
+                resolution
+            );
+            assert_eq!(
+                CPU_ROLLUP_SOURCE.get_entity_id(&buckets[1].entity_id),
+                "cpu"
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::monitor::models::get_temperature_status::{
+    SingleTemperatureInfo, TemperatureFrameStatus,
+};
+
+use super::get_default_sql_connection;
+use super::status_frames::{
+    get_frame_rows_between_dates, get_frames_between_dates, insert_frame_row, MAX_ROWS_PER_INSERT,
+};
+
+const TEMPERATURE_STATUS_FRAME_TABLE_NAME: &str = "temperature_status_frame";
+const TEMPERATURE_STATUS_FRAME_SINGLE_TABLE_NAME: &str = "temperature_status_frame_single";
+
+pub async fn insert_temperature_status_frame(
+    conn: &mut SqliteConnection,
+    status: &TemperatureFrameStatus,
+) -> Result<(), sqlx::Error> {
+    let frame_id =
+        insert_frame_row(conn, TEMPERATURE_STATUS_FRAME_TABLE_NAME, status.last_check).await?;
+
+    for chunk in status.components_temperature.chunks(MAX_ROWS_PER_INSERT) {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} (frame_id, component_id, temperature, max, critical) ",
+            TEMPERATURE_STATUS_FRAME_SINGLE_TABLE_NAME
+        ));
+        query.push_values(chunk, |mut row, single| {
+            row.push_bind(frame_id)
+                .push_bind(&single.component_id)
+                .push_bind(&single.temperature)
+                .push_bind(&single.max)
+                .push_bind(&single.critical);
+        });
+        query.build().execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_temperature_status_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<TemperatureFrameStatus>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let frames = get_frames_between_dates(
+        &conn,
+        TEMPERATURE_STATUS_FRAME_TABLE_NAME,
+        start_date,
+        end_date,
+    )
+    .await?;
+    let mut singles = get_frame_rows_between_dates(
+        &conn,
+        TEMPERATURE_STATUS_FRAME_TABLE_NAME,
+        TEMPERATURE_STATUS_FRAME_SINGLE_TABLE_NAME,
+        start_date,
+        end_date,
+        |row: &SingleTemperatureInfo| row.frame_id,
+    )
+    .await?;
+
+    let frames: Vec<TemperatureFrameStatus> = frames
+        .into_iter()
+        .map(|(id, last_check)| TemperatureFrameStatus {
+            id,
+            last_check,
+            components_temperature: singles.remove(&id).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(frames)
+}
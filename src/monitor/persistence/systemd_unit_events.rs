@@ -0,0 +1,68 @@
+use crate::monitor::models::get_systemd_units::SystemdUnitEvent;
+
+use super::get_default_sql_connection;
+
+const SYSTEMD_UNIT_EVENTS_TABLE_NAME: &str = "systemd_unit_events";
+
+pub async fn insert_systemd_unit_event(event: &SystemdUnitEvent) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "INSERT INTO {}
+        (unit, active_state, sub_state, n_restarts, last_check)
+        VALUES (?, ?, ?, ?, ?)",
+        SYSTEMD_UNIT_EVENTS_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&event.unit)
+        .bind(&event.active_state)
+        .bind(&event.sub_state)
+        .bind(&event.n_restarts)
+        .bind(&event.last_check)
+        .execute(&conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_latest_systemd_unit_event(
+    unit: &str,
+) -> Result<Option<SystemdUnitEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "
+        SELECT *
+        FROM {}
+        WHERE unit = ?
+        ORDER BY last_check DESC
+        ",
+        SYSTEMD_UNIT_EVENTS_TABLE_NAME
+    );
+    let event = sqlx::query_as::<_, SystemdUnitEvent>(&statement)
+        .bind(&unit)
+        .fetch_optional(&conn)
+        .await?;
+
+    Ok(event)
+}
+
+pub async fn get_systemd_unit_events_between_dates(
+    start_date: i64,
+    end_date: i64,
+) -> Result<Vec<SystemdUnitEvent>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} WHERE last_check BETWEEN ? AND ? ORDER BY last_check",
+        SYSTEMD_UNIT_EVENTS_TABLE_NAME
+    );
+    let events = sqlx::query_as::<_, SystemdUnitEvent>(&statement)
+        .bind(&start_date)
+        .bind(&end_date)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(events)
+}
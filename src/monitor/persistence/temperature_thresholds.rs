@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::monitor::models::get_temperature_status::TemperatureThreshold;
+
+use super::get_default_sql_connection;
+
+const TEMPERATURE_THRESHOLDS_TABLE_NAME: &str = "temperature_thresholds";
+
+// replaces all of the device's thresholds with the given ones
+pub async fn replace_temperature_thresholds(
+    device_id: &str,
+    thresholds: &HashMap<String, f64>,
+) -> Result<(), sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let mut tx = conn.begin().await?;
+
+    let delete_statement = format!(
+        "DELETE FROM {} WHERE device_id = ?",
+        TEMPERATURE_THRESHOLDS_TABLE_NAME
+    );
+    sqlx::query(&delete_statement)
+        .bind(&device_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let insert_statement = format!(
+        "INSERT INTO {} (device_id, component_id, threshold) VALUES (?, ?, ?)",
+        TEMPERATURE_THRESHOLDS_TABLE_NAME
+    );
+    for (component_id, threshold) in thresholds {
+        sqlx::query(&insert_statement)
+            .bind(&device_id)
+            .bind(&component_id)
+            .bind(&threshold)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn fetch_temperature_thresholds(
+    device_id: &str,
+) -> Result<Vec<TemperatureThreshold>, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+
+    let statement = format!(
+        "SELECT * FROM {} WHERE device_id = ?",
+        TEMPERATURE_THRESHOLDS_TABLE_NAME
+    );
+    let thresholds = sqlx::query_as::<_, TemperatureThreshold>(&statement)
+        .bind(&device_id)
+        .fetch_all(&conn)
+        .await?;
+
+    Ok(thresholds)
+}
@@ -0,0 +1,112 @@
+use super::models::get_pressure_status::SinglePressureInfo;
+
+const PRESSURE_DIR: &str = "/proc/pressure";
+
+pub const PRESSURE_RESOURCES: [&str; 3] = ["cpu", "memory", "io"];
+
+// parses the content of a /proc/pressure file, that looks like:
+// some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+pub fn parse_pressure(resource: &str, content: &str) -> Vec<SinglePressureInfo> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+
+            let kind = fields.next()?;
+            if kind != "some" && kind != "full" {
+                return None;
+            }
+
+            let mut avg10 = None;
+            let mut avg60 = None;
+            let mut avg300 = None;
+            let mut total = None;
+
+            for field in fields {
+                match field.split_once('=')? {
+                    ("avg10", val) => avg10 = val.parse::<f64>().ok(),
+                    ("avg60", val) => avg60 = val.parse::<f64>().ok(),
+                    ("avg300", val) => avg300 = val.parse::<f64>().ok(),
+                    ("total", val) => total = val.parse::<i64>().ok(),
+                    _ => {}
+                }
+            }
+
+            Some(SinglePressureInfo {
+                id: -1,
+                frame_id: -1,
+                resource: resource.to_string(),
+                kind: kind.to_string(),
+                avg10: avg10?,
+                avg60: avg60?,
+                avg300: avg300?,
+                total: total?,
+            })
+        })
+        .collect()
+}
+
+// returns None on kernels without PSI, that are older than 4.20 or booted with psi=0,
+// and on systems without procfs, like windows and macos
+pub fn read_pressure() -> Option<Vec<SinglePressureInfo>> {
+    let pressures: Vec<SinglePressureInfo> = PRESSURE_RESOURCES
+        .iter()
+        .filter_map(|resource| {
+            let content = std::fs::read_to_string(format!("{}/{}", PRESSURE_DIR, resource)).ok()?;
+
+            Some(parse_pressure(resource, &content))
+        })
+        .flatten()
+        .collect();
+
+    if pressures.is_empty() {
+        return None;
+    }
+
+    Some(pressures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pressure_test() {
+        let content = "some avg10=1.52 avg60=0.87 avg300=0.21 total=38290148\n\
+                       full avg10=0.00 avg60=0.10 avg300=0.02 total=1223344\n";
+
+        let pressures = parse_pressure("memory", content);
+
+        assert_eq!(
+            pressures,
+            vec![
+                SinglePressureInfo {
+                    id: -1,
+                    frame_id: -1,
+                    resource: "memory".to_string(),
+                    kind: "some".to_string(),
+                    avg10: 1.52,
+                    avg60: 0.87,
+                    avg300: 0.21,
+                    total: 38290148,
+                },
+                SinglePressureInfo {
+                    id: -1,
+                    frame_id: -1,
+                    resource: "memory".to_string(),
+                    kind: "full".to_string(),
+                    avg10: 0.0,
+                    avg60: 0.1,
+                    avg300: 0.02,
+                    total: 1223344,
+                },
+            ]
+        );
+
+        // unknown and malformed lines are skipped
+        assert!(parse_pressure("cpu", "some avg10=x avg60=0.00 avg300=0.00 total=0\n").is_empty());
+        assert!(parse_pressure("cpu", "other avg10=0.00\n").is_empty());
+        assert!(parse_pressure("cpu", "").is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use log::{error, info, warn};
+
+use super::models::get_process_watch::{
+    ProcessWatchEvent, ProcessWatchStatus, RunningProcess, WatchedProcess,
+};
+use super::notifier::{send_to_all_devices, Notifier};
+use super::persistence::{fetch_latest_process_watch_event, insert_process_watch_event};
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+// watched process name -> whether it was up on the last check
+pub type ProcessStateMap = HashMap<String, bool>;
+
+#[derive(Debug, PartialEq)]
+enum ProcessTransition {
+    // nothing changed since the last check
+    None,
+    // seen for the first time, and it's running as expected
+    Initial,
+    WentDown,
+    CameBack,
+}
+
+fn get_transition(previous: Option<bool>, is_up: bool) -> ProcessTransition {
+    match (previous, is_up) {
+        (Some(prev), is_up) if prev == is_up => ProcessTransition::None,
+        (None, true) => ProcessTransition::Initial,
+        (_, false) => ProcessTransition::WentDown,
+        (Some(_), true) => ProcessTransition::CameBack,
+    }
+}
+
+// the name has to match exactly, so "postgres" isn't kept up by a running
+// `postgres_exporter`. the command line is matched by a substring
+fn process_matches(watched: &WatchedProcess, process: &RunningProcess) -> bool {
+    if watched.match_cmdline {
+        process.cmdline.contains(&watched.pattern)
+    } else {
+        process.name == watched.pattern
+    }
+}
+
+pub fn evaluate_watchlist(
+    watchlist: &[WatchedProcess],
+    processes: &[RunningProcess],
+) -> Vec<ProcessWatchStatus> {
+    watchlist
+        .iter()
+        .map(|watched| {
+            let instance_count = processes
+                .iter()
+                .filter(|p| process_matches(watched, p))
+                .count() as i64;
+
+            ProcessWatchStatus {
+                name: watched.name.to_string(),
+                pattern: watched.pattern.to_string(),
+                instance_count,
+                min_instances: watched.min_instances,
+                is_up: instance_count >= watched.min_instances,
+            }
+        })
+        .collect()
+}
+
+pub(super) async fn check_process_watchlist(
+    watchlist: &[WatchedProcess],
+    processes: &[RunningProcess],
+    states: &mut ProcessStateMap,
+    notifier: &impl Notifier,
+    last_check: i64,
+) {
+    let statuses = evaluate_watchlist(watchlist, processes);
+
+    for status in statuses {
+        // after a restart, continue from the last recorded state
+        // so we don't report the same transition twice
+        if !states.contains_key(&status.name) {
+            match fetch_latest_process_watch_event(&status.name).await {
+                Ok(Some(event)) => {
+                    states.insert(status.name.to_string(), event.is_up);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("failed to fetch latest process watch event: {}", e);
+                }
+            }
+        }
+
+        let transition = get_transition(states.get(&status.name).copied(), status.is_up);
+
+        if transition == ProcessTransition::None {
+            continue;
+        }
+
+        states.insert(status.name.to_string(), status.is_up);
+
+        let event = ProcessWatchEvent {
+            id: -1,
+            name: status.name.to_string(),
+            pattern: status.pattern.to_string(),
+            instance_count: status.instance_count,
+            min_instances: status.min_instances,
+            is_up: status.is_up,
+            last_check,
+        };
+        if let Err(e) = insert_process_watch_event(&event).await {
+            error!("failed to insert process watch event: {}", e);
+        }
+
+        if transition == ProcessTransition::Initial {
+            continue;
+        }
+
+        send_process_transition_notification(&status, notifier).await;
+    }
+}
+
+async fn send_process_transition_notification(
+    status: &ProcessWatchStatus,
+    notifier: &impl Notifier,
+) -> bool {
+    let (message, notification_type) = if status.is_up {
+        info!("watched process {} is back up", status.name);
+
+        (
+            NotificationMessage {
+                title: format!("{} is back up", status.name),
+                body: format!(
+                    "the watched process {} has {} running instances again",
+                    status.name, status.instance_count
+                ),
+            },
+            NotificationType::ProcessUp,
+        )
+    } else {
+        warn!("watched process {} is down", status.name);
+
+        (
+            NotificationMessage {
+                title: format!("IMPORTANT: {} is down", status.name),
+                body: format!(
+                    "the watched process {} has {} running instances, expected at least {}",
+                    status.name, status.instance_count, status.min_instances
+                ),
+            },
+            NotificationType::ProcessDown,
+        )
+    };
+
+    let not_res = send_to_all_devices(notifier, &message, &notification_type).await;
+
+    if let Err(not_res) = not_res {
+        error!(
+            "Sending process watch notification resulted with the following error: {}",
+            not_res
+        );
+
+        return false;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::monitor::clock::{FakeClock, SharedClock};
+    use crate::monitor::notifier::RecordingNotifier;
+
+    fn running(name: &str, cmdline: &str) -> RunningProcess {
+        RunningProcess {
+            name: name.to_string(),
+            cmdline: cmdline.to_string(),
+        }
+    }
+
+    #[test]
+    fn evaluate_watchlist_test() {
+        let watchlist = vec![
+            WatchedProcess {
+                name: "postgres".to_string(),
+                pattern: "postgres".to_string(),
+                match_cmdline: false,
+                min_instances: 2,
+            },
+            WatchedProcess {
+                name: "nginx".to_string(),
+                pattern: "nginx".to_string(),
+                match_cmdline: false,
+                min_instances: 1,
+            },
+            WatchedProcess {
+                name: "api".to_string(),
+                pattern: "/srv/api/server.py".to_string(),
+                match_cmdline: true,
+                min_instances: 1,
+            },
+        ];
+
+        let processes = vec![
+            running(
+                "postgres",
+                "/usr/lib/postgresql/16/bin/postgres -D /var/lib/pg",
+            ),
+            running("postgres", "postgres: checkpointer"),
+            // not postgres, even though their names contain it
+            running("postgres_exporter", "/usr/bin/postgres_exporter"),
+            running("pg_postgres_backup", "pg_postgres_backup --daily"),
+            running("python3", "python3 /srv/api/server.py --port 80"),
+            running("bash", "bash"),
+        ];
+
+        assert_eq!(
+            evaluate_watchlist(&watchlist, &processes),
+            vec![
+                ProcessWatchStatus {
+                    name: "postgres".to_string(),
+                    pattern: "postgres".to_string(),
+                    instance_count: 2,
+                    min_instances: 2,
+                    is_up: true,
+                },
+                ProcessWatchStatus {
+                    name: "nginx".to_string(),
+                    pattern: "nginx".to_string(),
+                    instance_count: 0,
+                    min_instances: 1,
+                    is_up: false,
+                },
+                ProcessWatchStatus {
+                    name: "api".to_string(),
+                    pattern: "/srv/api/server.py".to_string(),
+                    instance_count: 1,
+                    min_instances: 1,
+                    is_up: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn get_transition_test() {
+        assert_eq!(get_transition(None, true), ProcessTransition::Initial);
+        assert_eq!(get_transition(None, false), ProcessTransition::WentDown);
+        assert_eq!(get_transition(Some(true), true), ProcessTransition::None);
+        assert_eq!(get_transition(Some(false), false), ProcessTransition::None);
+        assert_eq!(
+            get_transition(Some(true), false),
+            ProcessTransition::WentDown
+        );
+        assert_eq!(
+            get_transition(Some(false), true),
+            ProcessTransition::CameBack
+        );
+    }
+
+    #[tokio::test]
+    async fn send_process_transition_notification_test() {
+        let clock: SharedClock = Arc::new(FakeClock::new(1000));
+        let notifier = RecordingNotifier::new(clock)
+            .with_device("phone", "phone token")
+            .with_device("tablet", "tablet token");
+
+        let status = ProcessWatchStatus {
+            name: "postgres".to_string(),
+            pattern: "postgres".to_string(),
+            instance_count: 0,
+            min_instances: 1,
+            is_up: false,
+        };
+        assert!(send_process_transition_notification(&status, &notifier).await);
+
+        // every device is told, as it's not tied to a device's thresholds
+        let sent = notifier.get_sent();
+        assert_eq!(
+            sent.iter()
+                .map(|n| n.device_id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["phone", "tablet"]
+        );
+        assert!(sent
+            .iter()
+            .all(|n| n.notification_type == NotificationType::ProcessDown
+                && n.title == "IMPORTANT: postgres is down"));
+    }
+}
@@ -0,0 +1,246 @@
+use log::{error, info, warn};
+use sysinfo::System;
+
+use super::clock::SharedClock;
+use super::models::get_boot_events::BootEvent;
+use super::notifier::{send_to_all_devices, Notifier};
+use super::persistence::{fetch_latest_boot_event, fetch_latest_status_check, insert_boot_event};
+use crate::config::RebootDetectionConfig;
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+// the boot time is computed from the uptime, so it can be off by a second
+// between two reads, it's only compared where there's no boot_id
+const BOOT_TIME_TOLERANCE_MILLIS: i64 = 5000;
+
+// changes on every boot, only on linux
+fn read_boot_id() -> Option<String> {
+    let boot_id = std::fs::read_to_string(BOOT_ID_PATH).ok()?;
+    let boot_id = boot_id.trim();
+
+    if boot_id.is_empty() {
+        None
+    } else {
+        Some(boot_id.to_string())
+    }
+}
+
+fn is_same_boot(previous: &BootEvent, boot_id: Option<&str>, boot_time: i64) -> bool {
+    match (previous.boot_id.as_deref(), boot_id) {
+        (Some(previous_id), Some(boot_id)) => previous_id == boot_id,
+        _ => (previous.boot_time - boot_time).abs() <= BOOT_TIME_TOLERANCE_MILLIS,
+    }
+}
+
+// the event to record for the current boot, None if it was already recorded,
+// i.e. only remon was restarted
+fn detect_boot(
+    previous: Option<&BootEvent>,
+    boot_id: Option<String>,
+    boot_time: i64,
+    last_sample: Option<i64>,
+    unclean_gap_secs: i64,
+    last_check: i64,
+) -> Option<BootEvent> {
+    // a sample taken after the boot belongs to the current boot
+    let last_sample = last_sample.filter(|s| *s < boot_time);
+
+    let is_reboot = match previous {
+        Some(previous) if is_same_boot(previous, boot_id.as_deref(), boot_time) => return None,
+        Some(_) => true,
+        // the first boot seen since boot detection was added, it's still
+        // a reboot if there are samples from before it
+        None => last_sample.is_some(),
+    };
+
+    let downtime_secs = if is_reboot {
+        last_sample.map(|s| (boot_time - s) / 1000)
+    } else {
+        None
+    };
+
+    Some(BootEvent {
+        id: -1,
+        boot_id,
+        boot_time,
+        is_reboot,
+        last_sample: if is_reboot { last_sample } else { None },
+        downtime_secs,
+        is_unclean: downtime_secs.is_some_and(|d| d > unclean_gap_secs),
+        last_check,
+    })
+}
+
+fn format_duration(secs: i64) -> String {
+    if secs < 60 {
+        format!("{} seconds", secs)
+    } else if secs < 60 * 60 {
+        format!("{} minutes", secs / 60)
+    } else {
+        format!("{:.1} hours", secs as f64 / 60.0 / 60.0)
+    }
+}
+
+fn get_notification_message(event: &BootEvent) -> NotificationMessage {
+    let body = match event.downtime_secs {
+        Some(downtime_secs) => format!(
+            "there were no samples for {} before the boot",
+            format_duration(downtime_secs)
+        ),
+        None => "the server booted again".to_string(),
+    };
+
+    let title = if event.is_unclean {
+        "IMPORTANT: the server rebooted after an unclean shutdown"
+    } else {
+        "IMPORTANT: the server rebooted"
+    };
+
+    NotificationMessage {
+        title: title.to_string(),
+        body,
+    }
+}
+
+// compares the current boot with the last recorded one, runs once on startup
+// before any new sample is taken
+pub(super) async fn check_reboot(
+    config: &RebootDetectionConfig,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let previous = match fetch_latest_boot_event().await {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("failed to fetch the latest boot event: {}", e);
+            return;
+        }
+    };
+
+    // of any collector, the cpu one can be disabled
+    let last_sample = match fetch_latest_status_check().await {
+        Ok(last_sample) => last_sample,
+        Err(e) => {
+            error!("failed to fetch the latest status check: {}", e);
+            return;
+        }
+    };
+
+    let event = match detect_boot(
+        previous.as_ref(),
+        read_boot_id(),
+        System::boot_time() as i64 * 1000,
+        last_sample,
+        config.unclean_gap_secs,
+        clock.now_millis(),
+    ) {
+        Some(event) => event,
+        None => return,
+    };
+
+    if let Err(e) = insert_boot_event(&event).await {
+        error!("failed to insert boot event: {}", e);
+    }
+
+    if !event.is_reboot {
+        info!("recorded the first boot of the host");
+        return;
+    }
+
+    warn!("reboot detected: {:?}", event);
+
+    if !config.notify {
+        return;
+    }
+
+    let not_res = send_to_all_devices(
+        notifier,
+        &get_notification_message(&event),
+        &NotificationType::RebootDetected,
+    )
+    .await;
+
+    if let Err(not_res) = not_res {
+        error!(
+            "Sending reboot notification resulted with the following error: {}",
+            not_res
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOT_TIME: i64 = 1_700_000_000_000;
+
+    fn previous(boot_id: Option<&str>, boot_time: i64) -> BootEvent {
+        BootEvent {
+            id: 1,
+            boot_id: boot_id.map(|b| b.to_string()),
+            boot_time,
+            is_reboot: false,
+            last_sample: None,
+            downtime_secs: None,
+            is_unclean: false,
+            last_check: boot_time,
+        }
+    }
+
+    #[test]
+    fn detect_boot_test() {
+        let boot_id = || Some("b".to_string());
+
+        // only remon was restarted
+        let same = previous(Some("b"), BOOT_TIME - 1000);
+        assert!(detect_boot(Some(&same), boot_id(), BOOT_TIME, None, 300, 0).is_none());
+
+        // a clean reboot, the last sample was a minute before the boot
+        let other = previous(Some("a"), BOOT_TIME - 86_400_000);
+        let event = detect_boot(
+            Some(&other),
+            boot_id(),
+            BOOT_TIME,
+            Some(BOOT_TIME - 60_000),
+            300,
+            0,
+        )
+        .unwrap();
+        assert!(event.is_reboot);
+        assert_eq!(event.downtime_secs, Some(60));
+        assert!(!event.is_unclean);
+
+        // the last sample was an hour before the boot
+        let event = detect_boot(
+            Some(&other),
+            boot_id(),
+            BOOT_TIME,
+            Some(BOOT_TIME - 3_600_000),
+            300,
+            0,
+        )
+        .unwrap();
+        assert!(event.is_unclean);
+        assert_eq!(
+            get_notification_message(&event).body,
+            "there were no samples for 1.0 hours before the boot"
+        );
+
+        // without a boot_id, the boot times are compared
+        let same = previous(None, BOOT_TIME - 1000);
+        assert!(detect_boot(Some(&same), None, BOOT_TIME, None, 300, 0).is_none());
+
+        // the first boot, with and without samples from before it
+        let event =
+            detect_boot(None, boot_id(), BOOT_TIME, Some(BOOT_TIME + 1000), 300, 0).unwrap();
+        assert!(!event.is_reboot);
+        assert_eq!(event.downtime_secs, None);
+
+        let event =
+            detect_boot(None, boot_id(), BOOT_TIME, Some(BOOT_TIME - 1000), 300, 0).unwrap();
+        assert!(event.is_reboot);
+        assert_eq!(event.downtime_secs, Some(1));
+    }
+}
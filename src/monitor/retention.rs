@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use tokio_util::sync::CancellationToken;
+
+use super::scheduler::Schedule;
+use crate::config::RetentionConfig;
+use crate::persistence::retention::prune_db;
+
+pub(super) fn spawn_retention(config: &RetentionConfig, token: &CancellationToken) {
+    if !config.enabled {
+        return;
+    }
+
+    let config = config.clone();
+    let mut schedule = Schedule::new(
+        "retention",
+        Duration::from_secs(config.interval_secs),
+        token,
+    );
+
+    tokio::spawn(async move {
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            match prune_db(&config).await {
+                Ok(report) => info!(
+                    "pruned {} expired rows, reclaimed {} bytes",
+                    report.tables.iter().map(|t| t.deleted_rows).sum::<i64>(),
+                    report.reclaimed_bytes
+                ),
+                Err(e) => error!("failed to prune the expired rows: {}", e),
+            }
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, error};
+use tokio_util::sync::CancellationToken;
+
+use super::clock::{Clock, SharedClock, SystemClock};
+use super::models::{
+    get_cpu_status::{CpuCoreInfo, CpuFrameStatus},
+    get_disk_status::{DiskFrameStatus, SingleDiskInfo},
+    get_mem_status::{MemFrameStatus, SingleMemInfo},
+    get_status_rollups::{Aggregate, Resolution, StatusBucket, StatusHistory, StatusQuery},
+};
+use super::persistence::{
+    get_cpu_status_between_dates, get_disk_status_between_dates, get_mem_status_between_dates,
+    get_status_buckets_between_dates, update_status_rollups, RollupSource, CPU_ROLLUP_SOURCE,
+    DISK_ROLLUP_SOURCE, MEM_ROLLUP_SOURCE, ROLLUP_SOURCES,
+};
+use super::scheduler::Schedule;
+use crate::config::get_config;
+use crate::persistence::retention::{get_table_retention_days, MILLIS_PER_DAY};
+
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// a bucket is rolled up this long after it ended, so the frames that were
+// still on their way to the database are in it
+const ROLLUP_DELAY_MILLIS: i64 = 60 * 1000;
+
+// the points returned for a range when the client doesn't ask for a count
+pub const DEFAULT_MAX_POINTS: i64 = 1000;
+
+pub(super) fn spawn_rollups(token: &CancellationToken, clock: &SharedClock) {
+    let mut schedule = Schedule::new("rollups", ROLLUP_INTERVAL, token);
+    let clock = Arc::clone(clock);
+
+    tokio::spawn(async move {
+        while schedule.tick().await {
+            let started = Instant::now();
+            let until = clock.now_millis() - ROLLUP_DELAY_MILLIS;
+
+            for source in ROLLUP_SOURCES {
+                for resolution in Resolution::ROLLUPS {
+                    match update_status_rollups(source, resolution, until).await {
+                        Ok(0) => {}
+                        Ok(rows) => debug!(
+                            "rolled up {} {} buckets of {}",
+                            rows,
+                            resolution.get_name(),
+                            source.name
+                        ),
+                        Err(e) => error!(
+                            "failed to roll up the {} buckets of {}: {}",
+                            resolution.get_name(),
+                            source.name,
+                            e
+                        ),
+                    }
+                }
+            }
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
+// how a query is answered, the resolution and the length of the buckets in
+// milliseconds. the raw frames are returned as they are when the query asks
+// for nothing else and they fit in `max_points`, otherwise the samples are
+// bucketed from the coarsest resolution whose buckets fit evenly in the step,
+// or from the finest retained rollup once the raw frames were pruned.
+// `get_retention_days` is None where the rows are kept forever
+fn plan_query(
+    query: &StatusQuery,
+    now: i64,
+    raw_interval_millis: i64,
+    get_retention_days: impl Fn(Resolution) -> Option<i64>,
+) -> (Resolution, Option<i64>) {
+    let range = query.end_time.saturating_sub(query.start_time).max(0);
+    let max_points = query.max_points.unwrap_or(DEFAULT_MAX_POINTS).max(1);
+    let raw_interval_millis = raw_interval_millis.max(1);
+
+    // the rows from the start of the range were already pruned
+    let is_retained = |resolution| {
+        get_retention_days(resolution).is_none_or(|days| {
+            now.saturating_sub(query.start_time) <= days.saturating_mul(MILLIS_PER_DAY)
+        })
+    };
+    let round_up = |step: i64, bucket: i64| step.saturating_add(bucket - 1) / bucket * bucket;
+
+    let is_bucketed =
+        query.step.is_some() || query.aggregate.is_some() || query.entity_id.is_some();
+    if !is_bucketed && range / raw_interval_millis <= max_points && is_retained(Resolution::Raw) {
+        return (Resolution::Raw, None);
+    }
+
+    let step = match query.step {
+        Some(step) => step.saturating_mul(1000).max(1000),
+        None => {
+            let step = (range.saturating_add(max_points - 1) / max_points).max(raw_interval_millis);
+
+            // rounded up to whole buckets of the coarsest rollup that fits in it
+            match Resolution::ROLLUPS
+                .iter()
+                .rev()
+                .filter_map(|r| r.get_bucket_millis())
+                .find(|bucket| *bucket <= step)
+            {
+                Some(bucket) => round_up(step, bucket),
+                None => round_up(step, 1000),
+            }
+        }
+    };
+
+    let resolution = Resolution::ROLLUPS
+        .iter()
+        .rev()
+        .copied()
+        .find(|resolution| {
+            let bucket = resolution.get_bucket_millis().unwrap_or(1);
+
+            bucket <= step && step % bucket == 0 && is_retained(*resolution)
+        });
+    if let Some(resolution) = resolution {
+        return (resolution, Some(step));
+    }
+    if is_retained(Resolution::Raw) {
+        return (Resolution::Raw, Some(step));
+    }
+
+    // the raw frames are pruned before any rollup, so the step is rounded up
+    // to whole buckets of the finest rollup that still has the range
+    match Resolution::ROLLUPS
+        .iter()
+        .copied()
+        .find(|resolution| is_retained(*resolution))
+    {
+        Some(resolution) => {
+            let bucket = resolution.get_bucket_millis().unwrap_or(1);
+
+            (resolution, Some(round_up(step, bucket)))
+        }
+        None => (Resolution::Raw, Some(step)),
+    }
+}
+
+fn get_plan(source: &RollupSource, query: &StatusQuery, now: i64) -> (Resolution, Option<i64>) {
+    let config = get_config();
+    let raw_interval = config.sampling.get_collector_interval(source.name);
+
+    plan_query(query, now, raw_interval.as_millis() as i64, |resolution| {
+        let table_name = match resolution {
+            Resolution::Raw => source.frame_table_name.to_string(),
+            resolution => source.get_table_name(resolution),
+        };
+
+        get_table_retention_days(&config.retention, &table_name)
+    })
+}
+
+// the buckets are ordered by their start, so the ones of a bucket are next to each other
+fn get_frames<F>(buckets: &[StatusBucket], to_frame: impl Fn(i64, &[StatusBucket]) -> F) -> Vec<F> {
+    buckets
+        .chunk_by(|a, b| a.bucket_start == b.bucket_start)
+        .map(|bucket| to_frame(bucket[0].bucket_start, bucket))
+        .collect()
+}
+
+// the bucketed frames of a query, or None when the raw frames are returned
+async fn get_buckets(
+    source: &RollupSource,
+    query: &StatusQuery,
+) -> Result<(Resolution, Option<(i64, Vec<StatusBucket>)>), sqlx::Error> {
+    // the api has no clock of its own, the range is planned at the time of the request
+    let (resolution, step) = get_plan(source, query, SystemClock.now_millis());
+
+    let Some(step) = step else {
+        return Ok((resolution, None));
+    };
+
+    let aggregate = query.aggregate.unwrap_or(Aggregate::Avg);
+    let buckets =
+        get_status_buckets_between_dates(source, resolution, query, step, aggregate).await?;
+
+    Ok((resolution, Some((step, buckets))))
+}
+
+// the frames of the range, raw or bucketed. the bucketed frames only carry
+// the usage of every core, in the order of the cores, without the frequency
+pub async fn get_cpu_history(
+    query: &StatusQuery,
+) -> Result<StatusHistory<CpuFrameStatus>, sqlx::Error> {
+    let (resolution, buckets) = get_buckets(&CPU_ROLLUP_SOURCE, query).await?;
+
+    let Some((step, buckets)) = buckets else {
+        let frames = get_cpu_status_between_dates(query.start_time, query.end_time).await?;
+
+        return Ok(StatusHistory {
+            resolution,
+            step: None,
+            aggregate: None,
+            frames,
+        });
+    };
+
+    let frames = get_frames(&buckets, |bucket_start, bucket| CpuFrameStatus {
+        id: -1,
+        last_check: bucket_start,
+        cores_usage: bucket
+            .iter()
+            .map(|b| CpuCoreInfo {
+                id: -1,
+                frame_id: -1,
+                cpu_id: CPU_ROLLUP_SOURCE.get_entity_id(&b.entity_id).to_string(),
+                freq: 0,
+                usage: b.value.round() as i64,
+            })
+            .collect(),
+    });
+
+    Ok(StatusHistory {
+        resolution,
+        step: Some(step / 1000),
+        aggregate: Some(query.aggregate.unwrap_or(Aggregate::Avg)),
+        frames,
+    })
+}
+
+pub async fn get_mem_history(
+    query: &StatusQuery,
+) -> Result<StatusHistory<MemFrameStatus>, sqlx::Error> {
+    let (resolution, buckets) = get_buckets(&MEM_ROLLUP_SOURCE, query).await?;
+
+    let Some((step, buckets)) = buckets else {
+        let frames = get_mem_status_between_dates(query.start_time, query.end_time).await?;
+
+        return Ok(StatusHistory {
+            resolution,
+            step: None,
+            aggregate: None,
+            frames,
+        });
+    };
+
+    let frames = get_frames(&buckets, |bucket_start, bucket| MemFrameStatus {
+        id: -1,
+        last_check: bucket_start,
+        mems_usage: bucket
+            .iter()
+            .map(|b| SingleMemInfo {
+                id: -1,
+                frame_id: -1,
+                mem_id: b.entity_id.clone(),
+                available: b.value.round() as i64,
+            })
+            .collect(),
+    });
+
+    Ok(StatusHistory {
+        resolution,
+        step: Some(step / 1000),
+        aggregate: Some(query.aggregate.unwrap_or(Aggregate::Avg)),
+        frames,
+    })
+}
+
+// the bucketed frames only carry the available space, without the inodes and the io
+pub async fn get_disk_history(
+    query: &StatusQuery,
+) -> Result<StatusHistory<DiskFrameStatus>, sqlx::Error> {
+    let (resolution, buckets) = get_buckets(&DISK_ROLLUP_SOURCE, query).await?;
+
+    let Some((step, buckets)) = buckets else {
+        let frames = get_disk_status_between_dates(query.start_time, query.end_time).await?;
+
+        return Ok(StatusHistory {
+            resolution,
+            step: None,
+            aggregate: None,
+            frames,
+        });
+    };
+
+    let frames = get_frames(&buckets, |bucket_start, bucket| DiskFrameStatus {
+        id: -1,
+        last_check: bucket_start,
+        disks_usage: bucket
+            .iter()
+            .map(|b| SingleDiskInfo {
+                id: -1,
+                frame_id: -1,
+                disk_id: b.entity_id.clone(),
+                available: b.value.round() as i64,
+                inodes_total: None,
+                inodes_free: None,
+            })
+            .collect(),
+        disks_io: vec![],
+    });
+
+    Ok(StatusHistory {
+        resolution,
+        step: Some(step / 1000),
+        aggregate: Some(query.aggregate.unwrap_or(Aggregate::Avg)),
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+    const MINUTE: i64 = 60 * 1000;
+    const HOUR: i64 = 60 * MINUTE;
+
+    fn plan(hours: i64, query: StatusQuery) -> (Resolution, Option<i64>) {
+        let retention = |resolution| match resolution {
+            Resolution::Raw => Some(7),
+            Resolution::Minute => Some(30),
+            _ => None,
+        };
+        let query = StatusQuery {
+            start_time: NOW - hours * HOUR,
+            end_time: NOW,
+            ..query
+        };
+
+        plan_query(&query, NOW, 10_000, retention)
+    }
+
+    fn max_points(max_points: i64) -> StatusQuery {
+        StatusQuery {
+            max_points: Some(max_points),
+            ..Default::default()
+        }
+    }
+
+    fn step(step: i64) -> StatusQuery {
+        StatusQuery {
+            step: Some(step),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_query_test() {
+        // 360 frames of 10 seconds
+        assert_eq!(plan(1, max_points(1000)), (Resolution::Raw, None));
+        // 3600 / 100 is 36 seconds, which no rollup fits in
+        assert_eq!(plan(1, max_points(100)), (Resolution::Raw, Some(36_000)));
+        // a day is 1440 minutes, rounded up to 2
+        assert_eq!(
+            plan(24, max_points(1000)),
+            (Resolution::Minute, Some(2 * MINUTE))
+        );
+        assert_eq!(
+            plan(24, max_points(96)),
+            (Resolution::FifteenMinutes, Some(15 * MINUTE))
+        );
+        // a month is 43.2 minutes a point, rounded up to 3 buckets of 15 minutes
+        assert_eq!(
+            plan(24 * 30, max_points(1000)),
+            (Resolution::FifteenMinutes, Some(45 * MINUTE))
+        );
+        assert_eq!(
+            plan(24 * 30, max_points(300)),
+            (Resolution::Hour, Some(3 * HOUR))
+        );
+        // the raw frames and the 1m rollups from 60 days ago were already
+        // pruned, so the minutes are rounded up to the 15m rollups
+        assert_eq!(
+            plan(24 * 60, max_points(24 * 60 * 60)),
+            (Resolution::FifteenMinutes, Some(15 * MINUTE))
+        );
+        assert_eq!(
+            plan(24 * 60, step(90)),
+            (Resolution::FifteenMinutes, Some(15 * MINUTE))
+        );
+        assert_eq!(
+            plan(24 * 60, step(20 * 60)),
+            (Resolution::FifteenMinutes, Some(30 * MINUTE))
+        );
+
+        // an explicit step, aggregate or filter is always bucketed
+        assert_eq!(plan(1, step(30)), (Resolution::Raw, Some(30_000)));
+        assert_eq!(plan(1, step(300)), (Resolution::Minute, Some(5 * MINUTE)));
+        assert_eq!(plan(1, step(90)), (Resolution::Raw, Some(90_000)));
+        assert_eq!(plan(48, step(7200)), (Resolution::Hour, Some(2 * HOUR)));
+
+        let aggregate = StatusQuery {
+            aggregate: Some(Aggregate::P95),
+            ..Default::default()
+        };
+        assert_eq!(plan(1, aggregate), (Resolution::Raw, Some(10_000)));
+
+        // a step that large doesn't overflow
+        assert_eq!(plan(1, step(i64::MAX)), (Resolution::Raw, Some(i64::MAX)));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::Utc;
+use log::warn;
+use tokio::time::{self, Interval, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use super::models::get_collector_stats::CollectorStats;
+
+static COLLECTOR_STATS: OnceLock<Mutex<HashMap<String, CollectorStats>>> = OnceLock::new();
+
+fn get_stats_map() -> &'static Mutex<HashMap<String, CollectorStats>> {
+    COLLECTOR_STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn get_collector_stats() -> Vec<CollectorStats> {
+    let mut stats: Vec<CollectorStats> =
+        get_stats_map().lock().unwrap().values().cloned().collect();
+    stats.sort_by(|a, b| a.name.cmp(&b.name));
+
+    stats
+}
+
+fn record_run(stats: &mut CollectorStats, duration: Duration, interval: Duration, last_run: i64) {
+    let duration_ms = duration.as_secs_f64() * 1000.0;
+
+    stats.avg_duration_ms =
+        (stats.avg_duration_ms * stats.runs as f64 + duration_ms) / (stats.runs + 1) as f64;
+    stats.runs += 1;
+    stats.last_duration_ms = duration_ms;
+    stats.max_duration_ms = stats.max_duration_ms.max(duration_ms);
+    stats.last_run = last_run;
+
+    if duration > interval {
+        stats.overruns += 1;
+    }
+}
+
+// runs a collector on its own interval until the monitor is stopped,
+// and records how long every run took
+pub(super) struct Schedule {
+    name: String,
+    period: Duration,
+    interval: Interval,
+    token: CancellationToken,
+}
+
+impl Schedule {
+    pub fn new(name: &str, period: Duration, token: &CancellationToken) -> Self {
+        // a zero interval would make tokio panic
+        let period = period.max(Duration::from_secs(1));
+
+        let mut interval = time::interval(period);
+        // a run that took too long delays the next ones, instead of running them in a burst
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        get_stats_map().lock().unwrap().insert(
+            name.to_string(),
+            CollectorStats {
+                name: name.to_string(),
+                interval_ms: period.as_millis() as i64,
+                runs: 0,
+                overruns: 0,
+                last_duration_ms: 0.0,
+                max_duration_ms: 0.0,
+                avg_duration_ms: 0.0,
+                last_run: 0,
+            },
+        );
+
+        Self {
+            name: name.to_string(),
+            period,
+            interval,
+            token: token.clone(),
+        }
+    }
+
+    // waits for the next run, false once the monitor is stopped
+    pub async fn tick(&mut self) -> bool {
+        tokio::select! {
+            _ = self.token.cancelled() => false,
+            _ = self.interval.tick() => true,
+        }
+    }
+
+    pub fn record(&self, duration: Duration) {
+        if duration > self.period {
+            warn!(
+                "the {} collector took {:?}, longer than its interval of {:?}",
+                self.name, duration, self.period
+            );
+        }
+
+        if let Some(stats) = get_stats_map().lock().unwrap().get_mut(&self.name) {
+            record_run(stats, duration, self.period, Utc::now().timestamp_millis());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_run_test() {
+        let mut stats = CollectorStats {
+            name: "cpu".to_string(),
+            interval_ms: 1000,
+            runs: 0,
+            overruns: 0,
+            last_duration_ms: 0.0,
+            max_duration_ms: 0.0,
+            avg_duration_ms: 0.0,
+            last_run: 0,
+        };
+        let interval = Duration::from_secs(1);
+
+        record_run(&mut stats, Duration::from_millis(200), interval, 1);
+        record_run(&mut stats, Duration::from_millis(1500), interval, 2);
+        record_run(&mut stats, Duration::from_millis(100), interval, 3);
+
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.overruns, 1);
+        assert_eq!(stats.last_duration_ms, 100.0);
+        assert_eq!(stats.max_duration_ms, 1500.0);
+        assert_eq!(stats.avg_duration_ms, 600.0);
+        assert_eq!(stats.last_run, 3);
+    }
+
+    #[tokio::test]
+    async fn schedule_cancel_test() {
+        let token = CancellationToken::new();
+        let mut schedule = Schedule::new("schedule_cancel_test", Duration::from_secs(1), &token);
+
+        // the first tick completes right away
+        assert!(schedule.tick().await);
+        schedule.record(Duration::from_millis(10));
+
+        token.cancel();
+        assert!(!schedule.tick().await);
+
+        let stats = get_collector_stats();
+        let stats = stats
+            .iter()
+            .find(|s| s.name == "schedule_cancel_test")
+            .unwrap();
+        assert_eq!(stats.runs, 1);
+    }
+}
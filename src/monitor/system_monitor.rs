@@ -1,296 +1,383 @@
 use super::{
-    config_exceeds::check_thresholds,
+    backups::spawn_backups,
+    certificates::spawn_certificate_checks,
+    clock::SharedClock,
+    collectors::CollectorRegistry,
+    config_exceeds::{check_thresholds, ThresholdCheckData},
+    custom_probes::{spawn_custom_probes, LatestCustomMetrics},
+    endpoint_probes::spawn_endpoint_probes,
+    frame_writer::spawn_frame_writer,
+    hardware_changes::{get_hardware_diff, handle_hardware_changes},
+    log_watch::spawn_log_watches,
     models::{
-        get_cpu_status::{CpuCoreInfo, CpuFrameStatus, CpuStatusData},
-        get_disk_status::{DiskFrameStatus, DiskStatusData, SingleDiskInfo},
-        get_hardware_info::{HardwareCpuInfo, HardwareDiskInfo, HardwareInfo, HardwareMemInfo},
-        get_mem_status::{MemFrameStatus, MemStatusData, SingleMemInfo},
-    },
-    persistence::{
-        insert_cpu_status_frame, insert_disk_status_frame, insert_hardware_info,
-        insert_mem_status_frame,
+        get_cgroup_status::{CgroupFrameStatus, CgroupStatusData},
+        get_cpu_status::{CpuFrameStatus, CpuStatusData},
+        get_custom_metrics::CustomMetric,
+        get_disk_status::{DiskFrameStatus, DiskStatusData},
+        get_hardware_info::{
+            HardwareComponentInfo, HardwareCpuInfo, HardwareDiskInfo, HardwareInfo, HardwareMemInfo,
+        },
+        get_mem_status::{MemFrameStatus, MemStatusData},
+        get_pressure_status::{PressureFrameStatus, PressureStatusData},
+        get_temperature_status::{TemperatureFrameStatus, TemperatureStatusData},
     },
+    notifier::FcmNotifier,
+    persistence::fetch_latest_hardware_info,
+    process_watch::{check_process_watchlist, ProcessStateMap},
+    reboot_detection::check_reboot,
+    retention::spawn_retention,
+    rollups::spawn_rollups,
+    scheduler::Schedule,
+    system_source::{SysinfoSource, SystemSource},
+    systemd_units::{check_systemd_units, SystemCommandRunner, SystemdUnitStateMap},
 };
 
-use blake3::Hasher;
-use chrono::Utc;
+use crate::config::get_config;
 use log::{debug, error};
 use std::{
+    collections::HashMap,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
-    vec,
 };
-use sysinfo::{Cpu, CpuRefreshKind, Disk, Disks, MemoryRefreshKind, RefreshKind, System};
-use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+// the latest sample of every collector, read by the threshold checks and the
+// hardware inventory, which run on their own intervals
+#[derive(Debug, Default, Clone)]
+pub(super) struct LatestSamples {
+    pub(super) cpu_usage: Option<CpuFrameStatus>,
+    pub(super) cpu_info: Option<Vec<HardwareCpuInfo>>,
+    pub(super) mem_usage: Option<MemFrameStatus>,
+    pub(super) mem_info: Option<Vec<HardwareMemInfo>>,
+    pub(super) disk_usage: Option<DiskFrameStatus>,
+    pub(super) disks_info: Option<Vec<HardwareDiskInfo>>,
+    pub(super) temperature_usage: Option<TemperatureFrameStatus>,
+    pub(super) components_info: Option<Vec<HardwareComponentInfo>>,
+    pub(super) pressure_usage: Option<PressureFrameStatus>,
+    pub(super) cgroup_usage: Option<CgroupFrameStatus>,
+}
 
-// TODO(isaidsari): make it configurable
-pub fn get_check_interval() -> Duration {
-    Duration::from_millis(10000)
+pub(super) type SharedSamples = Arc<Mutex<LatestSamples>>;
+
+// the latest samples in the shape the thresholds are checked against
+pub(super) struct ThresholdStatus {
+    cpu_status: CpuStatusData,
+    mem_status: MemStatusData,
+    mems_info: Vec<HardwareMemInfo>,
+    disk_status: DiskStatusData,
+    disks_info: Vec<HardwareDiskInfo>,
+    temperature_status: TemperatureStatusData,
+    components_info: Vec<HardwareComponentInfo>,
+    pressure_status: PressureStatusData,
+    cgroup_status: CgroupStatusData,
 }
 
-pub struct SystemMonitor {
-    should_exit: Arc<Mutex<bool>>,
-    check_interval: Duration,
+impl From<LatestSamples> for ThresholdStatus {
+    fn from(latest: LatestSamples) -> Self {
+        Self {
+            cpu_status: CpuStatusData {
+                frames: latest.cpu_usage.into_iter().collect(),
+            },
+            mem_status: MemStatusData {
+                frames: latest.mem_usage.into_iter().collect(),
+            },
+            mems_info: latest.mem_info.unwrap_or_default(),
+            disk_status: DiskStatusData {
+                frames: latest.disk_usage.into_iter().collect(),
+            },
+            disks_info: latest.disks_info.unwrap_or_default(),
+            temperature_status: TemperatureStatusData {
+                frames: latest.temperature_usage.into_iter().collect(),
+            },
+            components_info: latest.components_info.unwrap_or_default(),
+            pressure_status: PressureStatusData {
+                frames: latest.pressure_usage.into_iter().collect(),
+            },
+            cgroup_status: CgroupStatusData {
+                frames: latest.cgroup_usage.into_iter().collect(),
+            },
+        }
+    }
 }
 
-trait CpuId {
-    fn get_cpu_id(&self) -> String;
+impl ThresholdStatus {
+    pub fn get_check_data<'a>(
+        &'a self,
+        custom_metrics: &'a [CustomMetric],
+    ) -> ThresholdCheckData<'a> {
+        ThresholdCheckData {
+            cpu_status: &self.cpu_status,
+            mem_status: &self.mem_status,
+            mems_info: &self.mems_info,
+            disk_status: &self.disk_status,
+            disks_info: &self.disks_info,
+            temperature_status: &self.temperature_status,
+            components_info: &self.components_info,
+            pressure_status: &self.pressure_status,
+            cgroup_status: &self.cgroup_status,
+            custom_metrics,
+        }
+    }
 }
 
-impl CpuId for Cpu {
-    fn get_cpu_id(&self) -> String {
-        let the_str: String = format!("{}{}", self.vendor_id(), self.brand());
-        let mut hasher = Hasher::new();
-        hasher.update(the_str.as_bytes());
+pub struct SystemMonitor {
+    // cancels every collector and probe
+    token: CancellationToken,
+    clock: SharedClock,
+}
 
-        let hash = hasher.finalize();
+// None when the collector is disabled in `sampling.disabled_collectors`
+fn get_schedule(name: &str, token: &CancellationToken) -> Option<Schedule> {
+    let sampling = &get_config().sampling;
+    if !sampling.is_collector_enabled(name) {
+        debug!("the {} collector is disabled", name);
+        return None;
+    }
 
-        let hashed = hash.to_string();
+    Some(Schedule::new(
+        name,
+        sampling.get_collector_interval(name),
+        token,
+    ))
+}
 
-        hashed
+// processes are expensive to refresh, so it only runs when there's something to watch
+fn spawn_process_watch(token: &CancellationToken, clock: &SharedClock) {
+    let watchlist = get_config().process_watchlist.clone();
+    if watchlist.is_empty() {
+        return;
     }
-}
 
-trait DiskId {
-    fn get_disk_id(&self) -> String;
+    let Some(mut schedule) = get_schedule("processes", token) else {
+        return;
+    };
+
+    let clock = Arc::clone(clock);
+
+    tokio::spawn(async move {
+        let mut source = SysinfoSource::new();
+        let mut process_states: ProcessStateMap = HashMap::new();
+
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            let processes = source.read_processes();
+            check_process_watchlist(
+                &watchlist,
+                &processes,
+                &mut process_states,
+                &FcmNotifier,
+                clock.now_millis(),
+            )
+            .await;
+
+            schedule.record(started.elapsed());
+        }
+    });
 }
 
-impl DiskId for Disk {
-    fn get_disk_id(&self) -> String {
-        let the_str: String = format!(
-            "{}{}{}{}{}{}",
-            self.name().to_string_lossy(),
-            self.file_system().to_str().unwrap_or_default(),
-            format!("{:?}", self.kind()),
-            if self.is_removable() { "yes" } else { "no" },
-            self.mount_point().to_string_lossy(),
-            self.total_space(),
-        );
+fn spawn_systemd_watch(token: &CancellationToken, clock: &SharedClock) {
+    let systemd_units = get_config().systemd_units.clone();
+    if systemd_units.is_empty() {
+        return;
+    }
 
-        debug!("the str: {}", the_str);
+    let Some(mut schedule) = get_schedule("systemd", token) else {
+        return;
+    };
 
-        let mut hasher = Hasher::new();
+    let clock = Arc::clone(clock);
 
-        hasher.update(the_str.as_bytes());
+    tokio::spawn(async move {
+        let mut systemd_unit_states: SystemdUnitStateMap = HashMap::new();
 
-        let hash = hasher.finalize();
+        while schedule.tick().await {
+            let started = Instant::now();
 
-        let hashed = hash.to_string();
+            check_systemd_units(
+                &SystemCommandRunner,
+                &systemd_units,
+                &mut systemd_unit_states,
+                &FcmNotifier,
+                &clock,
+            )
+            .await;
 
-        hashed
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
+// the stored part of the inventory is kept when its collector is disabled, as it's never sampled
+fn get_inventory_part<T: Clone>(
+    name: &str,
+    sample: &Option<Vec<T>>,
+    stored: &[T],
+) -> Option<Vec<T>> {
+    if get_config().sampling.is_collector_enabled(name) {
+        sample.clone()
+    } else {
+        Some(stored.to_vec())
     }
 }
 
+// the inventory of the latest samples, None until every enabled hardware collector has run once
+fn get_hardware_info(latest: &LatestSamples, stored: &HardwareInfo) -> Option<HardwareInfo> {
+    Some(HardwareInfo {
+        cpu_info: get_inventory_part("cpu", &latest.cpu_info, &stored.cpu_info)?,
+        disks_info: get_inventory_part("disk", &latest.disks_info, &stored.disks_info)?,
+        mem_info: get_inventory_part("mem", &latest.mem_info, &stored.mem_info)?,
+        components_info: get_inventory_part(
+            "temperature",
+            &latest.components_info,
+            &stored.components_info,
+        )?,
+    })
+}
+
+fn spawn_hardware_inventory(
+    token: &CancellationToken,
+    samples: &SharedSamples,
+    clock: &SharedClock,
+) {
+    let Some(mut schedule) = get_schedule("hardware", token) else {
+        return;
+    };
+    let samples = Arc::clone(samples);
+
+    let clock = Arc::clone(clock);
+
+    tokio::spawn(async move {
+        // the stored inventory, a new version is only written when the hardware changes
+        let mut known_hardware: Option<HardwareInfo> = None;
+
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            // on startup, the inventory is compared with the one stored before the restart
+            if known_hardware.is_none() {
+                match fetch_latest_hardware_info().await {
+                    Ok(stored) => known_hardware = Some(stored),
+                    Err(e) => error!("failed to fetch hardware info: {}", e),
+                }
+            }
+
+            if let Some(previous) = &known_hardware {
+                let hardware_info = get_hardware_info(&samples.lock().unwrap(), previous);
+
+                if let Some(hardware_info) = hardware_info {
+                    let diff = get_hardware_diff(previous, &hardware_info);
+                    if !diff.is_empty() {
+                        match handle_hardware_changes(
+                            previous,
+                            &diff,
+                            &FcmNotifier,
+                            clock.now_millis(),
+                        )
+                        .await
+                        {
+                            Ok(()) => known_hardware = Some(hardware_info),
+                            // retried on the next check, as the diff stays the same
+                            Err(e) => error!("failed to insert hardware info: {}", e),
+                        }
+                    }
+                }
+            }
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
+fn spawn_threshold_checks(
+    token: &CancellationToken,
+    samples: &SharedSamples,
+    latest_custom_metrics: &LatestCustomMetrics,
+    clock: &SharedClock,
+) {
+    let mut schedule = Schedule::new(
+        "thresholds",
+        Duration::from_secs(get_config().sampling.threshold_interval_secs),
+        token,
+    );
+    let samples = Arc::clone(samples);
+    let latest_custom_metrics = Arc::clone(latest_custom_metrics);
+    let clock = Arc::clone(clock);
+
+    tokio::spawn(async move {
+        while schedule.tick().await {
+            let started = Instant::now();
+
+            let status = ThresholdStatus::from(samples.lock().unwrap().clone());
+            let custom_metrics = latest_custom_metrics
+                .lock()
+                .unwrap()
+                .values()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+
+            check_thresholds(
+                &status.get_check_data(&custom_metrics),
+                &FcmNotifier,
+                &clock,
+            )
+            .await;
+
+            schedule.record(started.elapsed());
+        }
+    });
+}
+
 impl SystemMonitor {
-    pub fn new() -> Self {
-        let should_exit = Arc::new(Mutex::new(false));
+    pub(super) fn new(clock: SharedClock) -> Self {
         Self {
-            should_exit,
-            check_interval: get_check_interval(),
+            token: CancellationToken::new(),
+            clock,
         }
     }
 
-    pub async fn start_monitoring(&self) {
+    pub(super) async fn start_monitoring(&self, collectors: CollectorRegistry) {
         // TODO(isaidsari): put it more convenient place
         if !sysinfo::IS_SUPPORTED_SYSTEM {
             error!("sysinfo is not supported on this system");
             return;
         }
 
-        fn get_last_check() -> i64 {
-            Utc::now().timestamp_millis()
-        }
-
-        let should_exit_clone = Arc::clone(&self.should_exit);
-        // rust doesn't allow us to move self into the closure, so we have to clone it
-        let check_interval = self.check_interval;
-
-        tokio::spawn(async move {
-            let mut system = System::new();
-            let mut disks = Disks::new_with_refreshed_list();
-
-            while !*should_exit_clone.lock().unwrap() {
-                let start_time = Instant::now();
-
-                // refresh all system info WARN: this takes too much time
-                // let mut system = sysinfo::System::new_all();
-                // system.refresh_all();
-
-                // Refresh system information
-                system.refresh_specifics(
-                    RefreshKind::new()
-                        // TODO(isaidsari): check if we need to refresh all of them
-                        .with_cpu(CpuRefreshKind::everything())
-                        .with_memory(MemoryRefreshKind::everything()),
-                );
-
-                // Refresh disks information, since with sysinfo v0.30 it's not refreshed with the System
-                // NOTE: if a disk is added or removed, this method won't take it into account
-                disks.refresh();
-
-                // disks
-                let mut disk_usage: DiskFrameStatus = DiskFrameStatus {
-                    id: -1,
-                    last_check: get_last_check(),
-                    disks_usage: vec![],
-                };
-                let mut disks_info: Vec<HardwareDiskInfo> = vec![];
-                for disk in &disks {
-                    let disk_id = disk.get_disk_id();
-                    let disk_name = match disk.name().to_os_string().into_string() {
-                        Ok(name) => {
-                            if name.is_empty() {
-                                // if the name is empty, it's probably a local disk
-                                // TODO(isaidsari): add C: etc
-                                "Local Disk".to_string()
-                            } else {
-                                name
-                            }
-                        }
-                        Err(_) => "".to_string(),
-                    };
-
-                    disk_usage.disks_usage.push(SingleDiskInfo {
-                        id: -1,
-                        frame_id: -1,
-                        disk_id: disk_id.to_string(),
-                        // sqlx doesn't support u64
-                        available: disk.available_space() as i64,
-                    });
-
-                    disks_info.push(HardwareDiskInfo {
-                        id: -1,
-                        fs_type: disk
-                            .file_system()
-                            .to_os_string()
-                            .into_string()
-                            .unwrap_or_default(),
-                        is_removable: disk.is_removable(),
-                        kind: format!("{:?}", disk.kind()),
-                        mount_point: disk.mount_point().to_string_lossy().to_string(),
-                        // sqlx doesn't support u64
-                        total_space: disk.total_space() as i64,
-                        disk_id: disk_id.to_string(),
-                        name: disk_name,
-                        last_check: get_last_check(),
-                    });
-                }
-
-                // cpu
-                let all_cpus = system.cpus();
-                let mut cpu_usage: CpuFrameStatus = CpuFrameStatus {
-                    id: -1,
-                    last_check: get_last_check(),
-                    cores_usage: vec![],
-                };
-                let mut cpu_info: Vec<HardwareCpuInfo> = vec![];
-                for cpu in all_cpus {
-                    let cpu_id = &cpu.get_cpu_id();
-
-                    cpu_usage.cores_usage.push(CpuCoreInfo {
-                        id: -1,
-                        frame_id: -1,
-                        cpu_id: cpu_id.to_string(),
-                        freq: cpu.frequency() as i64,
-                        usage: cpu.cpu_usage() as i64,
-                    });
-
-                    let cpu_id_owned = cpu_id.to_owned();
-                    if cpu_info.iter().any(|c| c.cpu_id == cpu_id_owned) {
-                        continue;
-                    } else {
-                        let core_count = all_cpus
-                            .iter()
-                            .filter(|c| c.get_cpu_id() == cpu_id_owned)
-                            .count();
-
-                        let new_info = HardwareCpuInfo {
-                            id: -1,
-                            cpu_id: cpu_id_owned,
-                            core_count: core_count as i32,
-                            brand: cpu.brand().to_string(),
-                            vendor_id: cpu.vendor_id().to_string(),
-                            last_check: get_last_check(),
-                        };
-
-                        cpu_info.push(new_info);
-                    }
-                }
+        // before the first sample, so the last sample in the database is from before the boot
+        check_reboot(&get_config().reboot_detection, &FcmNotifier, &self.clock).await;
+
+        // every collector runs on its own task and interval
+        // and sends its frames to a single writer, which batches them by tick
+        let frames = spawn_frame_writer(&self.token);
+        let samples: SharedSamples = Arc::new(Mutex::new(LatestSamples::default()));
+        collectors.start(&self.token, &frames, &samples);
+        spawn_process_watch(&self.token, &self.clock);
+        spawn_systemd_watch(&self.token, &self.clock);
+        spawn_hardware_inventory(&self.token, &samples, &self.clock);
+
+        let latest_custom_metrics: LatestCustomMetrics = Arc::new(Mutex::new(HashMap::new()));
+        spawn_custom_probes(
+            &get_config().custom_probes,
+            &latest_custom_metrics,
+            &self.token,
+            &self.clock,
+        );
+        spawn_endpoint_probes(&get_config().endpoint_probes, &self.token, &self.clock);
+        spawn_log_watches(&get_config().log_watches, &self.token, &self.clock);
+        spawn_certificate_checks(&get_config().certificates, &self.token, &self.clock);
+        spawn_retention(&get_config().retention, &self.token);
+        spawn_backups(&get_config().backup, &self.token);
+        spawn_rollups(&self.token, &self.clock);
 
-                // mem
-                let mem_info: Vec<HardwareMemInfo> = vec![HardwareMemInfo {
-                    id: -1,
-                    mem_id: "1".to_string(),
-                    last_check: get_last_check(),
-                    total_space: system.total_memory() as i64,
-                }];
-
-                let mem_usage: MemFrameStatus = MemFrameStatus {
-                    id: -1,
-                    last_check: get_last_check(),
-                    mems_usage: vec![SingleMemInfo {
-                        id: -1,
-                        frame_id: -1,
-                        // constant, as there's only one mem
-                        mem_id: "1".to_string(),
-                        // sqlx doesn't support u64
-                        available: system.free_memory() as i64,
-                    }],
-                };
-
-                let hardware_info = HardwareInfo {
-                    cpu_info,
-                    disks_info: disks_info.clone(),
-                    mem_info: mem_info.clone(),
-                };
-
-                let elapsed_time = start_time.elapsed();
-
-                debug!("ms amount it took to refresh: {:?}", elapsed_time);
-
-                // TODO(adnanjpg): make this one run only once on startup, not every time
-                if let Err(e) = insert_hardware_info(&hardware_info).await {
-                    error!("failed to insert hardware info: {}", e);
-                };
-
-                if let Err(e) = insert_cpu_status_frame(&cpu_usage).await {
-                    error!("failed to insert cpu status: {}", e);
-                }
-                if let Err(e) = insert_disk_status_frame(&disk_usage).await {
-                    error!("failed to insert disk status: {}", e);
-                }
-                if let Err(e) = insert_mem_status_frame(&mem_usage).await {
-                    error!("failed to insert mem status: {}", e);
-                }
+        spawn_threshold_checks(&self.token, &samples, &latest_custom_metrics, &self.clock);
 
-                let cpu_status = &CpuStatusData {
-                    frames: vec![cpu_usage],
-                };
-                let mem_status = &MemStatusData {
-                    frames: vec![mem_usage],
-                };
-                let disk_status = &DiskStatusData {
-                    frames: vec![disk_usage],
-                };
-
-                // TODO(adnanjpg): run on a different thread with a different interval
-                check_thresholds(cpu_status, mem_status, &mem_info, disk_status, &disks_info).await;
-
-                let duration = match check_interval.checked_sub(elapsed_time) {
-                    Some(duration) => duration,
-                    None => {
-                        error!("check interval is less than elapsed time");
-                        Duration::default()
-                    }
-                };
-                time::sleep(duration).await;
-            }
-        });
+        debug!("all collectors started");
     }
 
     // TODO(isaidsari): graceful shutdown
     #[allow(dead_code)]
     pub fn stop_monitoring(&self) {
-        *self.should_exit.lock().unwrap() = true;
+        self.token.cancel();
     }
 }
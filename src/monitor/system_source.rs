@@ -0,0 +1,307 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use sysinfo::{
+    Components, CpuRefreshKind, Disks, MemoryRefreshKind, ProcessRefreshKind, RefreshKind, System,
+    UpdateKind,
+};
+
+use super::cgroups::{read_cgroups, CgroupCountersMap};
+use super::diskstats::{read_diskstats, DiskStatsMap};
+use super::inode_usage::get_inode_usage;
+use super::models::get_pressure_status::SinglePressureInfo;
+use super::models::get_process_watch::RunningProcess;
+use super::pressure::read_pressure;
+
+// how often the disk list is re-enumerated, to pick up the added and removed disks
+const DISK_RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub(super) struct CpuReading {
+    pub vendor_id: String,
+    pub brand: String,
+    pub frequency: u64,
+    pub usage: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct MemReading {
+    pub total: u64,
+    pub free: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct DiskReading {
+    pub name: String,
+    pub file_system: String,
+    // the debug format of the sysinfo `DiskKind`, e.g. `SSD`
+    pub kind: String,
+    pub is_removable: bool,
+    pub mount_point: String,
+    pub total_space: u64,
+    pub available_space: u64,
+    // the total and the free inode count
+    pub inodes: Option<(i64, i64)>,
+}
+
+// the values sysinfo failed to read are NaN
+#[derive(Debug, Clone)]
+pub(super) struct ComponentReading {
+    pub label: String,
+    pub temperature: f32,
+    pub max: f32,
+    pub critical: Option<f32>,
+}
+
+// where the collectors read the system from, so they can be run
+// against recorded readings in the tests
+pub(super) trait SystemSource: Send + Sync {
+    // one reading for every core
+    fn read_cpus(&mut self) -> Vec<CpuReading>;
+
+    fn read_mem(&mut self) -> MemReading;
+
+    fn read_disks(&mut self) -> Vec<DiskReading>;
+
+    // None where /proc/diskstats isn't available
+    fn read_diskstats(&mut self) -> Option<DiskStatsMap>;
+
+    // the temperature sensors, empty on most VMs
+    fn read_components(&mut self) -> Vec<ComponentReading>;
+
+    // None where the kernel doesn't support PSI
+    fn read_pressure(&mut self) -> Option<Vec<SinglePressureInfo>>;
+
+    // None where there's no cgroup v2 hierarchy at the root
+    fn read_cgroups(&mut self, root: &Path, max_depth: usize) -> Option<CgroupCountersMap>;
+
+    fn read_processes(&mut self) -> Vec<RunningProcess>;
+}
+
+pub(super) struct SysinfoSource {
+    system: System,
+    disks: Disks,
+    last_disk_rescan: Instant,
+    // listed on the first read, only the temperature collector reads them
+    components: Option<Components>,
+}
+
+impl SysinfoSource {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            disks: Disks::new_with_refreshed_list(),
+            last_disk_rescan: Instant::now(),
+            components: None,
+        }
+    }
+}
+
+impl SystemSource for SysinfoSource {
+    fn read_cpus(&mut self) -> Vec<CpuReading> {
+        self.system
+            .refresh_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::everything()));
+
+        self.system
+            .cpus()
+            .iter()
+            .map(|cpu| CpuReading {
+                vendor_id: cpu.vendor_id().to_string(),
+                brand: cpu.brand().to_string(),
+                frequency: cpu.frequency(),
+                usage: cpu.cpu_usage(),
+            })
+            .collect()
+    }
+
+    fn read_mem(&mut self) -> MemReading {
+        self.system
+            .refresh_specifics(RefreshKind::new().with_memory(MemoryRefreshKind::everything()));
+
+        MemReading {
+            total: self.system.total_memory(),
+            free: self.system.free_memory(),
+        }
+    }
+
+    fn read_disks(&mut self) -> Vec<DiskReading> {
+        // Refresh disks information, since with sysinfo v0.30 it's not refreshed with the System
+        // refresh() doesn't take added or removed disks into account, so the list
+        // is rebuilt every once in a while
+        if self.last_disk_rescan.elapsed() >= DISK_RESCAN_INTERVAL {
+            self.disks.refresh_list();
+            self.last_disk_rescan = Instant::now();
+        } else {
+            self.disks.refresh();
+        }
+
+        self.disks
+            .iter()
+            .map(|disk| DiskReading {
+                name: disk.name().to_string_lossy().to_string(),
+                file_system: disk.file_system().to_str().unwrap_or_default().to_string(),
+                kind: format!("{:?}", disk.kind()),
+                is_removable: disk.is_removable(),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                total_space: disk.total_space(),
+                available_space: disk.available_space(),
+                inodes: get_inode_usage(disk.mount_point()),
+            })
+            .collect()
+    }
+
+    fn read_diskstats(&mut self) -> Option<DiskStatsMap> {
+        read_diskstats()
+    }
+
+    fn read_components(&mut self) -> Vec<ComponentReading> {
+        let components = match &mut self.components {
+            Some(components) => {
+                components.refresh();
+                components
+            }
+            None => self
+                .components
+                .insert(Components::new_with_refreshed_list()),
+        };
+
+        components
+            .iter()
+            .map(|component| ComponentReading {
+                label: component.label().to_string(),
+                temperature: component.temperature(),
+                max: component.max(),
+                critical: component.critical(),
+            })
+            .collect()
+    }
+
+    fn read_pressure(&mut self) -> Option<Vec<SinglePressureInfo>> {
+        read_pressure()
+    }
+
+    fn read_cgroups(&mut self, root: &Path, max_depth: usize) -> Option<CgroupCountersMap> {
+        read_cgroups(root, max_depth)
+    }
+
+    fn read_processes(&mut self) -> Vec<RunningProcess> {
+        // processes are expensive to refresh, the command lines are only read once
+        self.system.refresh_processes_specifics(
+            ProcessRefreshKind::new().with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+
+        self.system
+            .processes()
+            .values()
+            .map(|p| RunningProcess {
+                name: p.name().to_string(),
+                cmdline: p.cmd().join(" "),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(super) use self::scripted::{ScriptedSource, SystemReading};
+
+#[cfg(test)]
+mod scripted {
+    use super::*;
+
+    // everything the system reported at one point
+    #[derive(Debug, Clone, Default)]
+    pub struct SystemReading {
+        pub cpus: Vec<CpuReading>,
+        pub mem: MemReading,
+        pub disks: Vec<DiskReading>,
+        pub diskstats: Option<DiskStatsMap>,
+        pub components: Vec<ComponentReading>,
+        pub pressure: Option<Vec<SinglePressureInfo>>,
+        pub cgroups: Option<CgroupCountersMap>,
+        pub processes: Vec<RunningProcess>,
+    }
+
+    // replays the readings in order, one for every read, and keeps
+    // repeating the last one once they run out
+    pub struct ScriptedSource {
+        readings: Vec<SystemReading>,
+        cpus_read: usize,
+        mem_read: usize,
+        disks_read: usize,
+        diskstats_read: usize,
+        components_read: usize,
+        pressure_read: usize,
+        cgroups_read: usize,
+        processes_read: usize,
+    }
+
+    impl ScriptedSource {
+        pub fn new(readings: Vec<SystemReading>) -> Self {
+            assert!(!readings.is_empty(), "there's nothing to replay");
+
+            Self {
+                readings,
+                cpus_read: 0,
+                mem_read: 0,
+                disks_read: 0,
+                diskstats_read: 0,
+                components_read: 0,
+                pressure_read: 0,
+                cgroups_read: 0,
+                processes_read: 0,
+            }
+        }
+    }
+
+    fn replay<'a>(readings: &'a [SystemReading], read: &mut usize) -> &'a SystemReading {
+        let reading = &readings[(*read).min(readings.len() - 1)];
+        *read += 1;
+
+        reading
+    }
+
+    impl SystemSource for ScriptedSource {
+        fn read_cpus(&mut self) -> Vec<CpuReading> {
+            replay(&self.readings, &mut self.cpus_read).cpus.clone()
+        }
+
+        fn read_mem(&mut self) -> MemReading {
+            replay(&self.readings, &mut self.mem_read).mem.clone()
+        }
+
+        fn read_disks(&mut self) -> Vec<DiskReading> {
+            replay(&self.readings, &mut self.disks_read).disks.clone()
+        }
+
+        fn read_diskstats(&mut self) -> Option<DiskStatsMap> {
+            replay(&self.readings, &mut self.diskstats_read)
+                .diskstats
+                .clone()
+        }
+
+        fn read_components(&mut self) -> Vec<ComponentReading> {
+            replay(&self.readings, &mut self.components_read)
+                .components
+                .clone()
+        }
+
+        fn read_pressure(&mut self) -> Option<Vec<SinglePressureInfo>> {
+            replay(&self.readings, &mut self.pressure_read)
+                .pressure
+                .clone()
+        }
+
+        // the readings are of the scripted hierarchy, whatever the root
+        fn read_cgroups(&mut self, _root: &Path, _max_depth: usize) -> Option<CgroupCountersMap> {
+            replay(&self.readings, &mut self.cgroups_read)
+                .cgroups
+                .clone()
+        }
+
+        fn read_processes(&mut self) -> Vec<RunningProcess> {
+            replay(&self.readings, &mut self.processes_read)
+                .processes
+                .clone()
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::process::Command;
+
+use super::clock::SharedClock;
+use super::config_exceeds::{get_send_notification_interval, should_send_notification};
+use super::models::get_systemd_units::{SystemdUnitEvent, SystemdUnitState};
+use super::notifier::{send_to_all_devices, Notifier};
+use super::persistence::{fetch_latest_systemd_unit_event, insert_systemd_unit_event};
+use crate::notification_service::NotificationMessage;
+use crate::persistence::notification_logs::NotificationType;
+
+const SHOWN_PROPERTIES: &str = "ActiveState,SubState,NRestarts";
+// a hung systemctl, e.g. while dbus is restarting, is killed after this
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// runs external commands, so the tests can replace systemctl with canned output
+pub trait CommandRunner: Send + Sync {
+    // returns the stdout of the command
+    fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+    ) -> impl Future<Output = Result<String, String>> + Send;
+}
+
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    async fn run(&self, program: &str, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(program).args(args).kill_on_drop(true).output();
+
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+            .await
+            .map_err(|_| format!("{} timed out after {:?}", program, COMMAND_TIMEOUT))?
+            .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+// unit -> its state on the last check
+pub type SystemdUnitStateMap = HashMap<String, SystemdUnitState>;
+
+#[derive(Debug, PartialEq)]
+enum UnitTransition {
+    // nothing changed since the last check
+    None,
+    // seen for the first time, and it's not failed
+    Initial,
+    // changed, but not in a way worth a notification, e.g. active -> reloading
+    Changed,
+    Failed,
+    Restarted,
+    Recovered,
+}
+
+fn get_transition(
+    previous: Option<&SystemdUnitState>,
+    current: &SystemdUnitState,
+) -> UnitTransition {
+    let is_failed = current.active_state == "failed";
+
+    let previous = match previous {
+        Some(previous) => previous,
+        None if is_failed => return UnitTransition::Failed,
+        None => return UnitTransition::Initial,
+    };
+
+    if previous == current {
+        return UnitTransition::None;
+    }
+
+    let was_failed = previous.active_state == "failed";
+
+    if is_failed && !was_failed {
+        UnitTransition::Failed
+    } else if current.n_restarts > previous.n_restarts {
+        UnitTransition::Restarted
+    } else if was_failed && current.active_state == "active" {
+        UnitTransition::Recovered
+    } else {
+        UnitTransition::Changed
+    }
+}
+
+// `systemctl show` prints a block of key=value lines for each unit,
+// separated by empty lines, in the same order the units were given
+fn parse_systemctl_show(units: &[String], content: &str) -> Vec<SystemdUnitState> {
+    let blocks = content
+        .split("\n\n")
+        .map(|block| {
+            block
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .collect::<HashMap<&str, &str>>()
+        })
+        .filter(|block| !block.is_empty());
+
+    units
+        .iter()
+        .zip(blocks)
+        .map(|(unit, block)| SystemdUnitState {
+            unit: unit.to_string(),
+            active_state: block.get("ActiveState").unwrap_or(&"unknown").to_string(),
+            sub_state: block.get("SubState").unwrap_or(&"unknown").to_string(),
+            // older systemd versions don't have it
+            n_restarts: block
+                .get("NRestarts")
+                .and_then(|n| n.parse::<i64>().ok())
+                .unwrap_or(0),
+        })
+        .collect()
+}
+
+pub async fn get_unit_states(
+    runner: &impl CommandRunner,
+    units: &[String],
+) -> Result<Vec<SystemdUnitState>, String> {
+    let property_arg = format!("--property={}", SHOWN_PROPERTIES);
+    let mut args = vec!["show", property_arg.as_str(), "--"];
+    args.extend(units.iter().map(|u| u.as_str()));
+
+    let output = runner.run("systemctl", &args).await?;
+
+    Ok(parse_systemctl_show(units, &output))
+}
+
+// a unit in a restart loop would notify on every check, so the restarts
+// are sent to the devices whose cooldown has passed, like the exceeding thresholds
+async fn send_restart_notification(
+    message: &NotificationMessage,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let devices = match notifier.fetch_devices().await {
+        Ok(devices) => devices,
+        Err(e) => {
+            error!("failed to fetch monitor configs: {}", e);
+            return;
+        }
+    };
+
+    for (device_id, fcm_token) in devices {
+        if !should_send_notification(
+            notifier,
+            clock,
+            &device_id,
+            &NotificationType::SystemdUnitRestarted,
+        )
+        .await
+        {
+            warn!("did not send systemd unit restart notification to device {} because a notification has already been sent in the last {} seconds", device_id, get_send_notification_interval().num_seconds());
+
+            continue;
+        }
+
+        let not_res = notifier
+            .send(
+                &device_id,
+                &fcm_token,
+                message,
+                &NotificationType::SystemdUnitRestarted,
+            )
+            .await;
+
+        if let Err(not_res) = not_res {
+            error!(
+                "Sending systemd unit notification resulted with the following error: {}",
+                not_res
+            );
+        }
+    }
+}
+
+pub(super) async fn check_systemd_units(
+    runner: &impl CommandRunner,
+    units: &[String],
+    states: &mut SystemdUnitStateMap,
+    notifier: &impl Notifier,
+    clock: &SharedClock,
+) {
+    let last_check = clock.now_millis();
+    let unit_states = match get_unit_states(runner, units).await {
+        Ok(unit_states) => unit_states,
+        Err(e) => {
+            error!("failed to read the systemd unit states: {}", e);
+            return;
+        }
+    };
+
+    for state in unit_states {
+        // after a restart, continue from the last recorded state
+        // so we don't report the same transition twice
+        if !states.contains_key(&state.unit) {
+            match fetch_latest_systemd_unit_event(&state.unit).await {
+                Ok(Some(event)) => {
+                    states.insert(
+                        state.unit.to_string(),
+                        SystemdUnitState {
+                            unit: event.unit,
+                            active_state: event.active_state,
+                            sub_state: event.sub_state,
+                            n_restarts: event.n_restarts,
+                        },
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("failed to fetch latest systemd unit event: {}", e);
+                }
+            }
+        }
+
+        let transition = get_transition(states.get(&state.unit), &state);
+
+        if transition == UnitTransition::None {
+            continue;
+        }
+
+        let event = SystemdUnitEvent {
+            id: -1,
+            unit: state.unit.to_string(),
+            active_state: state.active_state.to_string(),
+            sub_state: state.sub_state.to_string(),
+            n_restarts: state.n_restarts,
+            last_check,
+        };
+        if let Err(e) = insert_systemd_unit_event(&event).await {
+            error!("failed to insert systemd unit event: {}", e);
+        }
+
+        let previous_restarts = states.get(&state.unit).map(|s| s.n_restarts).unwrap_or(0);
+        states.insert(state.unit.to_string(), state.clone());
+
+        let (message, notification_type) = match transition {
+            UnitTransition::Failed => {
+                warn!("systemd unit {} failed", state.unit);
+
+                (
+                    NotificationMessage {
+                        title: format!("IMPORTANT: {} failed", state.unit),
+                        body: format!(
+                            "the systemd unit {} is {} ({})",
+                            state.unit, state.active_state, state.sub_state
+                        ),
+                    },
+                    NotificationType::SystemdUnitFailed,
+                )
+            }
+            UnitTransition::Restarted => {
+                warn!("systemd unit {} restarted", state.unit);
+
+                (
+                    NotificationMessage {
+                        title: format!("IMPORTANT: {} is restarting", state.unit),
+                        body: format!(
+                            "the systemd unit {} was restarted {} times since the last check, {} in total",
+                            state.unit,
+                            state.n_restarts - previous_restarts,
+                            state.n_restarts
+                        ),
+                    },
+                    NotificationType::SystemdUnitRestarted,
+                )
+            }
+            UnitTransition::Recovered => {
+                info!("systemd unit {} recovered", state.unit);
+
+                (
+                    NotificationMessage {
+                        title: format!("{} recovered", state.unit),
+                        body: format!(
+                            "the systemd unit {} is {} ({}) again",
+                            state.unit, state.active_state, state.sub_state
+                        ),
+                    },
+                    NotificationType::SystemdUnitRecovered,
+                )
+            }
+            _ => continue,
+        };
+
+        if matches!(notification_type, NotificationType::SystemdUnitRestarted) {
+            send_restart_notification(&message, notifier, clock).await;
+            continue;
+        }
+
+        let not_res = send_to_all_devices(notifier, &message, &notification_type).await;
+
+        if let Err(not_res) = not_res {
+            error!(
+                "Sending systemd unit notification resulted with the following error: {}",
+                not_res
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // returns the given output, and remembers the command it was asked to run
+    struct FakeCommandRunner {
+        output: Result<String, String>,
+        ran: Mutex<Vec<String>>,
+    }
+
+    impl CommandRunner for FakeCommandRunner {
+        async fn run(&self, program: &str, args: &[&str]) -> Result<String, String> {
+            self.ran
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", program, args.join(" ")));
+
+            self.output.clone()
+        }
+    }
+
+    fn state(active_state: &str, sub_state: &str, n_restarts: i64) -> SystemdUnitState {
+        SystemdUnitState {
+            unit: "api.service".to_string(),
+            active_state: active_state.to_string(),
+            sub_state: sub_state.to_string(),
+            n_restarts,
+        }
+    }
+
+    #[tokio::test]
+    async fn get_unit_states_test() {
+        let runner = FakeCommandRunner {
+            output: Ok("ActiveState=active\nSubState=running\nNRestarts=0\n\n\
+                        NRestarts=7\nActiveState=activating\nSubState=auto-restart\n\n\
+                        ActiveState=failed\nSubState=failed\n"
+                .to_string()),
+            ran: Mutex::new(vec![]),
+        };
+
+        let units = vec![
+            "nginx.service".to_string(),
+            "api.service".to_string(),
+            "old.service".to_string(),
+        ];
+
+        let states = get_unit_states(&runner, &units).await.unwrap();
+
+        assert_eq!(
+            runner.ran.lock().unwrap().as_slice(),
+            &["systemctl show --property=ActiveState,SubState,NRestarts -- nginx.service api.service old.service"]
+        );
+        assert_eq!(
+            states,
+            vec![
+                SystemdUnitState {
+                    unit: "nginx.service".to_string(),
+                    active_state: "active".to_string(),
+                    sub_state: "running".to_string(),
+                    n_restarts: 0,
+                },
+                SystemdUnitState {
+                    unit: "api.service".to_string(),
+                    active_state: "activating".to_string(),
+                    sub_state: "auto-restart".to_string(),
+                    n_restarts: 7,
+                },
+                // systemd versions without NRestarts
+                SystemdUnitState {
+                    unit: "old.service".to_string(),
+                    active_state: "failed".to_string(),
+                    sub_state: "failed".to_string(),
+                    n_restarts: 0,
+                },
+            ]
+        );
+
+        let failing_runner = FakeCommandRunner {
+            output: Err("failed to run systemctl".to_string()),
+            ran: Mutex::new(vec![]),
+        };
+        assert!(get_unit_states(&failing_runner, &units).await.is_err());
+    }
+
+    #[test]
+    fn get_transition_test() {
+        let running = state("active", "running", 0);
+
+        assert_eq!(get_transition(None, &running), UnitTransition::Initial);
+        assert_eq!(
+            get_transition(None, &state("failed", "failed", 0)),
+            UnitTransition::Failed
+        );
+        assert_eq!(
+            get_transition(Some(&running), &running),
+            UnitTransition::None
+        );
+        assert_eq!(
+            get_transition(Some(&running), &state("failed", "failed", 0)),
+            UnitTransition::Failed
+        );
+        assert_eq!(
+            get_transition(Some(&running), &state("activating", "auto-restart", 1)),
+            UnitTransition::Restarted
+        );
+        assert_eq!(
+            get_transition(Some(&state("failed", "failed", 0)), &running),
+            UnitTransition::Recovered
+        );
+        assert_eq!(
+            get_transition(Some(&running), &state("reloading", "running", 0)),
+            UnitTransition::Changed
+        );
+    }
+}
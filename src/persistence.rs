@@ -1,13 +1,62 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
 use async_once::AsyncOnce;
-use log::error;
-use sqlx::sqlite::SqlitePoolOptions;
+use log::{error, info, warn};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Pool, Sqlite};
 
+use self::migrations::{run_migrations, MigrationError};
+use self::retention::{is_incremental_vacuum_enabled, vacuum};
+use crate::config::{get_config, Synchronous};
+
+pub mod backup;
+pub mod migrations;
 pub mod notification_logs;
+pub mod retention;
+
+const DB_PATH_ENV_VAR: &str = "REMON_DB_PATH";
+const DB_FILE_NAME: &str = "monitor.sqlite3";
+// the default directory when remon runs as root, a system service
+const SYSTEM_DB_FOLDER_PATH: &str = "/var/lib/remon";
+// where the database was kept before its path was configurable, relative to
+// the directory the server was started in
+const LEGACY_DB_PATH: &str = "db/monitor.sqlite3";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error(
+        "the database path {0} isn't absolute, set `database.path` or REMON_DB_PATH to an \
+         absolute path"
+    )]
+    RelativePath(String),
+    #[error(
+        "there's no home directory to keep the database in, set `database.path` or \
+         REMON_DB_PATH"
+    )]
+    NoDataDir,
+    #[error("failed to {action} {}: {source}{}", .path.display(), get_permission_hint(.source))]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Migration(#[from] MigrationError),
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+}
 
-const SQLITE_DBS_FOLDER_PATH: &str = "./db";
-const SQLITE_DB_PATH: &str = "./db/monitor.sqlite3";
-const SQLITE_DB_CONN_STR: &str = "sqlite:./db/monitor.sqlite3";
+fn get_permission_hint(e: &std::io::Error) -> &'static str {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            ", the user running remon needs write access to it, or `database.path` can point \
+             to a directory it owns"
+        }
+        _ => "",
+    }
+}
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct FetchId {
@@ -37,48 +86,252 @@ pub async fn get_sql_connection() -> Result<SQLConnection, sqlx::Error> {
     }
 }
 
-// https://github.com/brettwooldridge/HikariCP/wiki/About-Pool-Sizing
-// https://www.cockroachlabs.com/blog/what-is-connection-pooling/
-// the info from these two links tells us that
-// - we should have a pool IF we have a lot of queries happening, which we do
-// - a lot of queries means a lot of connections, which has open and close overhead
-// - so this means we should have a pool
-// - as for the size of the pool, we should remember that each connection is a thread
-// - and maintaining a lot of threads is expensive
-// - as our _current_ queries are all small, a single connection should be enough
-// TODO(adnanjpg): make this configurable
-const MAX_CONNECTIONS: u32 = 1;
+// the path the database was opened at, set once by `init_db`
+static DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn get_db_path() -> Option<&'static Path> {
+    DB_PATH.get().map(|path| path.as_path())
+}
 
 // https://stackoverflow.com/a/67758135/12555423
 lazy_static! {
     static ref POOL: AsyncOnce<Result<SQLConnection, sqlx::Error>> = AsyncOnce::new(async {
-        let con = SqlitePoolOptions::new()
-            .max_connections(MAX_CONNECTIONS)
-            .connect(SQLITE_DB_CONN_STR);
+        let Some(path) = get_db_path() else {
+            return Err(sqlx::Error::Configuration(
+                "the database is opened before it was initialized".into(),
+            ));
+        };
+        let config = &get_config().database;
 
-        let pool = con.await;
+        let synchronous = match config.synchronous {
+            Synchronous::Off => SqliteSynchronous::Off,
+            Synchronous::Normal => SqliteSynchronous::Normal,
+            Synchronous::Full => SqliteSynchronous::Full,
+            Synchronous::Extra => SqliteSynchronous::Extra,
+        };
 
-        pool
+        // with the wal journal the readers don't wait for the writer, and the
+        // writers wait up to busy_timeout for each other
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(synchronous)
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms));
+
+        SqlitePoolOptions::new()
+            .max_connections(config.max_connections.max(1))
+            .connect_with(options)
+            .await
     });
 }
 
-pub async fn init_db() -> Result<(), sqlx::Error> {
-    // check if db folder exists
-    if !std::path::Path::new(SQLITE_DBS_FOLDER_PATH).exists() {
-        // create db folder
-        std::fs::create_dir(SQLITE_DBS_FOLDER_PATH).unwrap();
+// an explicit path, otherwise /var/lib/remon for root and the xdg data
+// directory for everyone else
+fn get_default_db_path(
+    path: Option<String>,
+    is_root: bool,
+    xdg_data_home: Option<String>,
+    home: Option<String>,
+) -> Result<PathBuf, DatabaseError> {
+    if let Some(path) = path {
+        if !Path::new(&path).is_absolute() {
+            return Err(DatabaseError::RelativePath(path));
+        }
+
+        return Ok(PathBuf::from(path));
     }
-    // if db file not exists, create it
-    if !std::path::Path::new(SQLITE_DB_PATH).exists() {
-        // create db file
-        std::fs::File::create(SQLITE_DB_PATH).unwrap();
+
+    if is_root {
+        return Ok(Path::new(SYSTEM_DB_FOLDER_PATH).join(DB_FILE_NAME));
     }
 
+    // the spec says a relative XDG_DATA_HOME is invalid and should be ignored
+    let data_home = xdg_data_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| home.map(|home| Path::new(&home).join(".local/share")))
+        .ok_or(DatabaseError::NoDataDir)?;
+
+    Ok(data_home.join("remon").join(DB_FILE_NAME))
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid can't fail and has no side effects
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+fn resolve_db_path() -> Result<PathBuf, DatabaseError> {
+    let configured = std::env::var(DB_PATH_ENV_VAR)
+        .ok()
+        .or_else(|| get_config().database.path.clone());
+
+    // the existing installs keep their database until it's moved
+    if configured.is_none() && Path::new(LEGACY_DB_PATH).exists() {
+        let path = std::env::current_dir()
+            .map_err(|source| DatabaseError::Io {
+                action: "read the current directory of",
+                path: PathBuf::from("."),
+                source,
+            })?
+            .join(LEGACY_DB_PATH);
+        warn!(
+            "using the database at {}, move it and set `database.path`, so it doesn't depend \
+             on the directory remon is started in",
+            path.display()
+        );
+
+        return Ok(path);
+    }
+
+    get_default_db_path(
+        configured,
+        is_root(),
+        std::env::var("XDG_DATA_HOME").ok(),
+        std::env::var("HOME").ok(),
+    )
+}
+
+// fails early with the path and the reason, sqlite only says it's unable to
+// open the database file
+fn check_db_path(path: &Path) -> Result<(), DatabaseError> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder).map_err(|source| DatabaseError::Io {
+            action: "create the database directory",
+            path: folder.to_path_buf(),
+            source,
+        })?;
+
+        // the wal and the shared memory files are created next to the database
+        check_folder_writable(folder)?;
+    }
+
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|source| DatabaseError::Io {
+            action: "open the database file",
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn check_folder_writable(folder: &Path) -> Result<(), DatabaseError> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(folder.as_os_str().as_bytes()) else {
+        return Ok(());
+    };
+
+    // SAFETY: `c_path` is a valid nul terminated string
+    if unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } != 0 {
+        return Err(DatabaseError::Io {
+            action: "write to the database directory",
+            path: folder.to_path_buf(),
+            source: std::io::Error::last_os_error(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_folder_writable(_folder: &Path) -> Result<(), DatabaseError> {
+    Ok(())
+}
+
+pub async fn init_db() -> Result<(), DatabaseError> {
+    let path = resolve_db_path()?;
+    check_db_path(&path)?;
+    info!("the database is at {}", path.display());
+
+    // if it was already set, we keep the first one
+    let _ = DB_PATH.set(path);
+
     let conn = get_default_sql_connection().await?;
 
-    crate::monitor::persistence::init_db(&conn).await?;
+    // a new database is switched to incremental vacuum while it's still empty,
+    // which is instant, the existing ones are left to `remon-server vacuum`
+    {
+        let mut vacuum_conn = conn.acquire().await?;
+
+        if !is_incremental_vacuum_enabled(&mut vacuum_conn).await? {
+            let table_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sqlite_master")
+                .fetch_one(&mut *vacuum_conn)
+                .await?;
+
+            if table_count == 0 {
+                vacuum(&mut vacuum_conn).await?;
+            } else {
+                info!("the pruned rows are only given back to the file system after `remon-server vacuum` runs once");
+            }
+        }
+    }
 
-    notification_logs::create_notification_logs_table(&conn).await?;
+    let version = run_migrations(&conn).await?;
+    info!("the database is at schema version {}", version);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_path(
+        path: Option<&str>,
+        is_root: bool,
+        xdg_data_home: Option<&str>,
+        home: Option<&str>,
+    ) -> Result<PathBuf, DatabaseError> {
+        get_default_db_path(
+            path.map(String::from),
+            is_root,
+            xdg_data_home.map(String::from),
+            home.map(String::from),
+        )
+    }
+
+    #[test]
+    fn get_default_db_path_test() {
+        assert_eq!(
+            default_path(Some("/srv/remon.sqlite3"), true, None, None).unwrap(),
+            PathBuf::from("/srv/remon.sqlite3")
+        );
+        assert!(matches!(
+            default_path(Some("db/monitor.sqlite3"), false, None, Some("/home/a")),
+            Err(DatabaseError::RelativePath(_))
+        ));
+
+        assert_eq!(
+            default_path(None, true, Some("/home/a/.data"), Some("/home/a")).unwrap(),
+            PathBuf::from("/var/lib/remon/monitor.sqlite3")
+        );
+        assert_eq!(
+            default_path(None, false, Some("/home/a/.data"), Some("/home/a")).unwrap(),
+            PathBuf::from("/home/a/.data/remon/monitor.sqlite3")
+        );
+        // a relative XDG_DATA_HOME is ignored
+        assert_eq!(
+            default_path(None, false, Some("data"), Some("/home/a")).unwrap(),
+            PathBuf::from("/home/a/.local/share/remon/monitor.sqlite3")
+        );
+        assert!(matches!(
+            default_path(None, false, None, None),
+            Err(DatabaseError::NoDataDir)
+        ));
+    }
+}
@@ -0,0 +1,489 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteLockingMode};
+use sqlx::{ConnectOptions, Connection, SqliteConnection};
+use tokio::sync::Mutex;
+
+use super::migrations::{fetch_schema_version, get_latest_schema_version};
+use super::{
+    get_db_path, get_default_sql_connection, resolve_db_path, DatabaseError, SQLConnection,
+};
+use crate::config::BackupConfig;
+
+const BACKUP_FILE_PREFIX: &str = "monitor-";
+const BACKUP_FILE_EXTENSION: &str = ".sqlite3";
+// the backups are written under a name the rotation doesn't match, and
+// renamed once they're complete
+const PARTIAL_BACKUP_EXTENSION: &str = "partial";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error("the backup path {0} isn't absolute, set `backup.path` to an absolute path")]
+    RelativePath(String),
+    #[error("{} isn't a remon database, it has no schema version", .0.display())]
+    NotRemonDatabase(PathBuf),
+    #[error("{} failed the integrity check: {result}", .path.display())]
+    Corrupt { path: PathBuf, result: String },
+    #[error(
+        "the database {} is in use, stop the server before restoring a backup",
+        .0.display()
+    )]
+    DatabaseInUse(PathBuf),
+    #[error(
+        "the backup is at schema version {backup_version}, but this build only knows up to \
+         version {code_version}, update remon before restoring it"
+    )]
+    NewerSchema {
+        backup_version: i64,
+        code_version: i64,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupReport {
+    pub path: String,
+    pub size: i64,
+    pub schema_version: i64,
+    // the older backups that were rotated out
+    pub removed: Vec<String>,
+    pub duration_ms: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RestoreReport {
+    pub path: String,
+    pub schema_version: i64,
+    // where the database that was replaced was moved to
+    pub previous_path: Option<String>,
+}
+
+// the scheduled backups and the admin endpoint don't write at the same time
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn io_error(action: &'static str, path: &Path) -> impl FnOnce(std::io::Error) -> BackupError {
+    let path = path.to_path_buf();
+
+    move |source| {
+        BackupError::Database(DatabaseError::Io {
+            action,
+            path,
+            source,
+        })
+    }
+}
+
+fn get_backup_folder(config: &BackupConfig) -> Result<PathBuf, BackupError> {
+    if let Some(path) = &config.path {
+        if !Path::new(path).is_absolute() {
+            return Err(BackupError::RelativePath(path.clone()));
+        }
+
+        return Ok(PathBuf::from(path));
+    }
+
+    let db_path = get_db_path().ok_or(sqlx::Error::PoolClosed)?;
+
+    Ok(db_path.with_file_name("backups"))
+}
+
+// the names sort in the order the backups were taken
+fn get_backup_file_name(now: DateTime<Utc>) -> String {
+    format!(
+        "{}{}{}",
+        BACKUP_FILE_PREFIX,
+        now.format("%Y%m%d-%H%M%S-%3f"),
+        BACKUP_FILE_EXTENSION
+    )
+}
+
+fn is_backup_file_name(name: &str) -> bool {
+    name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(BACKUP_FILE_EXTENSION)
+}
+
+// deletes the oldest backups of the folder, so at most `keep` of them are left
+fn rotate_backups(folder: &Path, keep: usize) -> Result<Vec<PathBuf>, BackupError> {
+    let entries =
+        std::fs::read_dir(folder).map_err(io_error("read the backup directory", folder))?;
+
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_backup_file_name(name))
+        .collect::<Vec<_>>();
+    names.sort();
+
+    let expired = names.len().saturating_sub(keep.max(1));
+    let mut removed = vec![];
+    for name in &names[..expired] {
+        let path = folder.join(name);
+        std::fs::remove_file(&path).map_err(io_error("delete the old backup", &path))?;
+
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+// a consistent snapshot of the database, taken while the collectors keep
+// writing. `VACUUM INTO` reads it in a single transaction and writes a
+// compacted copy, with the configs, the devices and the notification logs too
+async fn backup_into(
+    conn: &SQLConnection,
+    folder: &Path,
+    keep: usize,
+    now: DateTime<Utc>,
+) -> Result<BackupReport, BackupError> {
+    let started = Instant::now();
+
+    std::fs::create_dir_all(folder).map_err(io_error("create the backup directory", folder))?;
+
+    let path = folder.join(get_backup_file_name(now));
+    let partial_path = path.with_extension(PARTIAL_BACKUP_EXTENSION);
+    // a crash can leave one behind, and `VACUUM INTO` doesn't overwrite a file
+    if partial_path.exists() {
+        std::fs::remove_file(&partial_path)
+            .map_err(io_error("delete the partial backup", &partial_path))?;
+    }
+
+    let mut db = conn.acquire().await?;
+    let schema_version = fetch_schema_version(&mut db).await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(partial_path.to_string_lossy().as_ref())
+        .execute(&mut *db)
+        .await?;
+    drop(db);
+
+    let file =
+        std::fs::File::open(&partial_path).map_err(io_error("open the backup", &partial_path))?;
+    file.sync_all()
+        .map_err(io_error("sync the backup", &partial_path))?;
+    let size = file
+        .metadata()
+        .map_err(io_error("read the size of the backup", &partial_path))?
+        .len() as i64;
+    std::fs::rename(&partial_path, &path).map_err(io_error("rename the backup", &path))?;
+
+    let removed = rotate_backups(folder, keep)?;
+
+    Ok(BackupReport {
+        path: path.to_string_lossy().to_string(),
+        size,
+        schema_version,
+        removed: removed
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+        duration_ms: started.elapsed().as_millis() as i64,
+        created_at: now.timestamp_millis(),
+    })
+}
+
+pub async fn backup_db(config: &BackupConfig) -> Result<BackupReport, BackupError> {
+    let _lock = BACKUP_LOCK.lock().await;
+
+    let folder = get_backup_folder(config)?;
+    let conn = get_default_sql_connection().await?;
+
+    let report = backup_into(&conn, &folder, config.keep, Utc::now()).await?;
+    for path in &report.removed {
+        debug!("deleted the old backup {}", path);
+    }
+
+    Ok(report)
+}
+
+// the schema version of a backup, once it passed the integrity check. an
+// older version is migrated on the next start, a newer one is refused
+pub async fn validate_backup(path: &Path) -> Result<i64, BackupError> {
+    if !path.is_file() {
+        return Err(io_error("open the backup", path)(std::io::Error::from(
+            std::io::ErrorKind::NotFound,
+        )));
+    }
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    let result = sqlx::query_scalar::<_, String>("PRAGMA quick_check")
+        .fetch_one(&mut conn)
+        .await?;
+    if result != "ok" {
+        return Err(BackupError::Corrupt {
+            path: path.to_path_buf(),
+            result,
+        });
+    }
+
+    let has_schema_version = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+    )
+    .fetch_one(&mut conn)
+    .await?
+        > 0;
+    let backup_version = match has_schema_version {
+        true => fetch_schema_version(&mut conn).await?,
+        false => 0,
+    };
+    conn.close().await?;
+
+    let code_version = get_latest_schema_version();
+    if backup_version == 0 {
+        return Err(BackupError::NotRemonDatabase(path.to_path_buf()));
+    }
+    if backup_version > code_version {
+        return Err(BackupError::NewerSchema {
+            backup_version,
+            code_version,
+        });
+    }
+
+    Ok(backup_version)
+}
+
+// SQLITE_BUSY and SQLITE_LOCKED, with their extended codes
+fn is_locked_error(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|e| e.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, 5 | 6))
+}
+
+// an exclusive lock on the database, held while it's swapped, so a running
+// server, or anything else that has it open, makes the restore fail instead
+// of writing to the replaced file
+async fn lock_database(db_path: &Path) -> Result<SqliteConnection, BackupError> {
+    let in_use = |e: sqlx::Error| match is_locked_error(&e) {
+        true => BackupError::DatabaseInUse(db_path.to_path_buf()),
+        false => BackupError::Sql(e),
+    };
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(db_path)
+        // without the shared memory of the wal, so the lock covers the other processes too
+        .locking_mode(SqliteLockingMode::Exclusive)
+        .busy_timeout(Duration::ZERO)
+        .connect()
+        .await
+        .map_err(in_use)?;
+
+    sqlx::query("BEGIN EXCLUSIVE")
+        .execute(&mut conn)
+        .await
+        .map_err(in_use)?;
+
+    Ok(conn)
+}
+
+// swaps the database for the backup. the backup is copied next to the
+// database first, so the swap is a rename, and the replaced database is kept
+async fn restore_into(
+    backup_path: &Path,
+    db_path: &Path,
+    now: DateTime<Utc>,
+) -> Result<RestoreReport, BackupError> {
+    let schema_version = validate_backup(backup_path).await?;
+
+    let lock = match db_path.exists() {
+        true => Some(lock_database(db_path).await?),
+        false => None,
+    };
+
+    let with_suffix = |suffix: &str| {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+
+    let restoring_path = with_suffix(".restoring");
+    std::fs::copy(backup_path, &restoring_path)
+        .map_err(io_error("copy the backup to", &restoring_path))?;
+    std::fs::File::open(&restoring_path)
+        .and_then(|file| file.sync_all())
+        .map_err(io_error("sync the backup copy", &restoring_path))?;
+
+    let mut previous_path = None;
+    if db_path.exists() {
+        let previous = format!(".before-restore-{}", now.format("%Y%m%d-%H%M%S"));
+
+        // the wal is moved with it, it can hold the last commits
+        for suffix in ["", "-wal"] {
+            let path = with_suffix(suffix);
+            if path.exists() {
+                let to = with_suffix(&format!("{}{}", previous, suffix));
+                std::fs::rename(&path, &to).map_err(io_error("move the database to", &to))?;
+            }
+        }
+
+        previous_path = Some(with_suffix(&previous).to_string_lossy().to_string());
+    }
+
+    let shm_path = with_suffix("-shm");
+    if shm_path.exists() {
+        std::fs::remove_file(&shm_path).map_err(io_error("delete", &shm_path))?;
+    }
+
+    std::fs::rename(&restoring_path, db_path)
+        .map_err(io_error("restore the database to", db_path))?;
+
+    // the lock is on the replaced file, which was moved aside
+    if let Some(lock) = lock {
+        lock.close().await?;
+    }
+
+    Ok(RestoreReport {
+        path: db_path.to_string_lossy().to_string(),
+        schema_version,
+        previous_path,
+    })
+}
+
+// replaces the database with a backup. the server has to be stopped, it's
+// run before the database is opened
+pub async fn restore_db(backup_path: &Path) -> Result<RestoreReport, BackupError> {
+    let db_path = resolve_db_path()?;
+
+    let report = restore_into(backup_path, &db_path, Utc::now()).await?;
+    info!(
+        "restored {} to {}",
+        backup_path.display(),
+        db_path.display()
+    );
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::migrations::run_migrations;
+    use chrono::TimeZone;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn get_temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("remon-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+
+        folder
+    }
+
+    async fn count_rows(path: &Path, table_name: &str) -> i64 {
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path)
+            .read_only(true)
+            .connect()
+            .await
+            .unwrap();
+
+        sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table_name))
+            .fetch_one(&mut conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore_test() {
+        let folder = get_temp_folder("backup");
+        let backups = folder.join("backups");
+
+        let options = SqliteConnectOptions::new()
+            .filename(folder.join("source.sqlite3"))
+            .create_if_missing(true);
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        run_migrations(&conn).await.unwrap();
+        sqlx::query(
+            "INSERT INTO configs (id, device_id, cpu_threshold, mem_threshold, disk_threshold, fcm_token, updated_at)
+            VALUES (1, 'phone', 90, 90, 90, 'token', 0)",
+        )
+        .execute(&conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO notification_logs (notification_type, device_id, fcm_token, title, body, sent_at)
+            VALUES (0, 'phone', 'token', 'title', 'body', 0)",
+        )
+        .execute(&conn)
+        .await
+        .unwrap();
+
+        // 3 backups a minute apart, the oldest is rotated out
+        let mut reports = vec![];
+        for minute in 0..3 {
+            let now = Utc.timestamp_opt(1_700_000_000 + minute * 60, 0).unwrap();
+            reports.push(backup_into(&conn, &backups, 2, now).await.unwrap());
+        }
+        assert!(reports[0].removed.is_empty());
+        assert_eq!(reports[2].removed, [reports[0].path.clone()]);
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 2);
+
+        let backup_path = PathBuf::from(&reports[2].path);
+        assert_eq!(reports[2].schema_version, get_latest_schema_version());
+        assert_eq!(count_rows(&backup_path, "configs").await, 1);
+        assert_eq!(count_rows(&backup_path, "notification_logs").await, 1);
+
+        // the database can't be replaced while it's open, like by a running server
+        let db_path = folder.join("monitor.sqlite3");
+        let options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .create_if_missing(true)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+        let live_conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE old (id INTEGER)")
+            .execute(&live_conn)
+            .await
+            .unwrap();
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert!(matches!(
+            restore_into(&backup_path, &db_path, now).await,
+            Err(BackupError::DatabaseInUse(_))
+        ));
+        live_conn.close().await;
+
+        // the backup replaces the database, which is kept aside
+        let report = restore_into(&backup_path, &db_path, now).await.unwrap();
+
+        assert_eq!(report.schema_version, get_latest_schema_version());
+        let previous_path = report.previous_path.unwrap();
+        assert_eq!(count_rows(Path::new(&previous_path), "old").await, 0);
+        assert_eq!(count_rows(&db_path, "configs").await, 1);
+
+        // only remon databases that this build can migrate are restored
+        assert!(matches!(
+            restore_into(Path::new(&previous_path), &db_path, now).await,
+            Err(BackupError::NotRemonDatabase(_))
+        ));
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'from the future', 0)")
+            .bind(get_latest_schema_version() + 1)
+            .execute(&conn)
+            .await
+            .unwrap();
+        let later = Utc.timestamp_opt(1_700_000_000 + 10 * 60, 0).unwrap();
+        let newer = backup_into(&conn, &backups, 2, later).await.unwrap();
+        assert!(matches!(
+            validate_backup(Path::new(&newer.path)).await,
+            Err(BackupError::NewerSchema { .. })
+        ));
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
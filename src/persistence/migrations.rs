@@ -0,0 +1,678 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use chrono::Utc;
+use log::info;
+use sqlx::{Executor, SqliteConnection};
+
+use super::SQLConnection;
+
+const SCHEMA_VERSION_TABLE_NAME: &str = "schema_version";
+
+type MigrationFuture<'c> = Pin<Box<dyn Future<Output = Result<(), sqlx::Error>> + Send + 'c>>;
+
+// a numbered change to the schema. the ones newer than the version of the
+// database are run in order on startup. a migration is never edited once it's
+// released, a change to the schema is always a new migration at the end, so
+// they don't call the code that reads and writes the tables today
+struct Migration {
+    version: i64,
+    description: &'static str,
+    run: for<'c> fn(&'c mut SqliteConnection) -> MigrationFuture<'c>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline",
+        run: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "status rollup tables",
+        run: status_rollups,
+    },
+    Migration {
+        version: 3,
+        description: "time and frame indexes",
+        run: time_and_frame_indexes,
+    },
+    Migration {
+        version: 4,
+        description: "boot events pruning index",
+        run: boot_events_pruning_index,
+    },
+    Migration {
+        version: 5,
+        description: "disk ids by name and mount point",
+        run: disk_ids_by_name_and_mount_point,
+    },
+    Migration {
+        version: 6,
+        description: "per core cpu rollups",
+        run: per_core_cpu_rollups,
+    },
+];
+
+// the schema from before the migrations. the tables are only created where
+// they're missing and the columns added since are added to the existing tables,
+// so it also brings the databases of the older versions up to date
+const BASELINE_SQL: &str = include_str!("migrations/0001_baseline.sql");
+
+// the columns added to the tables before the migrations, which the tables of
+// the older versions don't have yet
+const BASELINE_COLUMNS: &[(&str, &str, &str)] = &[
+    ("configs", "inode_threshold", "REAL"),
+    ("configs", "cpu_pressure_threshold", "REAL"),
+    ("configs", "mem_pressure_threshold", "REAL"),
+    ("configs", "io_pressure_threshold", "REAL"),
+    ("configs", "cgroup_mem_threshold", "REAL"),
+    ("disk_status_frame_single", "inodes_total", "INTEGER"),
+    ("disk_status_frame_single", "inodes_free", "INTEGER"),
+    ("cpu_infos", "valid_from", "INTEGER"),
+    ("cpu_infos", "valid_to", "INTEGER"),
+    ("disk_infos", "valid_from", "INTEGER"),
+    ("disk_infos", "valid_to", "INTEGER"),
+    ("mem_infos", "valid_from", "INTEGER"),
+    ("mem_infos", "valid_to", "INTEGER"),
+    ("component_infos", "valid_from", "INTEGER"),
+    ("component_infos", "valid_to", "INTEGER"),
+];
+
+// the inventory used to be overwritten in place, so the rows of the
+// existing installs are taken as valid since their last check
+const VERSIONED_HARDWARE_TABLES: &[&str] =
+    &["cpu_infos", "disk_infos", "mem_infos", "component_infos"];
+
+// the older versions checked the last check of all the hardware in use every
+// 10 seconds, and kept the rows of the removed hardware as they were
+const PRE_VERSIONING_CHECK_INTERVAL_MILLIS: i64 = 10_000;
+
+// `CREATE TABLE IF NOT EXISTS` leaves the tables of the existing
+// installs untouched, so the columns added later on are added here
+async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table_name: &str,
+    column_name: &str,
+    column_definition: &str,
+) -> Result<(), sqlx::Error> {
+    let columns = sqlx::query_as::<_, (String,)>("SELECT name FROM pragma_table_info(?)")
+        .bind(&table_name)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if columns.iter().any(|c| c.0 == column_name) {
+        return Ok(());
+    }
+
+    let statement = format!(
+        "ALTER TABLE {} ADD COLUMN {} {}",
+        table_name, column_name, column_definition
+    );
+
+    sqlx::query(&statement).execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+fn baseline(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        conn.execute(BASELINE_SQL).await?;
+
+        for (table_name, column_name, column_definition) in BASELINE_COLUMNS {
+            add_column_if_missing(conn, table_name, column_name, column_definition).await?;
+        }
+
+        for table_name in VERSIONED_HARDWARE_TABLES {
+            // the hardware that wasn't in the last check is long gone
+            let statement = format!(
+                "UPDATE {0} SET valid_to = last_check
+                WHERE valid_from IS NULL AND last_check < (SELECT MAX(last_check) FROM {0}) - ?",
+                table_name
+            );
+            sqlx::query(&statement)
+                .bind(&PRE_VERSIONING_CHECK_INTERVAL_MILLIS)
+                .execute(&mut *conn)
+                .await?;
+
+            let statement = format!(
+                "UPDATE {} SET valid_from = last_check WHERE valid_from IS NULL",
+                table_name
+            );
+            sqlx::query(&statement).execute(&mut *conn).await?;
+        }
+
+        Ok(())
+    })
+}
+
+const STATUS_ROLLUPS_SQL: &str = include_str!("migrations/0002_status_rollups.sql");
+
+// the 1m, 15m and 1h buckets of the cpu, mem and disk frames
+fn status_rollups(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        conn.execute(STATUS_ROLLUPS_SQL).await?;
+
+        Ok(())
+    })
+}
+
+// the column every range is read and pruned by, and the frame the rows of a
+// sample are joined to their frame by. the rollups are already indexed by
+// their unique bucket
+const TIME_AND_FRAME_INDEXES: &[(&str, &str)] = &[
+    ("cpu_status_frame", "last_check"),
+    ("cpu_status_frame_core", "frame_id"),
+    ("mem_status_frame", "last_check"),
+    ("mem_status_frame_single", "frame_id"),
+    ("disk_status_frame", "last_check"),
+    ("disk_status_frame_single", "frame_id"),
+    ("disk_status_frame_io", "frame_id"),
+    ("temperature_status_frame", "last_check"),
+    ("temperature_status_frame_single", "frame_id"),
+    ("pressure_status_frame", "last_check"),
+    ("pressure_status_frame_single", "frame_id"),
+    ("cgroup_status_frame", "last_check"),
+    ("cgroup_status_frame_single", "frame_id"),
+    ("custom_metric", "last_check"),
+    ("endpoint_checks", "last_check"),
+    ("certificate_checks", "last_check"),
+    ("log_matches", "last_check"),
+    ("log_match_counts", "last_check"),
+    ("process_watch_events", "last_check"),
+    ("systemd_unit_events", "last_check"),
+    ("hardware_change_events", "last_check"),
+    ("boot_events", "boot_time"),
+    ("notification_logs", "sent_at"),
+];
+
+fn time_and_frame_indexes(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for (table_name, column) in TIME_AND_FRAME_INDEXES {
+            let statement = format!(
+                "CREATE INDEX IF NOT EXISTS {0}_{1}_idx ON {0} ({1})",
+                table_name, column
+            );
+
+            sqlx::query(&statement).execute(&mut *conn).await?;
+        }
+
+        Ok(())
+    })
+}
+
+// the boot events are read by their boot time, but pruned by their last check
+fn boot_events_pruning_index(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS boot_events_last_check_idx ON boot_events (last_check)",
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    })
+}
+
+// the tables the disk id is stored in, and its column
+const DISK_ID_COLUMNS: &[(&str, &str)] = &[
+    ("disk_status_frame_single", "disk_id"),
+    ("disk_status_frame_io", "disk_id"),
+    ("disk_status_rollup_1m", "entity_id"),
+    ("disk_status_rollup_15m", "entity_id"),
+    ("disk_status_rollup_1h", "entity_id"),
+];
+
+// the disk id used to be the hash of every field of the disk, so a resized
+// disk came back as a new one. it's the hash of the name and the mount point
+// now, the stored ids are rehashed so the disks don't show up as replaced
+fn disk_ids_by_name_and_mount_point(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        let disks = sqlx::query_as::<_, (i64, String, String, String)>(
+            "SELECT id, disk_id, name, mount_point FROM disk_infos",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        // the previous versions of a disk all had different ids
+        let mut disk_ids: HashMap<String, String> = HashMap::new();
+        for (id, disk_id, name, mount_point) in disks {
+            let new_disk_id = blake3::hash(format!("{}\0{}", name, mount_point).as_bytes());
+            let new_disk_id = new_disk_id.to_string();
+
+            sqlx::query("UPDATE disk_infos SET disk_id = ? WHERE id = ?")
+                .bind(&new_disk_id)
+                .bind(&id)
+                .execute(&mut *conn)
+                .await?;
+
+            disk_ids.insert(disk_id, new_disk_id);
+        }
+
+        for (disk_id, new_disk_id) in disk_ids.iter() {
+            for (table_name, column) in DISK_ID_COLUMNS {
+                // a rollup bucket both versions were in keeps only one of them
+                let statement = format!(
+                    "UPDATE OR IGNORE {0} SET {1} = ? WHERE {1} = ?",
+                    table_name, column
+                );
+                sqlx::query(&statement)
+                    .bind(new_disk_id)
+                    .bind(disk_id)
+                    .execute(&mut *conn)
+                    .await?;
+
+                let statement = format!("DELETE FROM {} WHERE {} = ?", table_name, column);
+                sqlx::query(&statement)
+                    .bind(disk_id)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+
+        // only the newest version of a disk is in use
+        sqlx::query(
+            "UPDATE disk_infos SET valid_to = (
+                SELECT MIN(n.valid_from) FROM disk_infos n
+                WHERE n.disk_id = disk_infos.disk_id AND n.valid_to IS NULL AND n.id > disk_infos.id
+            )
+            WHERE valid_to IS NULL AND EXISTS (
+                SELECT 1 FROM disk_infos n
+                WHERE n.disk_id = disk_infos.disk_id AND n.valid_to IS NULL AND n.id > disk_infos.id
+            )",
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    })
+}
+
+const CPU_ROLLUP_TABLES: &[&str] = &[
+    "cpu_status_rollup_1m",
+    "cpu_status_rollup_15m",
+    "cpu_status_rollup_1h",
+];
+
+// the cpu buckets used to merge every core of a cpu. they're rolled up
+// again per core from the frames that are still kept
+fn per_core_cpu_rollups(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for table_name in CPU_ROLLUP_TABLES {
+            let statement = format!("DELETE FROM {}", table_name);
+            sqlx::query(&statement).execute(&mut *conn).await?;
+
+            sqlx::query("DELETE FROM status_rollup_state WHERE table_name = ?")
+                .bind(table_name)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+    #[error(
+        "the database is at schema version {db_version}, but this build only knows up to \
+         version {code_version}, update remon before starting it on this database"
+    )]
+    NewerSchema { db_version: i64, code_version: i64 },
+}
+
+pub fn get_latest_schema_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or_default()
+}
+
+async fn create_schema_version_table(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+        version INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        applied_at INTEGER NOT NULL
+    )",
+        SCHEMA_VERSION_TABLE_NAME
+    );
+
+    sqlx::query(&statement).execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+// 0 for a database without any migrations
+pub async fn fetch_schema_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    let statement = format!(
+        "SELECT COALESCE(MAX(version), 0) FROM {}",
+        SCHEMA_VERSION_TABLE_NAME
+    );
+
+    let version = sqlx::query_scalar::<_, i64>(&statement)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(version)
+}
+
+async fn insert_schema_version(
+    conn: &mut SqliteConnection,
+    migration: &Migration,
+) -> Result<(), sqlx::Error> {
+    let statement = format!(
+        "INSERT INTO {} (version, description, applied_at) VALUES (?, ?, ?)",
+        SCHEMA_VERSION_TABLE_NAME
+    );
+
+    sqlx::query(&statement)
+        .bind(&migration.version)
+        .bind(&migration.description)
+        .bind(&Utc::now().timestamp_millis())
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// brings the database to the latest schema version, in a single transaction,
+// so a failed migration leaves the database as it was. returns the version
+pub async fn run_migrations(conn: &SQLConnection) -> Result<i64, MigrationError> {
+    let mut tx = conn.begin().await?;
+
+    create_schema_version_table(&mut tx).await?;
+
+    let db_version = fetch_schema_version(&mut tx).await?;
+    let code_version = get_latest_schema_version();
+    if db_version > code_version {
+        return Err(MigrationError::NewerSchema {
+            db_version,
+            code_version,
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > db_version) {
+        info!(
+            "running migration {}: {}",
+            migration.version, migration.description
+        );
+
+        (migration.run)(&mut tx).await?;
+        insert_schema_version(&mut tx, migration).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(code_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    const BASELINE_DB: &str = include_str!("../../tests/fixtures/db/baseline.sql");
+
+    async fn get_baseline_db() -> SQLConnection {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        conn.execute(BASELINE_DB).await.unwrap();
+
+        conn
+    }
+
+    async fn has_column(conn: &SQLConnection, table_name: &str, column_name: &str) -> bool {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+            .bind(&table_name)
+            .bind(&column_name)
+            .fetch_one(conn)
+            .await
+            .unwrap()
+            == 1
+    }
+
+    async fn count_rows(conn: &SQLConnection, table_name: &str) -> i64 {
+        sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table_name))
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn upgrade_baseline_db_test() {
+        let conn = get_baseline_db().await;
+        for (table_name, column_name, _) in BASELINE_COLUMNS {
+            assert!(!has_column(&conn, table_name, column_name).await);
+        }
+
+        let version = run_migrations(&conn).await.unwrap();
+        assert_eq!(version, get_latest_schema_version());
+        assert_eq!(
+            count_rows(&conn, SCHEMA_VERSION_TABLE_NAME).await,
+            MIGRATIONS.len() as i64
+        );
+
+        // the existing rows are kept
+        assert_eq!(count_rows(&conn, "cpu_status_frame_core").await, 4);
+        assert_eq!(count_rows(&conn, "mem_status_frame_single").await, 1);
+        assert_eq!(count_rows(&conn, "notification_logs").await, 1);
+
+        for (table_name, column_name, _) in BASELINE_COLUMNS {
+            assert!(
+                has_column(&conn, table_name, column_name).await,
+                "{}.{}",
+                table_name,
+                column_name
+            );
+        }
+
+        // the inventory is valid since its last check, the unplugged disk
+        // was closed, and the root disk keeps its samples under its new id
+        let cpu_valid_from = sqlx::query_scalar::<_, i64>("SELECT valid_from FROM cpu_infos")
+            .fetch_one(&conn)
+            .await
+            .unwrap();
+        assert_eq!(cpu_valid_from, 1700000010000);
+
+        let root_disk_id = blake3::hash("sda1\0/".as_bytes()).to_string();
+        let disks = sqlx::query_as::<_, (String, String, i64, Option<i64>)>(
+            "SELECT disk_id, name, valid_from, valid_to FROM disk_infos ORDER BY id",
+        )
+        .fetch_all(&conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            disks[0],
+            (
+                root_disk_id.to_string(),
+                "sda1".to_string(),
+                1700000010000,
+                None
+            )
+        );
+        assert_eq!(disks[1].1, "sdb1");
+        assert_eq!(disks[1].3, Some(1699913600000));
+
+        let frame_disk_id =
+            sqlx::query_scalar::<_, String>("SELECT disk_id FROM disk_status_frame_single")
+                .fetch_one(&conn)
+                .await
+                .unwrap();
+        assert_eq!(frame_disk_id, root_disk_id);
+
+        // nothing is run again on the next start
+        run_migrations(&conn).await.unwrap();
+        assert_eq!(
+            count_rows(&conn, SCHEMA_VERSION_TABLE_NAME).await,
+            MIGRATIONS.len() as i64
+        );
+    }
+
+    #[tokio::test]
+    async fn upgrade_older_tables_test() {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        // the tables as they were before the columns added to them since
+        conn.execute(
+            "CREATE TABLE configs (
+                id INTEGER PRIMARY KEY NOT NULL,
+                device_id TEXT NOT NULL,
+                cpu_threshold REAL NOT NULL,
+                mem_threshold REAL NOT NULL,
+                disk_threshold REAL NOT NULL,
+                fcm_token TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE cpu_infos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cpu_id TEXT NOT NULL,
+                core_count INTEGER NOT NULL,
+                vendor_id TEXT NOT NULL,
+                brand TEXT NOT NULL,
+                last_check INTEGER NOT NULL
+            );
+            INSERT INTO cpu_infos (cpu_id, core_count, vendor_id, brand, last_check)
+            VALUES ('old cpu', 2, 'vendor', 'brand', 1000), ('cpu', 4, 'vendor', 'brand', 100000);",
+        )
+        .await
+        .unwrap();
+
+        run_migrations(&conn).await.unwrap();
+
+        for (table_name, column_name, _) in BASELINE_COLUMNS {
+            assert!(
+                has_column(&conn, table_name, column_name).await,
+                "{}.{}",
+                table_name,
+                column_name
+            );
+        }
+
+        // the existing inventory is valid since its last check, and the
+        // hardware that was gone before the upgrade isn't in use anymore
+        let cpus = sqlx::query_as::<_, (String, i64, Option<i64>)>(
+            "SELECT cpu_id, valid_from, valid_to FROM cpu_infos ORDER BY id",
+        )
+        .fetch_all(&conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            cpus,
+            vec![
+                ("old cpu".to_string(), 1000, Some(1000)),
+                ("cpu".to_string(), 100000, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn disk_ids_by_name_and_mount_point_test() {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        // a disk that was resized, with an id for each of its sizes
+        conn.execute(
+            "CREATE TABLE disk_infos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                disk_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                fs_type TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                is_removable INTEGER NOT NULL,
+                mount_point TEXT NOT NULL,
+                total_space INTEGER NOT NULL,
+                last_check INTEGER NOT NULL
+            );
+            INSERT INTO disk_infos (disk_id, name, fs_type, kind, is_removable, mount_point, total_space, last_check)
+            VALUES ('small', 'sda1', 'ext4', 'SSD', 0, '/', 100, 1000),
+                ('big', 'sda1', 'ext4', 'SSD', 0, '/', 200, 100000);",
+        )
+        .await
+        .unwrap();
+
+        run_migrations(&conn).await.unwrap();
+
+        let disk_id = blake3::hash("sda1\0/".as_bytes()).to_string();
+        let disks = sqlx::query_as::<_, (String, i64, Option<i64>)>(
+            "SELECT disk_id, total_space, valid_to FROM disk_infos ORDER BY id",
+        )
+        .fetch_all(&conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            disks,
+            vec![
+                (disk_id.to_string(), 100, Some(1000)),
+                (disk_id.to_string(), 200, None),
+            ]
+        );
+
+        // the samples and the buckets written before are rehashed too
+        conn.execute(
+            "UPDATE disk_infos SET disk_id = CASE total_space WHEN 100 THEN 'small' ELSE 'big' END;
+            INSERT INTO disk_status_frame (id, last_check) VALUES (1, 1000), (2, 100000);
+            INSERT INTO disk_status_frame_single (frame_id, available, disk_id)
+            VALUES (1, 50, 'small'), (2, 150, 'big');
+            INSERT INTO disk_status_rollup_1h (bucket_start, entity_id, min_value, max_value, avg_value, p95_value, samples)
+            VALUES (0, 'small', 1, 1, 1, 1, 1), (0, 'big', 2, 2, 2, 2, 1);",
+        )
+        .await
+        .unwrap();
+
+        let mut tx = conn.begin().await.unwrap();
+        disk_ids_by_name_and_mount_point(&mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let frame_disk_ids = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT disk_id FROM disk_status_frame_single",
+        )
+        .fetch_all(&conn)
+        .await
+        .unwrap();
+        assert_eq!(frame_disk_ids, vec![disk_id.to_string()]);
+
+        let rollup_disk_ids =
+            sqlx::query_scalar::<_, String>("SELECT entity_id FROM disk_status_rollup_1h")
+                .fetch_all(&conn)
+                .await
+                .unwrap();
+        assert_eq!(rollup_disk_ids, vec![disk_id]);
+    }
+
+    #[tokio::test]
+    async fn newer_schema_test() {
+        let conn = get_baseline_db().await;
+        run_migrations(&conn).await.unwrap();
+
+        let newer_version = get_latest_schema_version() + 1;
+        sqlx::query(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+        )
+        .bind(&newer_version)
+        .bind(&"from the future")
+        .bind(&0)
+        .execute(&conn)
+        .await
+        .unwrap();
+
+        match run_migrations(&conn).await {
+            Err(MigrationError::NewerSchema {
+                db_version,
+                code_version,
+            }) => {
+                assert_eq!(db_version, newer_version);
+                assert_eq!(code_version, get_latest_schema_version());
+            }
+            res => panic!("expected a newer schema error, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+-- the schema from before the migrations. the tables are only created where
+-- they're missing, the columns added to them since are in BASELINE_COLUMNS.
+-- never edit it, a change to the schema is always a new migration
+
+CREATE TABLE IF NOT EXISTS configs (
+    id INTEGER PRIMARY KEY NOT NULL,
+    device_id TEXT NOT NULL,
+    cpu_threshold REAL NOT NULL,
+    mem_threshold REAL NOT NULL,
+    disk_threshold REAL NOT NULL,
+    inode_threshold REAL,
+    cpu_pressure_threshold REAL,
+    mem_pressure_threshold REAL,
+    io_pressure_threshold REAL,
+    cgroup_mem_threshold REAL,
+    fcm_token TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cpu_infos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cpu_id TEXT NOT NULL,
+    core_count INTEGER NOT NULL,
+    vendor_id TEXT NOT NULL,
+    brand TEXT NOT NULL,
+    last_check INTEGER NOT NULL,
+    valid_from INTEGER NOT NULL,
+    valid_to INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS disk_infos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    disk_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    fs_type TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    is_removable INTEGER NOT NULL,
+    mount_point TEXT NOT NULL,
+    total_space INTEGER NOT NULL,
+    last_check INTEGER NOT NULL,
+    valid_from INTEGER NOT NULL,
+    valid_to INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS mem_infos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    mem_id TEXT NOT NULL,
+    total_space INTEGER NOT NULL,
+    last_check INTEGER NOT NULL,
+    valid_from INTEGER NOT NULL,
+    valid_to INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS component_infos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    component_id TEXT NOT NULL,
+    label TEXT NOT NULL,
+    critical REAL,
+    last_check INTEGER NOT NULL,
+    valid_from INTEGER NOT NULL,
+    valid_to INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS hardware_change_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    hardware_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cpu_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cpu_status_frame_core (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cpu_id TEXT NOT NULL,
+    freq INTEGER NOT NULL,
+    usage INTEGER NOT NULL,
+    frame_id INTEGER NOT NULL,
+    FOREIGN KEY (frame_id)
+        REFERENCES cpu_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_frame_single (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    available INTEGER NOT NULL,
+    disk_id TEXT NOT NULL,
+    inodes_total INTEGER,
+    inodes_free INTEGER,
+    FOREIGN KEY (frame_id)
+        REFERENCES disk_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_frame_io (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    device_name TEXT NOT NULL,
+    disk_id TEXT,
+    read_bytes_per_sec REAL NOT NULL,
+    write_bytes_per_sec REAL NOT NULL,
+    read_iops REAL NOT NULL,
+    write_iops REAL NOT NULL,
+    busy_percent REAL NOT NULL,
+    FOREIGN KEY (frame_id)
+        REFERENCES disk_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS mem_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS mem_status_frame_single (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    mem_id TEXT NOT NULL,
+    available INTEGER NOT NULL,
+    frame_id INTEGER NOT NULL,
+    FOREIGN KEY (frame_id)
+        REFERENCES mem_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS temperature_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS temperature_status_frame_single (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    component_id TEXT NOT NULL,
+    temperature REAL NOT NULL,
+    max REAL,
+    critical REAL,
+    FOREIGN KEY (frame_id)
+        REFERENCES temperature_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS temperature_thresholds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_id TEXT NOT NULL,
+    component_id TEXT NOT NULL,
+    threshold REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pressure_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pressure_status_frame_single (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    resource TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    avg10 REAL NOT NULL,
+    avg60 REAL NOT NULL,
+    avg300 REAL NOT NULL,
+    total INTEGER NOT NULL,
+    FOREIGN KEY (frame_id)
+        REFERENCES pressure_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS cgroup_status_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cgroup_status_frame_single (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    cpu_usage_percent REAL,
+    memory_current INTEGER,
+    memory_max INTEGER,
+    io_read_bytes_per_sec REAL,
+    io_write_bytes_per_sec REAL,
+    pids_current INTEGER,
+    pids_max INTEGER,
+    FOREIGN KEY (frame_id)
+        REFERENCES cgroup_status_frame (id)
+);
+
+CREATE TABLE IF NOT EXISTS process_watch_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    instance_count INTEGER NOT NULL,
+    min_instances INTEGER NOT NULL,
+    is_up INTEGER NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS systemd_unit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    unit TEXT NOT NULL,
+    active_state TEXT NOT NULL,
+    sub_state TEXT NOT NULL,
+    n_restarts INTEGER NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS custom_metric (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    probe TEXT NOT NULL,
+    key TEXT,
+    value REAL,
+    status TEXT NOT NULL,
+    message TEXT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS custom_metric_thresholds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_id TEXT NOT NULL,
+    probe TEXT NOT NULL,
+    key TEXT NOT NULL,
+    threshold REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS endpoint_checks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    is_up INTEGER NOT NULL,
+    latency_ms REAL,
+    status_code INTEGER,
+    message TEXT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS log_matches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    watch TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    line TEXT NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS log_match_counts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    watch TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS certificate_checks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL,
+    status TEXT NOT NULL,
+    subject TEXT,
+    issuer TEXT,
+    not_after INTEGER,
+    days_to_expiry REAL,
+    message TEXT,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS boot_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    boot_id TEXT,
+    boot_time INTEGER NOT NULL,
+    is_reboot INTEGER NOT NULL,
+    last_sample INTEGER,
+    downtime_secs INTEGER,
+    is_unclean INTEGER NOT NULL,
+    last_check INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notification_logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    notification_type INTEGER NOT NULL,
+    device_id TEXT NOT NULL,
+    fcm_token TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    sent_at INTEGER NOT NULL
+);
@@ -0,0 +1,114 @@
+-- the 1m, 15m and 1h buckets of the cpu, mem and disk frames
+
+CREATE TABLE IF NOT EXISTS cpu_status_rollup_1m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS cpu_status_rollup_15m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS cpu_status_rollup_1h (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS mem_status_rollup_1m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS mem_status_rollup_15m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS mem_status_rollup_1h (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_rollup_1m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_rollup_15m (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS disk_status_rollup_1h (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket_start INTEGER NOT NULL,
+    entity_id TEXT NOT NULL,
+    min_value REAL NOT NULL,
+    max_value REAL NOT NULL,
+    avg_value REAL NOT NULL,
+    p95_value REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    UNIQUE (bucket_start, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS status_rollup_state (
+    table_name TEXT PRIMARY KEY NOT NULL,
+    rolled_up_to INTEGER NOT NULL
+);
@@ -1,10 +1,23 @@
-use super::{get_default_sql_connection, SQLConnection};
+use super::get_default_sql_connection;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone)]
+#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum NotificationType {
     StatusLimitsExceeding,
+    ProcessDown,
+    ProcessUp,
+    SystemdUnitFailed,
+    SystemdUnitRestarted,
+    SystemdUnitRecovered,
+    EndpointDown,
+    EndpointUp,
+    LogMatch,
+    CertificateExpiring,
+    CertificateFileProblem,
+    DiskRemoved,
+    HardwareChanged,
+    RebootDetected,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -67,24 +80,3 @@ pub async fn fetch_single_latest_for_device_id_and_type(
 
     Ok(info)
 }
-
-pub(super) async fn create_notification_logs_table(
-    conn: &SQLConnection,
-) -> Result<(), sqlx::Error> {
-    let statement = format!(
-        "CREATE TABLE IF NOT EXISTS {} (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        notification_type INTEGER NOT NULL,
-        device_id TEXT NOT NULL,
-        fcm_token TEXT NOT NULL,
-        title TEXT NOT NULL,
-        body TEXT NOT NULL,
-        sent_at INTEGER NOT NULL
-    )",
-        NOTIFICATION_LOGS_TABLE_NAME
-    );
-
-    sqlx::query(&statement).execute(conn).await?;
-
-    Ok(())
-}
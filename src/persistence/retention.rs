@@ -0,0 +1,591 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use log::{debug, info};
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqliteExecutor};
+use tokio::sync::Mutex;
+
+use super::{get_default_sql_connection, SQLConnection};
+use crate::config::RetentionConfig;
+
+pub const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+// sqlite's value for `PRAGMA auto_vacuum = INCREMENTAL`
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+// a table whose rows expire, with the tables whose rows reference it
+struct RetentionTarget {
+    table_name: &'static str,
+    time_column: &'static str,
+    // deleted with the rows they reference, by their frame_id
+    child_table_names: &'static [&'static str],
+    // None keeps the rows forever, unless a retention is configured for the table
+    default_days: Option<i64>,
+    // the coarsest rollup of the frames, they're kept until it rolled them up,
+    // even when they're older than their retention
+    rollup_table_name: Option<&'static str>,
+}
+
+const RETENTION_TARGETS: &[RetentionTarget] = &[
+    RetentionTarget {
+        table_name: "cpu_status_frame",
+        time_column: "last_check",
+        child_table_names: &["cpu_status_frame_core"],
+        default_days: Some(7),
+        rollup_table_name: Some("cpu_status_rollup_1h"),
+    },
+    RetentionTarget {
+        table_name: "mem_status_frame",
+        time_column: "last_check",
+        child_table_names: &["mem_status_frame_single"],
+        default_days: Some(7),
+        rollup_table_name: Some("mem_status_rollup_1h"),
+    },
+    RetentionTarget {
+        table_name: "disk_status_frame",
+        time_column: "last_check",
+        child_table_names: &["disk_status_frame_single", "disk_status_frame_io"],
+        default_days: Some(7),
+        rollup_table_name: Some("disk_status_rollup_1h"),
+    },
+    RetentionTarget {
+        table_name: "temperature_status_frame",
+        time_column: "last_check",
+        child_table_names: &["temperature_status_frame_single"],
+        default_days: Some(7),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "pressure_status_frame",
+        time_column: "last_check",
+        child_table_names: &["pressure_status_frame_single"],
+        default_days: Some(7),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "cgroup_status_frame",
+        time_column: "last_check",
+        child_table_names: &["cgroup_status_frame_single"],
+        default_days: Some(7),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "cpu_status_rollup_1m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "cpu_status_rollup_15m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(365),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "cpu_status_rollup_1h",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: None,
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "mem_status_rollup_1m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "mem_status_rollup_15m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(365),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "mem_status_rollup_1h",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: None,
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "disk_status_rollup_1m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "disk_status_rollup_15m",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: Some(365),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "disk_status_rollup_1h",
+        time_column: "bucket_start",
+        child_table_names: &[],
+        default_days: None,
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "custom_metric",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(7),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "endpoint_checks",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "certificate_checks",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "log_matches",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "log_match_counts",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(30),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "process_watch_events",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(90),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "systemd_unit_events",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(90),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "hardware_change_events",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: Some(90),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "notification_logs",
+        time_column: "sent_at",
+        child_table_names: &[],
+        default_days: Some(90),
+        rollup_table_name: None,
+    },
+    RetentionTarget {
+        table_name: "boot_events",
+        time_column: "last_check",
+        child_table_names: &[],
+        default_days: None,
+        rollup_table_name: None,
+    },
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PrunedTable {
+    pub table_name: String,
+    pub retention_days: i64,
+    // including the rows of its child tables
+    pub deleted_rows: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PruneReport {
+    pub tables: Vec<PrunedTable>,
+    pub size_before: i64,
+    pub size_after: i64,
+    // the bytes the database file shrank by
+    pub reclaimed_bytes: i64,
+    pub duration_ms: i64,
+    pub pruned_at: i64,
+}
+
+// None when the rows of the table are kept forever
+pub fn get_table_retention_days(config: &RetentionConfig, table_name: &str) -> Option<i64> {
+    if !config.enabled {
+        return None;
+    }
+
+    let default_days = RETENTION_TARGETS
+        .iter()
+        .find(|t| t.table_name == table_name)
+        .and_then(|t| t.default_days);
+
+    config.get_retention_days(table_name, default_days)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PruneError {
+    #[error("retention is disabled, set `retention.enabled` to prune the expired rows")]
+    Disabled,
+    #[error("database error: {0}")]
+    Sql(#[from] sqlx::Error),
+}
+
+// the background task and the admin endpoint prune the same tables
+static PRUNE_LOCK: Mutex<()> = Mutex::const_new(());
+
+async fn get_db_size<'c>(conn: impl SqliteExecutor<'c>) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+    )
+    .fetch_one(conn)
+    .await
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VacuumReport {
+    pub size_before: i64,
+    pub size_after: i64,
+    pub duration_ms: i64,
+}
+
+// the freed pages are only given back to the file system with
+// `auto_vacuum = INCREMENTAL`, which an existing database only switches to
+// on a full VACUUM. that rewrites the whole file and holds the write lock
+// while it runs, so it's only done by `remon-server vacuum`
+pub async fn is_incremental_vacuum_enabled(
+    conn: &mut SqliteConnection,
+) -> Result<bool, sqlx::Error> {
+    let auto_vacuum = sqlx::query_scalar::<_, i64>("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(auto_vacuum == AUTO_VACUUM_INCREMENTAL)
+}
+
+// compacts the database with a full VACUUM, and switches it to incremental vacuum
+pub async fn vacuum(conn: &mut SqliteConnection) -> Result<VacuumReport, sqlx::Error> {
+    let started = Instant::now();
+    let size_before = get_db_size(&mut *conn).await?;
+
+    if is_incremental_vacuum_enabled(conn).await? {
+        info!("vacuuming the database, {} bytes", size_before);
+    } else {
+        info!(
+            "switching the database, {} bytes, to incremental vacuum, this rewrites the whole file",
+            size_before
+        );
+
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query("VACUUM").execute(&mut *conn).await?;
+
+    let size_after = get_db_size(&mut *conn).await?;
+
+    Ok(VacuumReport {
+        size_before,
+        size_after,
+        duration_ms: started.elapsed().as_millis() as i64,
+    })
+}
+
+// vacuums the database the server uses, from the cli
+pub async fn vacuum_db() -> Result<VacuumReport, sqlx::Error> {
+    let conn = get_default_sql_connection().await?;
+    let _guard = PRUNE_LOCK.lock().await;
+
+    vacuum(&mut *conn.acquire().await?).await
+}
+
+// the end of the last bucket the rollup has, None before its first run
+async fn fetch_rolled_up_to(
+    conn: &SQLConnection,
+    rollup_table_name: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT rolled_up_to FROM status_rollup_state WHERE table_name = ?",
+    )
+    .bind(&rollup_table_name)
+    .fetch_optional(conn)
+    .await
+}
+
+// deletes up to `batch_size` expired rows of the table, and the rows of its
+// child tables, in a single transaction. returns the rows deleted from the
+// table, and the rows deleted in total
+async fn delete_expired_batch(
+    conn: &SQLConnection,
+    target: &RetentionTarget,
+    cutoff: i64,
+    batch_size: i64,
+) -> Result<(i64, i64), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+
+    let max_id = sqlx::query_scalar::<_, Option<i64>>(&format!(
+        "SELECT MAX(id) FROM (SELECT id FROM {} WHERE {} < ? ORDER BY id LIMIT ?)",
+        target.table_name, target.time_column
+    ))
+    .bind(&cutoff)
+    .bind(&batch_size)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let Some(max_id) = max_id else {
+        return Ok((0, 0));
+    };
+
+    let mut deleted_rows = 0;
+
+    for child_table_name in target.child_table_names {
+        let res = sqlx::query(&format!(
+            "DELETE FROM {} WHERE frame_id IN (SELECT id FROM {} WHERE id <= ? AND {} < ?)",
+            child_table_name, target.table_name, target.time_column
+        ))
+        .bind(&max_id)
+        .bind(&cutoff)
+        .execute(&mut *tx)
+        .await?;
+
+        deleted_rows += res.rows_affected() as i64;
+    }
+
+    let res = sqlx::query(&format!(
+        "DELETE FROM {} WHERE id <= ? AND {} < ?",
+        target.table_name, target.time_column
+    ))
+    .bind(&max_id)
+    .bind(&cutoff)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let deleted = res.rows_affected() as i64;
+
+    Ok((deleted, deleted_rows + deleted))
+}
+
+// deletes the rows older than the retention of their table and gives the
+// freed pages back to the file system
+pub async fn prune_expired_rows(
+    conn: &SQLConnection,
+    config: &RetentionConfig,
+    now: i64,
+) -> Result<PruneReport, PruneError> {
+    if !config.enabled {
+        return Err(PruneError::Disabled);
+    }
+
+    let _guard = PRUNE_LOCK.lock().await;
+    let started = Instant::now();
+
+    let size_before = get_db_size(conn).await?;
+    let batch_size = config.batch_size.max(1);
+    let mut tables = vec![];
+
+    for target in RETENTION_TARGETS {
+        let Some(retention_days) =
+            config.get_retention_days(target.table_name, target.default_days)
+        else {
+            continue;
+        };
+
+        let mut cutoff = now - retention_days * MILLIS_PER_DAY;
+        if let Some(rollup_table_name) = target.rollup_table_name {
+            let rolled_up_to = fetch_rolled_up_to(conn, rollup_table_name).await?;
+            cutoff = cutoff.min(rolled_up_to.unwrap_or(i64::MIN));
+        }
+        let mut deleted_rows = 0;
+
+        // every batch is its own transaction, so the frame writer can
+        // get the connection in between
+        loop {
+            let (deleted, rows) = delete_expired_batch(conn, target, cutoff, batch_size).await?;
+            deleted_rows += rows;
+
+            if deleted < batch_size {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        if deleted_rows > 0 {
+            debug!(
+                "deleted {} rows of {} older than {} days",
+                deleted_rows, target.table_name, retention_days
+            );
+        }
+
+        tables.push(PrunedTable {
+            table_name: target.table_name.to_string(),
+            retention_days,
+            deleted_rows,
+        });
+    }
+
+    // a no-op when the database isn't in incremental mode
+    sqlx::query("PRAGMA incremental_vacuum")
+        .execute(conn)
+        .await?;
+
+    let size_after = get_db_size(conn).await?;
+
+    Ok(PruneReport {
+        tables,
+        size_before,
+        size_after,
+        reclaimed_bytes: size_before - size_after,
+        duration_ms: started.elapsed().as_millis() as i64,
+        pruned_at: now,
+    })
+}
+
+// prunes the database the server uses, from the background task, the cli or the api
+pub async fn prune_db(config: &RetentionConfig) -> Result<PruneReport, PruneError> {
+    let conn = get_default_sql_connection().await?;
+
+    prune_expired_rows(&conn, config, Utc::now().timestamp_millis()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::migrations::run_migrations;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::collections::HashMap;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    async fn count_rows(conn: &SQLConnection, table_name: &str) -> i64 {
+        sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table_name))
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn get_retention_days_test() {
+        let config = RetentionConfig {
+            days: HashMap::from([
+                ("cpu_status_frame".to_string(), 30),
+                ("notification_logs".to_string(), 0),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.get_retention_days("cpu_status_frame", Some(7)),
+            Some(30)
+        );
+        assert_eq!(
+            config.get_retention_days("mem_status_frame", Some(7)),
+            Some(7)
+        );
+        assert_eq!(
+            config.get_retention_days("notification_logs", Some(90)),
+            None
+        );
+        assert_eq!(config.get_retention_days("boot_events", None), None);
+    }
+
+    #[tokio::test]
+    async fn prune_expired_rows_test() {
+        let conn = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        run_migrations(&conn).await.unwrap();
+
+        // a frame every day for the last 10 days, with 2 cores each
+        for day in 0..10 {
+            let frame_id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO cpu_status_frame (last_check) VALUES (?) RETURNING id",
+            )
+            .bind(NOW - day * MILLIS_PER_DAY - 1)
+            .fetch_one(&conn)
+            .await
+            .unwrap();
+
+            for cpu_id in ["0", "1"] {
+                sqlx::query(
+                    "INSERT INTO cpu_status_frame_core (frame_id, cpu_id, freq, usage) VALUES (?, ?, 0, 0)",
+                )
+                .bind(&frame_id)
+                .bind(&cpu_id)
+                .execute(&conn)
+                .await
+                .unwrap();
+            }
+        }
+
+        let config = RetentionConfig {
+            batch_size: 2,
+            ..Default::default()
+        };
+
+        // the frames that aren't rolled up yet are kept
+        let report = prune_expired_rows(&conn, &config, NOW).await.unwrap();
+        assert_eq!(report.tables[0].deleted_rows, 0);
+        assert_eq!(count_rows(&conn, "cpu_status_frame").await, 10);
+
+        let set_rolled_up_to = |rolled_up_to: i64| {
+            sqlx::query(
+                "INSERT OR REPLACE INTO status_rollup_state (table_name, rolled_up_to) VALUES ('cpu_status_rollup_1h', ?)",
+            )
+            .bind(rolled_up_to)
+            .execute(&conn)
+        };
+
+        // only the frames of the days 8 and 9 are rolled up
+        set_rolled_up_to(NOW - 8 * MILLIS_PER_DAY).await.unwrap();
+        let report = prune_expired_rows(&conn, &config, NOW).await.unwrap();
+        assert_eq!(report.tables[0].deleted_rows, 6);
+        assert_eq!(count_rows(&conn, "cpu_status_frame").await, 8);
+
+        set_rolled_up_to(NOW).await.unwrap();
+        let report = prune_expired_rows(&conn, &config, NOW).await.unwrap();
+
+        // the frame of the day 7 is older than 7 days too
+        let cpu = report
+            .tables
+            .iter()
+            .find(|t| t.table_name == "cpu_status_frame")
+            .unwrap();
+        assert_eq!(cpu.retention_days, 7);
+        assert_eq!(cpu.deleted_rows, 3);
+        assert_eq!(count_rows(&conn, "cpu_status_frame").await, 7);
+        assert_eq!(count_rows(&conn, "cpu_status_frame_core").await, 14);
+
+        assert!(!report.tables.iter().any(|t| t.table_name == "boot_events"));
+
+        // nothing is deleted while the retention is disabled, even on request
+        let config = RetentionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            prune_expired_rows(&conn, &config, NOW + 30 * MILLIS_PER_DAY).await,
+            Err(PruneError::Disabled)
+        ));
+        assert_eq!(count_rows(&conn, "cpu_status_frame").await, 7);
+    }
+}
@@ -0,0 +1,121 @@
+-- the database of the last version before the versioned migrations, as its
+-- init_db created it, with a few rows in it
+
+CREATE TABLE configs (
+        id INTEGER PRIMARY KEY NOT NULL,
+        device_id TEXT NOT NULL,
+        cpu_threshold REAL NOT NULL,
+        mem_threshold REAL NOT NULL,
+        disk_threshold REAL NOT NULL,
+        fcm_token TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+
+CREATE TABLE cpu_infos (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        cpu_id TEXT NOT NULL,
+        core_count INTEGER NOT NULL,
+        vendor_id TEXT NOT NULL,
+        brand TEXT NOT NULL,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE disk_infos (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        disk_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        fs_type TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        is_removable INTEGER NOT NULL,
+        mount_point TEXT NOT NULL,
+        total_space INTEGER NOT NULL,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE mem_infos (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mem_id TEXT NOT NULL,
+        total_space INTEGER NOT NULL,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE cpu_status_frame (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE cpu_status_frame_core (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        cpu_id TEXT NOT NULL,
+        freq INTEGER NOT NULL,
+        usage INTEGER NOT NULL,
+        frame_id INTEGER NOT NULL,
+        FOREIGN KEY (frame_id)
+            REFERENCES cpu_status_frame (id)
+    );
+
+CREATE TABLE disk_status_frame (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE disk_status_frame_single (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        frame_id INTEGER NOT NULL,
+        available INTEGER NOT NULL,
+        disk_id TEXT NOT NULL,
+        FOREIGN KEY (frame_id)
+            REFERENCES disk_status_frame (id)
+    );
+
+CREATE TABLE mem_status_frame (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        last_check INTEGER NOT NULL
+    );
+
+CREATE TABLE mem_status_frame_single (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mem_id TEXT NOT NULL,
+        available INTEGER NOT NULL,
+        frame_id INTEGER NOT NULL,
+        FOREIGN KEY (frame_id)
+            REFERENCES mem_status_frame (id)
+    );
+
+CREATE TABLE notification_logs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        notification_type INTEGER NOT NULL,
+        device_id TEXT NOT NULL,
+        fcm_token TEXT NOT NULL,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL,
+        sent_at INTEGER NOT NULL
+    );
+
+INSERT INTO configs (id, device_id, cpu_threshold, mem_threshold, disk_threshold, fcm_token, updated_at)
+VALUES (1, 'device', 90.0, 90.0, 90.0, 'token', 1700000000000);
+
+INSERT INTO cpu_infos (cpu_id, core_count, vendor_id, brand, last_check)
+VALUES ('cpu', 8, 'GenuineIntel', 'Intel(R) Xeon(R) CPU', 1700000010000);
+
+-- the usb stick was unplugged a day before the upgrade, and the root disk
+-- got its id from every one of its fields back then
+INSERT INTO disk_infos (disk_id, name, fs_type, kind, is_removable, mount_point, total_space, last_check)
+VALUES ('sda1ext4SSDno/250059350016', 'sda1', 'ext4', 'SSD', 0, '/', 250059350016, 1700000010000),
+    ('sdb1vfatHDDyes/media/usb16008609792', 'sdb1', 'vfat', 'HDD', 1, '/media/usb', 16008609792, 1699913600000);
+
+INSERT INTO mem_infos (mem_id, total_space, last_check) VALUES ('1', 17179869184, 1700000010000);
+
+INSERT INTO cpu_status_frame (id, last_check) VALUES (1, 1700000000000), (2, 1700000010000);
+INSERT INTO cpu_status_frame_core (cpu_id, freq, usage, frame_id)
+VALUES ('cpu', 2400, 20, 1), ('cpu', 2400, 30, 1), ('cpu', 2400, 95, 2), ('cpu', 2400, 90, 2);
+
+INSERT INTO disk_status_frame (id, last_check) VALUES (1, 1700000010000);
+INSERT INTO disk_status_frame_single (frame_id, available, disk_id)
+VALUES (1, 125029675008, 'sda1ext4SSDno/250059350016');
+
+INSERT INTO mem_status_frame (id, last_check) VALUES (1, 1700000000000);
+INSERT INTO mem_status_frame_single (mem_id, available, frame_id) VALUES ('1', 8589934592, 1);
+
+INSERT INTO notification_logs (notification_type, device_id, fcm_token, title, body, sent_at)
+VALUES ('StatusLimitsExceeding', 'device', 'token', 'title', 'body', 1700000010000);